@@ -0,0 +1,96 @@
+-- companion plugin for `rbxlx-mesh-fixer serve`. sends every MeshPart in the
+-- open place to the tool and applies the merge plan it answers with
+local AssetService = game:GetService("AssetService")
+local ChangeHistoryService = game:GetService("ChangeHistoryService")
+local HttpService = game:GetService("HttpService")
+
+local URL = "http://127.0.0.1:34873"
+local SEARCHED_SERVICES = { "Workspace", "ReplicatedStorage", "ServerStorage", "StarterPack", "StarterPlayer" }
+
+-- the same "/" separated path the tool uses, without the DataModel
+local function instancePath(instance)
+	local names = {}
+	while instance and instance ~= game do
+		table.insert(names, 1, instance.Name)
+		instance = instance.Parent
+	end
+	return table.concat(names, "/")
+end
+
+local function collectParts()
+	local parts, byId = {}, {}
+	for _, serviceName in ipairs(SEARCHED_SERVICES) do
+		local service = game:FindFirstChild(serviceName)
+		if service then
+			for _, instance in ipairs(service:GetDescendants()) do
+				if instance:IsA("MeshPart") then
+					local id = tostring(#parts + 1)
+					byId[id] = instance
+					local size, initialSize = instance.Size, instance.MeshSize
+					table.insert(parts, {
+						id = id,
+						path = instancePath(instance),
+						meshId = instance.MeshId,
+						textureId = instance.TextureID,
+						size = { size.X, size.Y, size.Z },
+						initialSize = { initialSize.X, initialSize.Y, initialSize.Z },
+						cframe = { instance.CFrame:GetComponents() },
+					})
+				end
+			end
+		end
+	end
+	return parts, byId
+end
+
+local function toValue(value)
+	if value.type == "Content" then
+		return value.value
+	elseif value.type == "Vector3" then
+		return Vector3.new(table.unpack(value.value))
+	else
+		return CFrame.new(table.unpack(value.value))
+	end
+end
+
+local meshes = {}
+local function applyMesh(instance, meshId)
+	local source = meshes[meshId]
+	if source == nil then
+		source = AssetService:CreateMeshPartAsync(meshId)
+		meshes[meshId] = source
+	end
+	instance:ApplyMesh(source)
+end
+
+local function run()
+	local parts, byId = collectParts()
+	local ok, response = pcall(function()
+		return HttpService:PostAsync(URL .. "/plan", HttpService:JSONEncode({ parts = parts }))
+	end)
+	if not ok then
+		warn("rbxlx-mesh-fixer: is `rbxlx-mesh-fixer serve` running? " .. tostring(response))
+		return
+	end
+
+	local plan = HttpService:JSONDecode(response)
+	ChangeHistoryService:SetWaypoint("Before rbxlx-mesh-fixer")
+	local applied = 0
+	for _, change in ipairs(plan.changes) do
+		local instance = byId[change.id]
+		-- InitialSize is read only, ApplyMesh sets it along with the mesh
+		if instance and change.property == "MeshId" then
+			applyMesh(instance, change.value.value)
+			applied += 1
+		elseif instance and (change.property == "Size" or change.property == "CFrame") then
+			instance[change.property] = toValue(change.value)
+			applied += 1
+		end
+	end
+	ChangeHistoryService:SetWaypoint("After rbxlx-mesh-fixer")
+	print(string.format("rbxlx-mesh-fixer: applied %d changes across %d clusters", applied, plan.clusters))
+end
+
+local toolbar = plugin:CreateToolbar("Mesh Fixer")
+local button = toolbar:CreateButton("Merge meshes", "Deduplicate MeshParts through rbxlx-mesh-fixer serve", "")
+button.Click:Connect(run)
@@ -0,0 +1,122 @@
+use rbx_dom_weak::{
+    types::{Content, Ref, Variant},
+    InstanceBuilder, WeakDom,
+};
+use rbx_types::{CFrame, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    dedupe::MeshDeduplicator,
+    place::Place,
+    utils::{cframe::CFrameExt, journal::JournalValue, FixerError},
+};
+
+// a MeshPart as the studio plugin sends it. `id` is whatever the plugin uses
+// to find the part again, it comes back on every change to that part
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgePart {
+    pub id: String,
+    pub path: String,
+    pub mesh_id: String,
+    #[serde(default)]
+    pub texture_id: String,
+    pub size: [f32; 3],
+    pub initial_size: Option<[f32; 3]>,
+    pub cframe: [f32; 12],
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlanRequest {
+    pub parts: Vec<BridgePart>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanChange {
+    pub id: String,
+    pub path: String,
+    pub property: String,
+    pub value: JournalValue,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanResponse {
+    pub changes: Vec<PlanChange>,
+    pub clusters: usize,
+    pub remapped: usize,
+    pub failed_assets: Vec<String>,
+}
+
+fn vector3(v: [f32; 3]) -> Vector3 {
+    Vector3::new(v[0], v[1], v[2])
+}
+
+// rebuilds just enough of the plugin's place for a dedupe run: the parts at
+// their paths, with folders standing in for everything above them
+fn build_place(parts: &[BridgePart]) -> (Place, HashMap<String, String>) {
+    let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+    let mut folders = HashMap::<String, Ref>::new();
+    let mut ids = HashMap::<String, String>::new();
+
+    for part in parts {
+        let mut parent = dom.root_ref();
+        let mut names = part.path.split('/').collect::<Vec<_>>();
+        let name = names.pop().unwrap_or_default();
+        let mut path = String::new();
+        for folder in names {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(folder);
+            parent = *folders.entry(path.clone()).or_insert_with(|| {
+                dom.insert(parent, InstanceBuilder::new("Folder").with_name(folder))
+            });
+        }
+
+        let mut builder = InstanceBuilder::new("MeshPart")
+            .with_name(name)
+            .with_property("MeshId", Content::from(part.mesh_id.clone()))
+            .with_property("TextureID", Content::from(part.texture_id.clone()))
+            .with_property("Size", vector3(part.size))
+            .with_property("CFrame", CFrame::from_components(&part.cframe));
+        if let Some(initial_size) = part.initial_size {
+            builder = builder.with_property("InitialSize", Variant::Vector3(vector3(initial_size)));
+        }
+        let referent = dom.insert(parent, builder);
+        ids.insert(format!("{:?}", referent), part.id.clone());
+    }
+
+    (Place::from_dom(dom), ids)
+}
+
+// runs the same dedupe a place file would get and hands back every property
+// it rewrote, for the plugin to apply to the live instances
+pub async fn plan_merge(
+    deduplicator: &MeshDeduplicator,
+    request: &PlanRequest,
+) -> Result<PlanResponse, FixerError> {
+    let (mut place, ids) = build_place(&request.parts);
+    let result = deduplicator.run(&mut place).await?;
+
+    let changes = result
+        .journal
+        .entries
+        .into_iter()
+        .filter_map(|x| {
+            Some(PlanChange {
+                id: ids.get(&x.referent)?.clone(),
+                path: x.path,
+                property: x.property,
+                value: x.new_value,
+            })
+        })
+        .collect();
+
+    Ok(PlanResponse {
+        changes,
+        clusters: result.report.clusters.iter().filter(|x| x.applied).count(),
+        remapped: result.mapping.len(),
+        failed_assets: result.failures.into_iter().map(|x| x.asset_id).collect(),
+    })
+}
@@ -0,0 +1,344 @@
+use glam::{Affine3A, Mat3, Vec3};
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    Instance,
+};
+use rbx_types::{CFrame, Matrix3, Vector3};
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+use crate::{
+    place::{filter_mesh_parts, Place},
+    utils::{
+        asset_provider::AssetProvider,
+        content_url::normalize_content,
+        fidelity::PHYSICS_PROPERTIES,
+        math::{affine, vec3, vector3},
+        mesh_content::{get_mesh_id, set_mesh_id},
+        mesh_reader::{RobloxMesh, RobloxMeshVertex},
+        open_cloud::OpenCloudClient,
+        path_filter::PathFilter,
+        reflection::property_or_default,
+        FixerError,
+    },
+};
+
+// the most triangles a single mesh upload is allowed to have
+const MAX_COMBINED_TRIANGLES: usize = 20_000;
+
+// everything that has to match for two parts to render as one. anything
+// left out here would be taken from whichever part ends up hosting the mesh
+const SHARED_PROPERTIES: [&str; 13] = [
+    "TextureID",
+    "Material",
+    "MaterialVariant",
+    "Color3uint8",
+    "Transparency",
+    "Reflectance",
+    "CastShadow",
+    "CanCollide",
+    "CanQuery",
+    "CanTouch",
+    "CollisionGroup",
+    "DoubleSided",
+    "RenderFidelity",
+];
+
+#[derive(Debug, Clone)]
+pub struct CombineOptions {
+    // parts with any side longer than this are left alone
+    pub max_part_size: f32,
+    // groups never reach across cells of this size, so a combined mesh
+    // stays small enough to be culled on its own
+    pub cell_size: f32,
+    pub path_filter: PathFilter,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CombineResult {
+    pub meshes: usize,
+    pub parts_combined: usize,
+    pub parts_removed: usize,
+    pub triangles: usize,
+    pub failed_groups: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GroupKey {
+    parent: Ref,
+    cell: [i32; 3],
+    properties: Vec<String>,
+}
+
+struct CombinePart {
+    referent: Ref,
+    mesh_id: String,
+    cframe: CFrame,
+    size: Vector3,
+}
+
+fn get_vector3(instance: &Instance, name: &str) -> Option<Vector3> {
+    match property_or_default(instance, name) {
+        Some(Variant::Vector3(value)) => Some(value),
+        _ => None,
+    }
+}
+
+// every part another instance points at through a ref property. welds,
+// constraints and object values would all be left dangling by a merge
+fn referenced_parts(place: &Place) -> HashSet<Ref> {
+    let mut referenced = HashSet::new();
+    for referent in place.descendants() {
+        let instance = place.dom.get_by_ref(referent).expect("ref-inst");
+        for value in instance.properties.values() {
+            if let Variant::Ref(target) = value {
+                if target.is_some() {
+                    referenced.insert(*target);
+                }
+            }
+        }
+    }
+    referenced
+}
+
+// anchored, childless and unreferenced, so nothing can tell the part apart
+// from the triangles it turns into
+fn combine_part(
+    place: &Place,
+    referenced: &HashSet<Ref>,
+    referent: Ref,
+    max_part_size: f32,
+) -> Option<CombinePart> {
+    let instance = place.dom.get_by_ref(referent)?;
+    if !instance.children().is_empty() || referenced.contains(&referent) {
+        return None;
+    }
+    if !matches!(
+        property_or_default(instance, "Anchored"),
+        Some(Variant::Bool(true))
+    ) {
+        return None;
+    }
+
+    let size = get_vector3(instance, "Size")?;
+    if vec3(size).max_element() > max_part_size {
+        return None;
+    }
+    let cframe = match property_or_default(instance, "CFrame") {
+        Some(Variant::CFrame(cframe)) => cframe,
+        _ => return None,
+    };
+    let mesh_id = normalize_content(&get_mesh_id(instance));
+    if mesh_id.trim() == "" {
+        return None;
+    }
+
+    Some(CombinePart {
+        referent,
+        mesh_id,
+        cframe,
+        size,
+    })
+}
+
+fn group_key(place: &Place, part: &CombinePart, cell_size: f32) -> GroupKey {
+    let instance = place.dom.get_by_ref(part.referent).expect("ref-inst");
+    let cell = (vec3(part.cframe.position) / cell_size).floor();
+    GroupKey {
+        parent: instance.parent(),
+        cell: [cell.x as i32, cell.y as i32, cell.z as i32],
+        properties: SHARED_PROPERTIES
+            .iter()
+            .map(|x| format!("{:?}", property_or_default(instance, x)))
+            .collect(),
+    }
+}
+
+// a mesh is drawn centered on its part and stretched to the part's size,
+// this is the transform from mesh space to world space that implies
+fn part_transform(mesh: &RobloxMesh, part: &CombinePart) -> Affine3A {
+    let min = vec3(mesh.bounding_box_size.min);
+    let max = vec3(mesh.bounding_box_size.max);
+    let extent = max - min;
+    // flat meshes keep their thickness instead of dividing by zero
+    let scale = Vec3::select(
+        extent.cmpgt(Vec3::splat(1e-6)),
+        vec3(part.size) / extent,
+        Vec3::ONE,
+    );
+    affine(&part.cframe)
+        * Affine3A::from_scale(scale)
+        * Affine3A::from_translation(-(min + max) / 2.0)
+}
+
+// bakes every part into one world space vertex buffer, only the full detail
+// faces are kept
+fn bake_parts(
+    parts: &[CombinePart],
+    meshes: &HashMap<String, RobloxMesh>,
+) -> (Vec<RobloxMeshVertex>, Vec<[i32; 3]>) {
+    let mut vertices = Vec::<RobloxMeshVertex>::new();
+    let mut faces = Vec::<[i32; 3]>::new();
+
+    for part in parts {
+        let mesh = &meshes[&part.mesh_id];
+        let transform = part_transform(mesh, part);
+        let linear = Mat3::from(transform.matrix3);
+        let normal_matrix = linear.inverse().transpose();
+        // a mirrored transform turns the faces inside out
+        let flip = linear.determinant() < 0.0;
+
+        let offset = vertices.len() as i32;
+        vertices.extend(mesh.vertices.iter().map(|x| RobloxMeshVertex {
+            position: vector3(transform.transform_point3(vec3(x.position))),
+            normal: vector3((normal_matrix * vec3(x.normal)).normalize_or_zero()),
+            ..x.clone()
+        }));
+        faces.extend(mesh.lod0_faces().iter().map(|x| {
+            if flip {
+                [x[0] + offset, x[2] + offset, x[1] + offset]
+            } else {
+                [x[0] + offset, x[1] + offset, x[2] + offset]
+            }
+        }));
+    }
+
+    (vertices, faces)
+}
+
+// splits a group into runs that each fit in one upload
+fn split_by_budget(
+    parts: Vec<CombinePart>,
+    meshes: &HashMap<String, RobloxMesh>,
+) -> Vec<Vec<CombinePart>> {
+    let mut batches = vec![];
+    let mut batch = vec![];
+    let mut triangles = 0;
+    for part in parts {
+        let faces = meshes[&part.mesh_id].lod0_faces().len();
+        if !batch.is_empty() && triangles + faces > MAX_COMBINED_TRIANGLES {
+            batches.push(std::mem::take(&mut batch));
+            triangles = 0;
+        }
+        triangles += faces;
+        batch.push(part);
+    }
+    batches.push(batch);
+    batches.into_iter().filter(|x| x.len() > 1).collect()
+}
+
+// merges groups of small static mesh parts that share a look into a single
+// mesh each, uploads it and replaces the group with the first part of it
+pub async fn combine_place(
+    provider: &dyn AssetProvider,
+    uploader: &OpenCloudClient,
+    place: &mut Place,
+    options: &CombineOptions,
+) -> Result<CombineResult, FixerError> {
+    let mesh_parts =
+        filter_mesh_parts(&place.dom, place.filtered_descendants(&options.path_filter));
+    let referenced = referenced_parts(place);
+
+    let mut keys = HashMap::<GroupKey, usize>::new();
+    let mut groups = Vec::<Vec<CombinePart>>::new();
+    for referent in mesh_parts {
+        let part = match combine_part(place, &referenced, referent, options.max_part_size) {
+            Some(part) => part,
+            None => continue,
+        };
+        let key = group_key(place, &part, options.cell_size);
+        let index = *keys.entry(key).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[index].push(part);
+    }
+
+    let mut result = CombineResult::default();
+    let mut meshes = HashMap::<String, RobloxMesh>::new();
+    for group in groups.into_iter().filter(|x| x.len() > 1) {
+        // skinned meshes deform with their bones and can't be baked
+        let mut usable = vec![];
+        for part in group {
+            if !meshes.contains_key(&part.mesh_id) {
+                match RobloxMesh::from_provider(provider, part.mesh_id.clone()).await {
+                    Ok(mesh) => {
+                        meshes.insert(part.mesh_id.clone(), mesh);
+                    }
+                    Err(err) => {
+                        warn!("Skipping {:?}: {}", part.mesh_id, err);
+                        continue;
+                    }
+                }
+            }
+            if meshes[&part.mesh_id].header.num_bones == 0 {
+                usable.push(part);
+            }
+        }
+
+        for batch in split_by_budget(usable, &meshes) {
+            let (vertices, faces) = bake_parts(&batch, &meshes);
+            let triangles = faces.len();
+            let mut mesh = RobloxMesh::from_geometry(vertices, faces)?;
+
+            // the combined part sits unrotated at the center of the bounds,
+            // the mesh is moved so its own center lands there
+            let min = vec3(mesh.bounding_box_size.min);
+            let max = vec3(mesh.bounding_box_size.max);
+            let center = (min + max) / 2.0;
+            for vertex in mesh.vertices.iter_mut() {
+                vertex.position = vector3(vec3(vertex.position) - center);
+            }
+            mesh.recalculate();
+
+            let host = batch[0].referent;
+            let name = format!(
+                "{} (combined {})",
+                place.dom.get_by_ref(host).expect("ref-inst").name,
+                batch.len()
+            );
+            let new_id = match uploader.upload_mesh(&name, mesh.to_bytes()?).await {
+                Ok(new_id) => new_id,
+                Err(err) => {
+                    warn!("Could not upload {:?}: {}", name, err);
+                    result.failed_groups += 1;
+                    continue;
+                }
+            };
+            info!(
+                "Combined {:?} parts into {:?} ({:?} triangles)",
+                batch.len(),
+                new_id,
+                triangles
+            );
+
+            let instance = place.dom.get_by_ref_mut(host).expect("ref-inst");
+            set_mesh_id(instance, &new_id);
+            let size = vector3(max - min);
+            instance.properties.insert(
+                String::from("CFrame"),
+                Variant::CFrame(CFrame::new(vector3(center), Matrix3::identity())),
+            );
+            instance
+                .properties
+                .insert(String::from("Size"), Variant::Vector3(size));
+            instance
+                .properties
+                .insert(String::from("InitialSize"), Variant::Vector3(size));
+            for property in PHYSICS_PROPERTIES.iter() {
+                instance.properties.remove(*property);
+            }
+
+            for part in batch.iter().skip(1) {
+                place.dom.destroy(part.referent);
+            }
+
+            result.meshes += 1;
+            result.parts_combined += batch.len();
+            result.parts_removed += batch.len() - 1;
+            result.triangles += triangles;
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use rbxlx_mesh_fixer::FixerError;
+
+pub const DEFAULT_CONFIG_PATH: &str = "rbxl-mesh-fixer.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FilterConfig {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub tags: Vec<String>,
+    pub skip_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AuthConfig {
+    pub api_key: Option<String>,
+    pub remote_cache_token: Option<String>,
+    pub upload_user: Option<u64>,
+    pub upload_group: Option<u64>,
+}
+
+// a `[[policy]]` table, the filters work like the ones under `[filters]`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PolicyConfig {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub tags: Vec<String>,
+    pub skip_tags: Vec<String>,
+    pub render_fidelity: Option<String>,
+    pub collision_fidelity: Option<String>,
+    pub double_sided: Option<bool>,
+}
+
+// every key mirrors the command line flag of the same name, flags passed on
+// the command line win over the file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub content_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub jobs: Option<usize>,
+    pub remote_cache: Option<String>,
+    pub asset_delivery_url: Option<String>,
+    pub database: Option<String>,
+    pub canonical: Option<String>,
+    pub prefer_owner: Option<String>,
+    pub compensate_attachments: Option<bool>,
+    pub stamp_attributes: Option<bool>,
+    pub weld: Option<bool>,
+    pub uv_signature: Option<bool>,
+    pub fail_fast: Option<bool>,
+    pub offline: Option<bool>,
+    pub revalidate: Option<bool>,
+    pub rate_limit: Option<f64>,
+    pub deadline: Option<u64>,
+    pub min_duplicates: Option<usize>,
+    pub min_confidence: Option<f32>,
+    pub scope: Option<String>,
+    pub fuzzy: Option<f32>,
+    pub fuzzy_metric: Option<String>,
+    pub min_triangles: Option<i32>,
+    pub ignore: Vec<String>,
+    pub merge: Vec<Vec<String>>,
+    pub log_level: Option<String>,
+    pub filters: FilterConfig,
+    pub policy: Vec<PolicyConfig>,
+    pub auth: AuthConfig,
+}
+
+impl ConfigFile {
+    pub fn read(path: &str) -> Result<Self, FixerError> {
+        let data = std::fs::read_to_string(path)?;
+        toml::from_str(&data).map_err(|err| format!("{}: {}", path, err).into())
+    }
+
+    // an explicit --config has to exist, the default one is optional
+    pub fn load(path: Option<&str>) -> Result<Self, FixerError> {
+        match path {
+            Some(path) => ConfigFile::read(path),
+            None if Path::new(DEFAULT_CONFIG_PATH).is_file() => {
+                ConfigFile::read(DEFAULT_CONFIG_PATH)
+            }
+            None => Ok(ConfigFile::default()),
+        }
+    }
+}
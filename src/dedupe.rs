@@ -0,0 +1,1348 @@
+use glam::{Quat, Vec3};
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    Instance, WeakDom,
+};
+use rbx_types::{CFrame, Vector3};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Cursor,
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use crate::{
+    place::{filter_mesh_parts, has_wrap_instance, Place},
+    utils::{
+        asset_downloader::{is_local_asset, AssetDownloader},
+        asset_provider::AssetProvider,
+        attributes::{stamp_original, ORIGINAL_MESH_ID},
+        canonical::{select_canonical, select_owned_canonical, CanonicalPolicy, PreferredOwner},
+        cframe::CFrameExt,
+        cleanup::{cleanup_mesh, DEFAULT_WELD_EPSILON},
+        confidence::MatchKind,
+        content_url::{normalize_content, parse_content_url, ContentUrl},
+        digest_bytes, instance_path,
+        joints::{compensate_attachments, JointGraph},
+        journal::{ChangeJournal, JournalSnapshot},
+        mapping::AssetMapping,
+        math::{vec3, vector3},
+        mesh_content::{get_mesh_id, set_mesh_id},
+        mesh_reader::{MeshError, RobloxMesh},
+        open_cloud::OpenCloudClient,
+        path_filter::PathFilter,
+        progress::Progress,
+        reflection::{is_a, property_or_default},
+        report::{AffectedInstance, ClusterReport, DedupeReport},
+        shutdown::interrupted,
+        signature_cache::{SignatureCache, SignatureRecord},
+        signature_db::{CanonicalRecord, SignatureDatabase},
+        similarity::{FuzzyMatch, SignatureFeatures},
+        surface_appearance::{dedupe_surface_appearances, find_surface_appearances, MapSavings},
+        tags::TagFilter,
+        timings::Timings,
+        union_operation::{dedupe_unions, filter_unions, UnionCluster},
+        FixerError,
+    },
+};
+
+fn property_error(instance: &Instance, name: &str, value: Option<Variant>) -> FixerError {
+    let message = match value {
+        Some(value) => format!("expected another type than {:?}", value.ty()),
+        None => format!("{} has no default for it", instance.class),
+    };
+    FixerError::Property {
+        instance: instance.name.clone(),
+        property: name.to_string(),
+        message,
+    }
+}
+
+macro_rules! get_size {
+    ($instance:expr, $name:expr) => {
+        match property_or_default($instance, $name) {
+            Some(Variant::Vector3(prop)) => Ok(prop),
+            other => Err(property_error($instance, $name, other)),
+        }
+    };
+}
+
+macro_rules! get_cframe {
+    ($instance:expr) => {
+        match property_or_default($instance, "CFrame") {
+            Some(Variant::CFrame(prop)) => Ok(prop),
+            other => Err(property_error($instance, "CFrame", other)),
+        }
+    };
+}
+
+// parts that were never rescaled don't always carry an InitialSize, their
+// size is the one the mesh was imported at
+fn get_initial_size(instance: &Instance) -> Result<Vector3, FixerError> {
+    match property_or_default(instance, "InitialSize") {
+        Some(Variant::Vector3(prop)) => Ok(prop),
+        _ => get_size!(instance, "Size"),
+    }
+}
+
+// offsets shorter than this are float noise from the bounds, not a real
+// difference in origin
+const ORIGIN_EPSILON: f32 = 1e-3;
+// relative difference in mesh extent before two meshes count as scaled
+// copies rather than the same size
+const SCALE_EPSILON: f32 = 0.01;
+
+// how far the canonical mesh has to be moved inside the part, after it has
+// been turned by `yaw`, so its bounds land where the old mesh's did. both
+// meshes are placed by their origin, which uploads of the same geometry
+// don't always agree on
+fn origin_offset(
+    old: &RobloxMesh,
+    old_scale: Vec3,
+    new: &RobloxMesh,
+    new_scale: Vec3,
+    yaw: f32,
+) -> Vec3 {
+    let old_center = vec3(old.bounds_center()) * old_scale;
+    let new_center = vec3(new.bounds_center()) * new_scale;
+    Quat::from_rotation_y(-yaw) * old_center - new_center
+}
+
+// measured along the mesh's own axes, so a copy that was only turned still
+// has the same extent
+fn mesh_extent(mesh: &RobloxMesh) -> Vec3 {
+    vec3(mesh.oriented_bounding_box.half_size) * 2.0
+}
+
+fn size_scale(size: Vector3, init_size: Vector3) -> Vec3 {
+    vec3(size) / vec3(init_size).max(Vec3::splat(1e-6))
+}
+
+// properties still at their default may not be there yet, setting them adds
+// them back
+macro_rules! modify_property {
+    ($props:expr, $prop:expr, $value:expr) => {
+        $props.insert(String::from($prop), $value);
+    };
+}
+
+// meshes whose signature came out of the cache only carry their hash until
+// their cluster turns out to have something to merge
+struct CachedMesh {
+    cframe: CFrame,
+    mesh: Option<RobloxMesh>,
+    asset_id: String,
+    init_size: Vector3,
+    size: Vector3,
+}
+
+impl CachedMesh {
+    fn mesh(&self) -> &RobloxMesh {
+        self.mesh.as_ref().expect("candidate-mesh")
+    }
+}
+
+struct ParsedMesh {
+    hash: i32,
+    uv_hash: u32,
+    triangles: i32,
+    features: SignatureFeatures,
+    mesh: Option<RobloxMesh>,
+}
+
+impl ParsedMesh {
+    // meshes with the same shape but a different unwrap can't share a
+    // texture, folding the uvs in keeps them in separate clusters
+    fn cluster_key(&self, uv_signature: bool) -> i32 {
+        match uv_signature {
+            true => self.hash ^ self.uv_hash as i32,
+            false => self.hash,
+        }
+    }
+}
+
+struct MeshInstance {
+    referent: Ref,
+    asset_id: String,
+    wrapped: bool,
+}
+
+#[derive(Default)]
+struct MeshCluster {
+    scope: String,
+    features: Option<SignatureFeatures>,
+    candidates: Vec<CachedMesh>,
+    instances: Vec<MeshInstance>,
+}
+
+// how many downloaded meshes are handed to the rayon pool at once
+const PARSE_BATCH_SIZE: usize = 64;
+
+// errors have to cross the rayon pool, so only what the caller looks at is
+// kept of them
+struct ParseFailure {
+    message: String,
+    unsupported_version: bool,
+}
+
+impl From<FixerError> for ParseFailure {
+    fn from(err: FixerError) -> Self {
+        ParseFailure {
+            unsupported_version: MeshError::is_unsupported_version(&err),
+            message: err.to_string(),
+        }
+    }
+}
+
+// welding first lets meshes that only differ by split vertices or sliver
+// triangles land in the same cluster
+fn prepare_mesh(mut mesh: RobloxMesh, weld: bool) -> RobloxMesh {
+    if weld {
+        let stats = cleanup_mesh(&mut mesh, DEFAULT_WELD_EPSILON);
+        debug!(
+            "Welded {:?} vertices and dropped {:?} faces",
+            stats.welded_vertices, stats.degenerate_faces
+        );
+    }
+
+    debug!(
+        "num_meshes={:?}, num_verts={:?}, num_faces={:?}, num_lod={:?}, num_bones={:?}, nts={:?}, nsd={:?}, stub={:?}, triangles={:?}, hash={:?}",
+        mesh.header.num_meshes, mesh.header.num_verts, mesh.header.num_faces, mesh.header.num_lods, mesh.header.num_bones, mesh.header.name_table_size, mesh.header.num_skin_data, mesh.header.stub,
+        mesh.triangles, mesh.hash
+    );
+    debug!("bounding_box={:#?}", mesh.bounding_box);
+    mesh
+}
+
+// only the signature is read here, cached or streamed, the geometry is read
+// later if the mesh ends up in a cluster with something to merge
+fn parse_mesh(
+    mut data: Cursor<Vec<u8>>,
+    cache: Option<&SignatureCache>,
+    weld: bool,
+) -> Result<ParsedMesh, ParseFailure> {
+    let key = cache.map(|_| SignatureCache::key(data.get_ref(), weld));
+    if let (Some(cache), Some(key)) = (cache, &key) {
+        if let Some(record) = cache.get(key) {
+            return Ok(ParsedMesh {
+                hash: record.hash,
+                uv_hash: record.uv_hash,
+                triangles: record.triangles,
+                features: SignatureFeatures::from_record(&record),
+                mesh: None,
+            });
+        }
+    }
+
+    // welding moves vertices around, so only then does the hash need the
+    // whole mesh up front
+    let (signature, mesh) = match weld {
+        true => {
+            let mesh = prepare_mesh(RobloxMesh::from_cursor(&mut data)?, weld);
+            (mesh.signature(), Some(mesh))
+        }
+        false => (RobloxMesh::read_signature(&mut data)?, None),
+    };
+    let record = SignatureRecord::from_signature(&signature);
+    let features = SignatureFeatures::from_record(&record);
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.insert(key, record);
+    }
+    Ok(ParsedMesh {
+        hash: signature.hash,
+        uv_hash: signature.uv_hash,
+        triangles: signature.triangles,
+        features,
+        mesh,
+    })
+}
+
+// clusters holding assets the user declared equivalent are folded into the
+// first of them, whatever their hashes say. forced merges still stay inside
+// their scope
+fn merge_forced_clusters(clusters: &mut BTreeMap<i32, MeshCluster>, groups: &[Vec<String>]) {
+    for group in groups {
+        let mut scopes = BTreeMap::<String, Vec<i32>>::new();
+        for (key, cluster) in clusters.iter() {
+            if cluster
+                .candidates
+                .iter()
+                .any(|x| group.contains(&x.asset_id))
+            {
+                scopes.entry(cluster.scope.clone()).or_default().push(*key);
+            }
+        }
+
+        for keys in scopes.values() {
+            if keys.len() < 2 {
+                continue;
+            }
+
+            let mut merged = clusters.remove(&keys[0]).expect("merge-cluster");
+            for key in keys[1..].iter() {
+                let cluster = clusters.remove(key).expect("merge-cluster");
+                merged.candidates.extend(cluster.candidates);
+                merged.instances.extend(cluster.instances);
+            }
+            debug!("Force merged {:?} clusters for {:?}", keys.len(), group);
+            clusters.insert(keys[0], merged);
+        }
+    }
+}
+
+// plans the merges inside one bucket, every cluster in it already shares a
+// scope and triangle count. sorted by mesh volume, a cluster only has to look
+// back at roots whose volume is close enough that they could still match
+fn plan_fuzzy_bucket(
+    mut bucket: Vec<(i32, SignatureFeatures)>,
+    fuzzy: &FuzzyMatch,
+    uv_signature: bool,
+) -> Vec<(i32, i32)> {
+    bucket.sort_by(|a, b| {
+        a.1.volume()
+            .partial_cmp(&b.1.volume())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+
+    let mut roots = Vec::<(i32, SignatureFeatures)>::new();
+    let mut merges = vec![];
+    for (key, features) in bucket {
+        let window = fuzzy.volume_window(features.volume());
+        let root = roots
+            .iter()
+            .rev()
+            .take_while(|(_, root)| features.volume() - root.volume() <= window)
+            .find(|(_, root)| fuzzy.matches(root, &features, uv_signature))
+            .map(|(root, _)| *root);
+        match root {
+            Some(root) => merges.push((key, root)),
+            None => roots.push((key, features)),
+        }
+    }
+    merges
+}
+
+// clusters whose signatures only drifted apart are folded into a root they
+// are close to. each is compared against that root rather than its last
+// addition, so drift can't chain across a whole family. only clusters with
+// the same scope and triangle count can match, so those buckets are planned
+// independently on the rayon pool
+fn merge_fuzzy_clusters(
+    clusters: &mut BTreeMap<i32, MeshCluster>,
+    fuzzy: &FuzzyMatch,
+    uv_signature: bool,
+) {
+    let mut buckets = HashMap::<(&str, i32), Vec<(i32, SignatureFeatures)>>::new();
+    for (key, cluster) in clusters.iter() {
+        if let Some(features) = cluster.features {
+            buckets
+                .entry((cluster.scope.as_str(), features.triangles))
+                .or_default()
+                .push((*key, features));
+        }
+    }
+
+    let merges = buckets
+        .into_par_iter()
+        .flat_map_iter(|(_, bucket)| plan_fuzzy_bucket(bucket, fuzzy, uv_signature))
+        .collect::<Vec<_>>();
+
+    for (key, root) in merges.iter() {
+        let cluster = clusters.remove(key).expect("fuzzy-cluster");
+        let target = clusters.get_mut(root).expect("fuzzy-cluster");
+        target.candidates.extend(cluster.candidates);
+        target.instances.extend(cluster.instances);
+    }
+
+    if !merges.is_empty() {
+        debug!(
+            "Fuzzy matching folded {:?} clusters into others",
+            merges.len()
+        );
+    }
+}
+
+// how far a merge may reach. with a narrower scope every top-level Model or
+// Folder keeps its own canonicals and never borrows another kit's mesh
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DedupeScope {
+    Global,
+    PerModel,
+    PerFolder,
+}
+
+impl Default for DedupeScope {
+    fn default() -> Self {
+        DedupeScope::Global
+    }
+}
+
+impl FromStr for DedupeScope {
+    type Err = String;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "global" => Ok(DedupeScope::Global),
+            "per-model" => Ok(DedupeScope::PerModel),
+            "per-folder" => Ok(DedupeScope::PerFolder),
+            _ => Err(format!("Unknown dedupe scope {:?}", scope)),
+        }
+    }
+}
+
+// the path of the outermost Model or Folder above the part, parts outside
+// of any share the empty scope
+fn scope_of(dom: &WeakDom, referent: Ref, scope: DedupeScope) -> String {
+    let class = match scope {
+        DedupeScope::Global => return String::new(),
+        DedupeScope::PerModel => "Model",
+        DedupeScope::PerFolder => "Folder",
+    };
+
+    let mut outermost = None;
+    let mut current = dom
+        .get_by_ref(referent)
+        .and_then(|x| dom.get_by_ref(x.parent()));
+    while let Some(instance) = current {
+        if is_a(&instance.class, class) {
+            outermost = Some(instance.referent());
+        }
+        current = dom.get_by_ref(instance.parent());
+    }
+
+    match outermost {
+        Some(referent) => instance_path(dom, referent),
+        None => String::new(),
+    }
+}
+
+impl MeshCluster {
+    fn references(&self) -> Vec<(String, usize)> {
+        self.candidates
+            .iter()
+            .map(|candidate| {
+                let count = self
+                    .instances
+                    .iter()
+                    .filter(|x| x.asset_id == candidate.asset_id)
+                    .count();
+                (candidate.asset_id.clone(), count)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DedupeOptions {
+    pub compensate_attachments: bool,
+    pub canonical_policy: CanonicalPolicy,
+    pub preferred_owner: Option<PreferredOwner>,
+    pub stamp_attributes: bool,
+    pub database_path: Option<String>,
+    pub fail_fast: bool,
+    pub quiet: bool,
+    pub weld: bool,
+    pub uv_signature: bool,
+    pub path_filter: PathFilter,
+    pub tag_filter: TagFilter,
+    pub ignored_assets: BTreeSet<String>,
+    pub merge_groups: Vec<Vec<String>>,
+    pub min_duplicates: usize,
+    pub min_triangles: i32,
+    pub scope: DedupeScope,
+    pub fuzzy: Option<FuzzyMatch>,
+    // clusters below this are reported for review instead of rewritten
+    pub min_confidence: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DedupeResult {
+    pub report: DedupeReport,
+    pub mapping: AssetMapping,
+    pub journal: ChangeJournal,
+    pub map_savings: BTreeMap<&'static str, MapSavings>,
+    pub union_clusters: Vec<UnionCluster>,
+    pub updated_joints: usize,
+    pub updated_attachments: usize,
+    pub scanned: usize,
+    pub failures: Vec<AssetFailure>,
+    pub interrupted: bool,
+    pub timings: Timings,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssetFailure {
+    pub asset_id: String,
+    pub instances: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterProposal {
+    pub hash: i32,
+    pub candidates: Vec<String>,
+    pub instances: usize,
+    pub triangles: i32,
+    pub vertices: usize,
+    pub geometry_bytes: usize,
+}
+
+// gets to accept or reject every proposed merge before anything is
+// rewritten, one decision per proposal in the same order
+pub trait ClusterReviewer: Send + Sync {
+    fn review(&self, proposals: &[ClusterProposal]) -> Vec<bool>;
+}
+
+pub struct MeshDeduplicator {
+    options: DedupeOptions,
+    provider: Arc<dyn AssetProvider>,
+    downloader: AssetDownloader,
+    uploader: Option<Arc<OpenCloudClient>>,
+    reviewer: Option<Arc<dyn ClusterReviewer>>,
+    signature_cache: Option<Arc<SignatureCache>>,
+}
+
+impl MeshDeduplicator {
+    pub fn new(options: DedupeOptions) -> Self {
+        MeshDeduplicator {
+            options,
+            provider: Arc::new(AssetDownloader::default()),
+            downloader: AssetDownloader::default(),
+            uploader: None,
+            reviewer: None,
+            signature_cache: None,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: Arc<dyn AssetProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    // asset details for the canonical policies go through the same client,
+    // limiter and deadline as the downloads
+    pub fn with_downloader(mut self, downloader: AssetDownloader) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
+    // canonical meshes of duplicate clusters get re-uploaded under this
+    // creator so the place stops depending on someone else's uploads
+    pub fn with_uploader(mut self, uploader: Arc<OpenCloudClient>) -> Self {
+        self.uploader = Some(uploader);
+        self
+    }
+
+    pub fn with_reviewer(mut self, reviewer: Arc<dyn ClusterReviewer>) -> Self {
+        self.reviewer = Some(reviewer);
+        self
+    }
+
+    pub fn with_signature_cache(mut self, cache: Arc<SignatureCache>) -> Self {
+        self.signature_cache = Some(cache);
+        self
+    }
+
+    // with a database, clusters are also matched against the meshes earlier
+    // runs indexed. a hit moves the cluster under the earlier key, so the
+    // canonical recorded for it is picked up while rewriting. misses are
+    // indexed for the runs after this one
+    fn match_fuzzy_index(
+        &self,
+        clusters: &mut BTreeMap<i32, MeshCluster>,
+    ) -> Result<(), FixerError> {
+        let options = &self.options;
+        let (path, fuzzy) = match (&options.database_path, &options.fuzzy) {
+            (Some(path), Some(fuzzy)) => (path, fuzzy),
+            _ => return Ok(()),
+        };
+        let database = SignatureDatabase::open(path)?;
+
+        let keys = clusters.keys().copied().collect::<Vec<_>>();
+        let mut matched = 0;
+        for key in keys {
+            let cluster = &clusters[&key];
+            let features = match cluster.features {
+                Some(features) => features,
+                None => continue,
+            };
+            let scope = cluster.scope.clone();
+            match database.find_fuzzy(fuzzy, options.uv_signature, &scope, &features)? {
+                Some(known) if known == key => {}
+                Some(known) => {
+                    let cluster = clusters.remove(&key).expect("fuzzy-cluster");
+                    match clusters.get_mut(&known) {
+                        Some(target) => {
+                            target.candidates.extend(cluster.candidates);
+                            target.instances.extend(cluster.instances);
+                        }
+                        None => {
+                            clusters.insert(known, cluster);
+                        }
+                    }
+                    matched += 1;
+                }
+                None => database.index_fuzzy(fuzzy, key, &scope, &features)?,
+            }
+        }
+
+        database.flush()?;
+        if matched > 0 {
+            debug!("Matched {:?} clusters to earlier runs", matched);
+        }
+        Ok(())
+    }
+
+    // the weakest way the duplicate could have been matched to the canonical
+    async fn match_kind(
+        &self,
+        candidate: &CachedMesh,
+        canonical: &CachedMesh,
+    ) -> Result<MatchKind, FixerError> {
+        let (mesh, target) = (candidate.mesh(), canonical.mesh());
+        let (extent, target_extent) = (mesh_extent(mesh), mesh_extent(target));
+        let scaled = (extent - target_extent)
+            .abs()
+            .cmpgt(target_extent.abs() * SCALE_EPSILON)
+            .any();
+
+        if mesh.hash != target.hash && !scaled {
+            return Ok(MatchKind::Fuzzy);
+        }
+        if mesh.calculate_rotation(target).y != 0.0 {
+            return Ok(MatchKind::Rotated);
+        }
+        if scaled {
+            return Ok(MatchKind::Scaled);
+        }
+
+        let bytes = self
+            .provider
+            .content_hash(candidate.asset_id.clone())
+            .await?;
+        let target_bytes = self
+            .provider
+            .content_hash(canonical.asset_id.clone())
+            .await?;
+        match bytes == target_bytes {
+            true => Ok(MatchKind::ExactBytes),
+            false => Ok(MatchKind::Geometric),
+        }
+    }
+
+    async fn load_meshes(&self, candidates: Vec<&mut CachedMesh>) -> Result<(), FixerError> {
+        let mut fetched = Vec::new();
+        for candidate in candidates {
+            if candidate.mesh.is_none() {
+                let data = self.provider.fetch(candidate.asset_id.clone()).await?;
+                fetched.push((candidate, data));
+            }
+        }
+
+        let weld = self.options.weld;
+        fetched
+            .into_par_iter()
+            .try_for_each(|(candidate, mut data)| {
+                let mesh = RobloxMesh::from_cursor(&mut data).map_err(ParseFailure::from)?;
+                candidate.mesh = Some(prepare_mesh(mesh, weld));
+                Ok(())
+            })
+            .map_err(|err: ParseFailure| err.message.into())
+    }
+
+    // rejected clusters are dropped whole, their instances keep their meshes
+    fn review_clusters(&self, clusters: &mut BTreeMap<i32, MeshCluster>) {
+        let reviewer = match &self.reviewer {
+            Some(reviewer) => reviewer,
+            None => return,
+        };
+
+        let proposals = clusters
+            .iter()
+            .filter(|(_, cluster)| cluster.candidates.len() >= 2)
+            .map(|(hash, cluster)| {
+                let mesh = cluster.candidates[0].mesh();
+                ClusterProposal {
+                    hash: *hash,
+                    candidates: cluster
+                        .candidates
+                        .iter()
+                        .map(|x| x.asset_id.clone())
+                        .collect(),
+                    instances: cluster.instances.len(),
+                    triangles: mesh.triangles,
+                    vertices: mesh.vertices.len(),
+                    geometry_bytes: mesh.geometry_bytes(),
+                }
+            })
+            .collect::<Vec<_>>();
+        if proposals.is_empty() {
+            return;
+        }
+
+        let decisions = reviewer.review(&proposals);
+        for (proposal, accepted) in proposals.iter().zip(decisions.into_iter()) {
+            if !accepted {
+                debug!("Rejected cluster {:?}", proposal.hash);
+                clusters.remove(&proposal.hash);
+            }
+        }
+    }
+
+    async fn download_meshs(
+        &self,
+        dom: &WeakDom,
+        refs: Vec<Ref>,
+        bar: &ProgressBar,
+    ) -> Result<BTreeMap<String, AssetFailure>, FixerError> {
+        let mut handles = vec![];
+        let mut instances = BTreeMap::<String, usize>::new();
+        let master_semaphore = Arc::new(tokio::sync::Semaphore::new(4));
+
+        for referent in refs {
+            if let Some(child) = dom.get_by_ref(referent) {
+                let mesh_id = normalize_content(&get_mesh_id(child));
+                // local content is never downloaded, the provider reads it
+                // straight from disk when the mesh gets parsed
+                if is_local_asset(&mesh_id)
+                    || mesh_id.trim() == ""
+                    || self.options.ignored_assets.contains(&mesh_id)
+                {
+                    continue;
+                }
+
+                let count = instances.entry(mesh_id.clone()).or_insert(0);
+                *count += 1;
+                if *count > 1 {
+                    continue;
+                }
+
+                let semaphore = master_semaphore.clone();
+                let provider = self.provider.clone();
+                let bar = bar.clone();
+                handles.push(tokio::spawn(async move {
+                    let error = match semaphore.acquire().await {
+                        // queued downloads are dropped once interrupted
+                        Ok(_) if interrupted() => None,
+                        Ok(_) => match provider.fetch(mesh_id.clone()).await {
+                            Ok(_) => None,
+                            Err(err) => Some(err.to_string()),
+                        },
+                        Err(err) => Some(err.to_string()),
+                    };
+                    bar.inc(1);
+                    (mesh_id, error)
+                }));
+            } else {
+                return Err(FixerError::Dom(String::from(
+                    "Mesh part is missing from the place",
+                )));
+            }
+        }
+        bar.set_length(handles.len() as u64);
+
+        let mut failures = BTreeMap::<String, AssetFailure>::new();
+        for handle in futures::future::join_all(handles).await {
+            let (asset_id, error) = handle?;
+            if let Some(error) = error {
+                if self.options.fail_fast {
+                    return Err(format!("Failed to download {}: {}", asset_id, error).into());
+                }
+
+                failures.insert(
+                    asset_id.clone(),
+                    AssetFailure {
+                        instances: instances[&asset_id],
+                        asset_id,
+                        error,
+                    },
+                );
+            }
+        }
+
+        Ok(failures)
+    }
+
+    async fn parse_meshes(
+        &self,
+        dom: &WeakDom,
+        children: Vec<Ref>,
+        failures: &mut BTreeMap<String, AssetFailure>,
+        timings: &mut Timings,
+        bar: &ProgressBar,
+    ) -> Result<BTreeMap<i32, MeshCluster>, FixerError> {
+        let options = &self.options;
+        let mut clusters = BTreeMap::<i32, MeshCluster>::new();
+
+        let mut pending = Vec::<(Ref, String)>::new();
+        for child_ref in children {
+            let child = dom.get_by_ref(child_ref).expect("workspace-child");
+            let raw_mesh_id = get_mesh_id(child);
+
+            // an untextured MeshPart still gets deduped, only its geometry
+            // matters here
+            if raw_mesh_id.trim() == "" {
+                warn!(
+                    "Instance {:?}, does not have a valid meshId, skipping",
+                    child.name.clone()
+                );
+                continue;
+            }
+
+            match parse_content_url(&raw_mesh_id) {
+                Ok(ContentUrl::AssetId { .. }) | Ok(ContentUrl::Local(_)) => {}
+                Ok(_) => {
+                    warn!(
+                        "Instance {:?} has a non-mesh MeshId {:?}, skipping",
+                        child.name.clone(),
+                        raw_mesh_id
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Instance {:?}: {}, skipping", child.name.clone(), err);
+                    continue;
+                }
+            }
+            let mesh_id = normalize_content(&raw_mesh_id);
+
+            if options.ignored_assets.contains(&mesh_id) {
+                debug!(
+                    "Instance {:?} uses ignored mesh {:?}, skipping",
+                    child.name.clone(),
+                    mesh_id
+                );
+                continue;
+            }
+
+            if failures.contains_key(&mesh_id) {
+                warn!(
+                    "Instance {:?} uses mesh {:?} which failed to download, skipping",
+                    child.name.clone(),
+                    mesh_id
+                );
+                continue;
+            }
+
+            pending.push((child_ref, mesh_id));
+        }
+
+        // every asset is parsed and hashed once, on the rayon pool, a batch
+        // at a time so only a handful of raw downloads are held at once
+        let mut unique = Vec::<String>::new();
+        let mut seen = BTreeSet::<&str>::new();
+        for (_, mesh_id) in pending.iter() {
+            if seen.insert(mesh_id) {
+                unique.push(mesh_id.clone());
+            }
+        }
+        bar.set_length(unique.len() as u64);
+
+        let mut meshes = HashMap::<String, Result<ParsedMesh, ParseFailure>>::new();
+        let cache = self.signature_cache.as_deref();
+        let parse_start = Instant::now();
+        for batch in unique.chunks(PARSE_BATCH_SIZE) {
+            if interrupted() {
+                break;
+            }
+
+            let mut fetched = Vec::with_capacity(batch.len());
+            for mesh_id in batch {
+                let data = self.provider.fetch(mesh_id.clone()).await;
+                fetched.push((mesh_id.clone(), data.map_err(ParseFailure::from)));
+            }
+
+            let weld = options.weld;
+            let parsed = fetched
+                .into_par_iter()
+                .map(|(mesh_id, data)| (mesh_id, data.and_then(|x| parse_mesh(x, cache, weld))))
+                .collect::<Vec<_>>();
+            bar.inc(batch.len() as u64);
+            meshes.extend(parsed);
+        }
+        timings.record("mesh-parse", parse_start);
+
+        let hash_start = Instant::now();
+        for (child_ref, mesh_id) in pending {
+            let wrapped = has_wrap_instance(dom, child_ref);
+            let child = dom.get_by_ref(child_ref).expect("workspace-child");
+
+            let parsed = match meshes.get_mut(&mesh_id) {
+                Some(Ok(parsed)) => parsed,
+                Some(Err(err)) if is_local_asset(&mesh_id) => {
+                    warn!(
+                        "Instance {:?} uses unreadable local mesh {:?} ({}), skipping",
+                        child.name.clone(),
+                        mesh_id,
+                        err.message
+                    );
+                    continue;
+                }
+                // newer mesh versions aren't understood yet, that isn't
+                // worth aborting the run over even with --fail-fast
+                Some(Err(err)) if !options.fail_fast || err.unsupported_version => {
+                    warn!(
+                        "Instance {:?} uses unparseable mesh {:?} ({}), skipping",
+                        child.name.clone(),
+                        mesh_id,
+                        err.message
+                    );
+                    let failure = failures.entry(mesh_id.clone()).or_insert(AssetFailure {
+                        asset_id: mesh_id.clone(),
+                        instances: 0,
+                        error: err.message.clone(),
+                    });
+                    failure.instances += 1;
+                    continue;
+                }
+                Some(Err(err)) => return Err(err.message.clone().into()),
+                // interrupted before this mesh was parsed
+                None => continue,
+            };
+            if parsed.triangles < options.min_triangles {
+                debug!(
+                    "Instance {:?} uses mesh {:?} with only {:?} triangles, skipping",
+                    child.name.clone(),
+                    mesh_id,
+                    parsed.triangles
+                );
+                continue;
+            }
+
+            // scopes are folded into the key the same way uv hashes are. the
+            // key ends up in the signature database, so it must not depend on
+            // std's hasher
+            let scope = scope_of(dom, child_ref, options.scope);
+            let scope_key = match scope.is_empty() {
+                true => 0,
+                false => digest_bytes(scope.as_bytes()) as i32,
+            };
+            let cluster = clusters
+                .entry(parsed.cluster_key(options.uv_signature) ^ scope_key)
+                .or_insert_with(|| MeshCluster {
+                    scope,
+                    features: Some(parsed.features),
+                    ..MeshCluster::default()
+                });
+            cluster.instances.push(MeshInstance {
+                referent: child_ref,
+                asset_id: mesh_id.clone(),
+                wrapped: wrapped,
+            });
+
+            if !cluster.candidates.iter().any(|x| x.asset_id == mesh_id) {
+                cluster.candidates.push(CachedMesh {
+                    mesh: parsed.mesh.take(),
+                    asset_id: mesh_id.clone(),
+                    cframe: get_cframe!(child)?,
+                    init_size: get_initial_size(child)?,
+                    size: get_size!(child, "Size")?,
+                });
+                debug!("Cached {:?}", mesh_id);
+            }
+
+            debug!("{:?}", child.name.clone());
+        }
+
+        if let Some(fuzzy) = &options.fuzzy {
+            merge_fuzzy_clusters(&mut clusters, fuzzy, options.uv_signature);
+        }
+        merge_forced_clusters(&mut clusters, &options.merge_groups);
+        // the index can grow clusters too, so it goes before anything that
+        // looks at cluster sizes
+        self.match_fuzzy_index(&mut clusters)?;
+        // rarely repeated meshes aren't worth the risk of a rewrite, they are
+        // dropped before their geometry is ever loaded
+        if options.min_duplicates > 0 {
+            clusters.retain(|_, x| x.instances.len() >= options.min_duplicates);
+        }
+        timings.record("hashing", hash_start);
+
+        let load_start = Instant::now();
+        let candidates = clusters
+            .values_mut()
+            .filter(|x| x.candidates.len() >= 2)
+            .flat_map(|x| x.candidates.iter_mut())
+            .collect();
+        self.load_meshes(candidates).await?;
+        timings.record("mesh-parse", load_start);
+
+        if let Some(cache) = cache {
+            if let Err(err) = cache.save() {
+                warn!("Could not save signature cache {:?}: {}", cache.path(), err);
+            }
+        }
+        Ok(clusters)
+    }
+
+    async fn rewrite_clusters(
+        &self,
+        dom: &mut WeakDom,
+        clusters: &mut BTreeMap<i32, MeshCluster>,
+        result: &mut DedupeResult,
+        bar: &ProgressBar,
+    ) -> Result<Vec<(Ref, CFrame)>, FixerError> {
+        let options = &self.options;
+        let mut rotated_parts = Vec::<(Ref, CFrame)>::new();
+
+        let database = match &options.database_path {
+            Some(path) => Some(SignatureDatabase::open(path)?),
+            None => None,
+        };
+
+        bar.set_length(clusters.len() as u64);
+        for (hash, cluster) in clusters.iter_mut() {
+            // clusters are rewritten whole, stopping between two of them
+            // leaves the place and the journal consistent
+            if interrupted() {
+                break;
+            }
+            bar.inc(1);
+            let known = match &database {
+                Some(database) => database.get(*hash)?,
+                None => None,
+            };
+
+            // a canonical picked by an earlier run wins even when this place
+            // doesn't reference it, so pull it in as an extra candidate
+            if let Some(record) = &known {
+                if !cluster
+                    .candidates
+                    .iter()
+                    .any(|x| x.asset_id == record.asset_id)
+                {
+                    cluster.candidates.push(CachedMesh {
+                        mesh: None,
+                        asset_id: record.asset_id.clone(),
+                        cframe: CFrame::default(),
+                        init_size: record.init_size(),
+                        size: record.size(),
+                    });
+                }
+            }
+            if cluster.candidates.len() >= 2 {
+                self.load_meshes(cluster.candidates.iter_mut().collect())
+                    .await?;
+            }
+
+            let mut third_party = false;
+            let canonical_idx = match (&known, &options.preferred_owner) {
+                (Some(record), _) => cluster
+                    .candidates
+                    .iter()
+                    .position(|x| x.asset_id == record.asset_id)
+                    .expect("database-candidate"),
+                (None, Some(owner)) if cluster.candidates.len() >= 2 => {
+                    let (idx, foreign) = select_owned_canonical(
+                        &self.downloader,
+                        &options.canonical_policy,
+                        owner,
+                        &cluster.references(),
+                    )
+                    .await?;
+                    if foreign {
+                        warn!(
+                            "No mesh in cluster {:?} is owned by {}, consolidating onto third-party {:?}",
+                            hash, owner, cluster.candidates[idx].asset_id
+                        );
+                    }
+                    third_party = foreign;
+                    idx
+                }
+                (None, _) => {
+                    select_canonical(
+                        &self.downloader,
+                        &options.canonical_policy,
+                        &cluster.references(),
+                    )
+                    .await?
+                }
+            };
+            let cluster = &*cluster;
+            let new_mesh = &cluster.candidates[canonical_idx];
+
+            let mut match_kind = MatchKind::ExactBytes;
+            if cluster.candidates.len() >= 2 {
+                for candidate in cluster.candidates.iter() {
+                    if candidate.asset_id != new_mesh.asset_id {
+                        match_kind = match_kind.max(self.match_kind(candidate, new_mesh).await?);
+                    }
+                }
+            }
+            // left for someone to look at, nothing is uploaded, recorded or
+            // rewritten for it
+            if cluster.candidates.len() >= 2 && match_kind.confidence() < options.min_confidence {
+                info!(
+                    "Cluster {:?} is a {} match ({:.2}), leaving it for review",
+                    hash,
+                    match_kind,
+                    match_kind.confidence()
+                );
+                let others = cluster
+                    .candidates
+                    .iter()
+                    .filter(|x| x.asset_id != new_mesh.asset_id)
+                    .collect::<Vec<_>>();
+                result.report.clusters.push(ClusterReport {
+                    hash: new_mesh.mesh().hash,
+                    canonical_asset: new_mesh.asset_id.clone(),
+                    replaced_assets: others.iter().map(|x| x.asset_id.clone()).collect(),
+                    color_mismatches: vec![],
+                    instances: cluster
+                        .instances
+                        .iter()
+                        .filter(|x| x.asset_id != new_mesh.asset_id)
+                        .map(|x| AffectedInstance {
+                            path: instance_path(dom, x.referent),
+                            original_asset: x.asset_id.clone(),
+                        })
+                        .collect(),
+                    triangles: new_mesh.mesh().triangles,
+                    estimated_savings: others.iter().map(|x| x.mesh().geometry_bytes()).sum(),
+                    third_party_canonical: third_party,
+                    match_kind,
+                    confidence: match_kind.confidence(),
+                    applied: false,
+                });
+                continue;
+            }
+
+            let mut canonical_id = new_mesh.asset_id.clone();
+            if let (Some(uploader), None) = (&self.uploader, &known) {
+                if cluster.candidates.len() >= 2 {
+                    let data = self.provider.fetch(new_mesh.asset_id.clone()).await?;
+                    let name = format!("Mesh {}", hash);
+                    canonical_id = uploader.upload_mesh(&name, data.into_inner()).await?;
+                    info!("Uploaded {:?} as {:?}", new_mesh.asset_id, canonical_id);
+                }
+            }
+
+            if let (Some(database), None) = (&database, &known) {
+                let record =
+                    CanonicalRecord::new(canonical_id.clone(), new_mesh.init_size, new_mesh.size);
+                database.insert(*hash, &record)?;
+            }
+
+            if cluster.candidates.len() < 2 {
+                continue;
+            }
+            // merging a differently tinted mesh would change how it looks,
+            // those keep their own mesh and are only reported
+            let colors = new_mesh.mesh().vertex_color_hash();
+            let (replaced, mismatched): (Vec<&CachedMesh>, Vec<&CachedMesh>) = cluster
+                .candidates
+                .iter()
+                .filter(|x| x.asset_id != new_mesh.asset_id)
+                .partition(|x| x.mesh().vertex_color_hash() == colors);
+            for candidate in mismatched.iter() {
+                warn!(
+                    "Not merging {:?} into {:?}, their vertex colors differ",
+                    candidate.asset_id, canonical_id
+                );
+            }
+            let mut cluster_report = ClusterReport {
+                hash: new_mesh.mesh().hash,
+                canonical_asset: canonical_id.clone(),
+                replaced_assets: replaced.iter().map(|x| x.asset_id.clone()).collect(),
+                color_mismatches: mismatched.iter().map(|x| x.asset_id.clone()).collect(),
+                instances: vec![],
+                triangles: new_mesh.mesh().triangles,
+                estimated_savings: replaced.iter().map(|x| x.mesh().geometry_bytes()).sum(),
+                third_party_canonical: third_party,
+                match_kind,
+                confidence: match_kind.confidence(),
+                applied: true,
+            };
+            let memory = &mut result.report.memory;
+            for candidate in replaced.iter() {
+                memory.vertex_bytes += candidate.mesh().vertex_bytes();
+                memory.index_bytes += candidate.mesh().index_bytes();
+            }
+
+            for candidate in replaced.iter() {
+                result
+                    .mapping
+                    .insert(candidate.asset_id.clone(), canonical_id.clone());
+            }
+
+            for instance in cluster.instances.iter() {
+                if instance.asset_id == canonical_id
+                    || mismatched.iter().any(|x| x.asset_id == instance.asset_id)
+                {
+                    continue;
+                }
+
+                cluster_report.instances.push(AffectedInstance {
+                    path: instance_path(dom, instance.referent),
+                    original_asset: instance.asset_id.clone(),
+                });
+
+                let mesh = &cluster
+                    .candidates
+                    .iter()
+                    .find(|x| x.asset_id == instance.asset_id)
+                    .expect("cluster-candidate")
+                    .mesh();
+                let child = dom
+                    .get_by_ref_mut(instance.referent)
+                    .expect("workspace-child");
+                let cframe = get_cframe!(child)?;
+                let old_scale = size_scale(get_size!(child, "Size")?, get_initial_size(child)?);
+                let new_scale = match instance.wrapped {
+                    true => size_scale(get_size!(child, "Size")?, new_mesh.init_size),
+                    false => size_scale(new_mesh.size, new_mesh.init_size),
+                };
+
+                set_mesh_id(child, &canonical_id);
+                if options.stamp_attributes {
+                    stamp_original(child, ORIGINAL_MESH_ID, instance.asset_id.clone());
+                }
+
+                if instance.wrapped {
+                    debug!(
+                        "Instance {:?} has a WrapLayer/WrapTarget, keeping its size",
+                        child.name.clone()
+                    );
+                } else {
+                    modify_property!(child.properties, "Size", Variant::Vector3(new_mesh.size));
+                    modify_property!(
+                        child.properties,
+                        "InitialSize",
+                        Variant::Vector3(new_mesh.init_size)
+                    );
+                }
+
+                let rotation = mesh.calculate_rotation(new_mesh.mesh());
+                let offset = origin_offset(mesh, old_scale, new_mesh.mesh(), new_scale, rotation.y);
+                if rotation.y != 0.0 || offset.length() > ORIGIN_EPSILON {
+                    let mut correction = CFrame::angles(0.0, rotation.y, 0.0);
+                    if offset.length() > ORIGIN_EPSILON {
+                        correction =
+                            correction.mult(CFrame::from_xyz(offset.x, offset.y, offset.z));
+                    }
+                    modify_property!(
+                        child.properties,
+                        "CFrame",
+                        Variant::CFrame(cframe.mult(correction))
+                    );
+                    rotated_parts.push((instance.referent, correction));
+                    debug!("rotation={:?} offset={:?}", rotation, vector3(offset));
+                }
+
+                debug!("Id: {:?}", get_mesh_id(child));
+            }
+
+            result.report.clusters.push(cluster_report);
+        }
+
+        if let Some(database) = &database {
+            database.flush()?;
+        }
+
+        Ok(rotated_parts)
+    }
+
+    pub async fn run(&self, place: &mut Place) -> Result<DedupeResult, FixerError> {
+        let options = &self.options;
+        let descendants = place.descendants();
+        // joints and the journal still look at the whole place, a part inside
+        // the filter can be welded to one outside of it
+        let mut targets = if options.path_filter.is_empty() {
+            descendants.clone()
+        } else {
+            place.filtered_descendants(&options.path_filter)
+        };
+        if !options.tag_filter.is_empty() {
+            targets.retain(|x| options.tag_filter.matches(&place.dom, *x));
+        }
+        let dom = &mut place.dom;
+        let children = filter_mesh_parts(dom, targets.clone());
+        let unions = filter_unions(dom, &targets);
+        let snapshot = JournalSnapshot::capture(dom, &descendants);
+        let progress = Progress::new(options.quiet);
+        info!(
+            mesh_parts = children.len(),
+            unions = unions.len(),
+            "Found meshes"
+        );
+
+        let download_bar = progress.phase("Downloading", children.len());
+        let parse_bar = progress.phase("Parsing", children.len());
+        let rewrite_bar = progress.phase("Rewriting", 0);
+        let mut result = DedupeResult {
+            scanned: children.len(),
+            ..DedupeResult::default()
+        };
+        let download_start = Instant::now();
+        let mut failures = self
+            .download_meshs(dom, children.clone(), &download_bar)
+            .instrument(info_span!("download"))
+            .await?;
+        download_bar.finish();
+        result.timings.record("download", download_start);
+
+        let appearances = find_surface_appearances(dom, &children);
+        let joints = JointGraph::from_dom(dom, &descendants);
+        let mut clusters = self
+            .parse_meshes(
+                dom,
+                children,
+                &mut failures,
+                &mut result.timings,
+                &parse_bar,
+            )
+            .instrument(info_span!("parse"))
+            .await?;
+        parse_bar.finish();
+        self.review_clusters(&mut clusters);
+
+        let dedupe_span = info_span!("dedupe");
+        let rewrite_start = Instant::now();
+        let rotated_parts = self
+            .rewrite_clusters(dom, &mut clusters, &mut result, &rewrite_bar)
+            .instrument(dedupe_span.clone())
+            .await?;
+        rewrite_bar.finish();
+
+        dedupe_span.in_scope(|| {
+            for (part, correction) in rotated_parts {
+                result.updated_joints += joints.compensate(dom, part, correction);
+                if options.compensate_attachments {
+                    result.updated_attachments += compensate_attachments(dom, part, correction);
+                }
+            }
+        });
+        result.timings.record("rewrite", rewrite_start);
+
+        result.interrupted = interrupted();
+        if !result.interrupted {
+            let surfaces_start = Instant::now();
+            result.map_savings =
+                dedupe_surface_appearances(self.provider.as_ref(), dom, &appearances)
+                    .instrument(dedupe_span.clone())
+                    .await?;
+            result.union_clusters = dedupe_unions(self.provider.as_ref(), dom, &unions)
+                .instrument(dedupe_span)
+                .await?;
+            let memory = &mut result.report.memory;
+            memory.texture_bytes = result.map_savings.values().map(|x| x.bytes_saved).sum();
+            memory.union_bytes = result.union_clusters.iter().map(|x| x.bytes_saved).sum();
+            result.timings.record("appearances-unions", surfaces_start);
+        }
+        result.journal = snapshot.diff(dom);
+        result.failures = failures.into_iter().map(|(_, x)| x).collect();
+        info!(
+            clusters = result.report.clusters.len(),
+            remapped = result.mapping.len(),
+            failures = result.failures.len(),
+            "Dedupe finished"
+        );
+
+        Ok(result)
+    }
+}
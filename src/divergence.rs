@@ -0,0 +1,173 @@
+use glam::Vec3;
+use rbx_dom_weak::types::{Ref, Variant};
+use std::collections::BTreeMap;
+
+use crate::{
+    place::{has_wrap_instance, Place},
+    utils::{
+        content_url::normalize_content,
+        fidelity::PHYSICS_PROPERTIES,
+        math::{vec3, vector3},
+        mesh_content::get_mesh_id,
+        reflection::property_or_default,
+    },
+};
+
+// properties every copy of a prop is expected to agree on
+const CHECKED_PROPERTIES: [&str; 5] = [
+    "InitialSize",
+    "TextureID",
+    "RenderFidelity",
+    "CollisionFidelity",
+    "DoubleSided",
+];
+// a different texture on the same mesh is as often a deliberate recolor as a
+// mistake, it is reported but never normalized
+const REPORT_ONLY: [&str; 1] = ["TextureID"];
+// sizes closer than this count as the same, studio rounds the imported bounds
+const SIZE_STEP: f32 = 0.001;
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub mesh_id: String,
+    pub property: String,
+    // every value seen with how many parts had it, most common first
+    pub values: Vec<(String, usize)>,
+    pub normalized: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DivergenceReport {
+    pub meshes: usize,
+    pub divergences: Vec<Divergence>,
+    pub normalized: usize,
+}
+
+fn read_property(place: &Place, referent: Ref, name: &str) -> Option<Variant> {
+    let instance = place.dom.get_by_ref(referent)?;
+    // parts that were never rescaled may not carry an InitialSize
+    if name == "InitialSize" && !instance.properties.contains_key(name) {
+        return property_or_default(instance, "Size");
+    }
+    property_or_default(instance, name)
+}
+
+// what two values are compared by, close sizes and differently spelled
+// content urls come out the same
+fn value_key(value: &Variant) -> String {
+    match value {
+        Variant::Vector3(v) => {
+            let rounded = (vec3(*v) / SIZE_STEP).round() * SIZE_STEP;
+            format!("{:?}", vector3(rounded))
+        }
+        Variant::Content(content) => normalize_content(content.as_str()),
+        Variant::Enum(value) => value.to_u32().to_string(),
+        Variant::Bool(value) => value.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// moves a part onto the majority value. a new InitialSize keeps the part's
+// scale the same way fix-sizes does, and a new collision fidelity throws the
+// baked collision geometry away
+fn normalize(place: &mut Place, referent: Ref, property: &str, value: &Variant) -> bool {
+    if property == "InitialSize" {
+        // cages are authored against the current size
+        if has_wrap_instance(&place.dom, referent) {
+            return false;
+        }
+        let (old, new) = match (read_property(place, referent, property), value) {
+            (Some(Variant::Vector3(old)), Variant::Vector3(new)) => (vec3(old), vec3(*new)),
+            _ => return false,
+        };
+        let size = match read_property(place, referent, "Size") {
+            Some(Variant::Vector3(size)) => vec3(size),
+            _ => return false,
+        };
+        let scale = size / old.max(Vec3::splat(SIZE_STEP));
+        let instance = place.dom.get_by_ref_mut(referent).expect("divergent-part");
+        instance
+            .properties
+            .insert(String::from("Size"), Variant::Vector3(vector3(new * scale)));
+    }
+
+    let instance = place.dom.get_by_ref_mut(referent).expect("divergent-part");
+    instance
+        .properties
+        .insert(property.to_string(), value.clone());
+    if property == "CollisionFidelity" {
+        for name in PHYSICS_PROPERTIES.iter() {
+            instance.properties.remove(*name);
+        }
+    }
+    true
+}
+
+// finds MeshParts that share a MeshId but disagree on a property. with
+// `fix` the minority is moved onto the most common value, ties go to the
+// value seen first
+pub fn find_divergence(place: &mut Place, fix: bool) -> DivergenceReport {
+    let mut report = DivergenceReport::default();
+
+    let mut by_mesh = BTreeMap::<String, Vec<Ref>>::new();
+    for referent in place.mesh_parts() {
+        let instance = place.dom.get_by_ref(referent).expect("mesh-part");
+        let mesh_id = normalize_content(&get_mesh_id(instance));
+        if mesh_id.trim() == "" {
+            continue;
+        }
+        by_mesh.entry(mesh_id).or_default().push(referent);
+    }
+
+    for (mesh_id, parts) in by_mesh {
+        if parts.len() < 2 {
+            continue;
+        }
+        report.meshes += 1;
+
+        for property in CHECKED_PROPERTIES.iter() {
+            let mut groups = Vec::<(String, Variant, Vec<Ref>)>::new();
+            for referent in parts.iter() {
+                let value = match read_property(place, *referent, property) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let key = value_key(&value);
+                match groups.iter_mut().find(|x| x.0 == key) {
+                    Some(group) => group.2.push(*referent),
+                    None => groups.push((key, value, vec![*referent])),
+                }
+            }
+            if groups.len() < 2 {
+                continue;
+            }
+            // stable, so ties keep the value seen first on top
+            groups.sort_by(|a, b| b.2.len().cmp(&a.2.len()));
+
+            let mut normalized = 0;
+            if fix && !REPORT_ONLY.contains(property) {
+                let majority = groups[0].1.clone();
+                for (_, _, referents) in groups[1..].iter() {
+                    for referent in referents {
+                        if normalize(place, *referent, property, &majority) {
+                            normalized += 1;
+                        }
+                    }
+                }
+            }
+
+            report.normalized += normalized;
+            report.divergences.push(Divergence {
+                mesh_id: mesh_id.clone(),
+                property: property.to_string(),
+                values: groups
+                    .into_iter()
+                    .map(|(key, _, referents)| (key, referents.len()))
+                    .collect(),
+                normalized,
+            });
+        }
+    }
+
+    report
+}
@@ -0,0 +1,313 @@
+use futures::StreamExt;
+use indicatif::ProgressBar;
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tracing::warn;
+
+use crate::{
+    place::Place,
+    utils::{
+        asset_provider::AssetProvider,
+        atomic_file::write_atomic,
+        content_url::{extract_assetid, normalize_content},
+        digest_bytes,
+        gltf::export_glb,
+        instance_path,
+        mesh_content::get_mesh_id,
+        mesh_reader::RobloxMesh,
+        obj_writer::export_obj,
+        reflection::is_a,
+        shutdown::interrupted,
+        surface_appearance::SURFACE_APPEARANCE_MAPS,
+        texture_header::{TextureFormat, TextureHeader},
+        FixerError,
+    },
+};
+
+// parsing and converting is cheap next to the download, same as prefetch
+const EXTRACT_CONCURRENCY: usize = 32;
+// decal ids from the library point at a model wrapping the real image, and
+// that one is never wrapped again
+const MAX_CONTAINER_DEPTH: usize = 1;
+// long instance paths are cut so the name stays well under the 255 bytes
+// most filesystems allow
+const MAX_NAME_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeshFormat {
+    // the bytes as roblox serves them
+    Raw,
+    Obj,
+    Gltf,
+}
+
+impl Default for MeshFormat {
+    fn default() -> Self {
+        MeshFormat::Raw
+    }
+}
+
+impl FromStr for MeshFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "raw" | "mesh" => Ok(MeshFormat::Raw),
+            "obj" => Ok(MeshFormat::Obj),
+            "gltf" | "glb" => Ok(MeshFormat::Gltf),
+            _ => Err(format!("Unknown mesh format {:?}", format)),
+        }
+    }
+}
+
+impl MeshFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            MeshFormat::Raw => "mesh",
+            MeshFormat::Obj => "obj",
+            MeshFormat::Gltf => "glb",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractResult {
+    pub assets: usize,
+    pub written: Vec<(String, PathBuf)>,
+    pub failed: Vec<(String, String)>,
+}
+
+// anything a filesystem or a shell could trip over becomes an underscore
+fn file_stem(path: &str, asset_id: &str) -> String {
+    let mut stem = path
+        .chars()
+        .map(|c| match c {
+            '/' => '.',
+            c if c.is_ascii_alphanumeric() || c == '-' || c == '_' => c,
+            _ => '_',
+        })
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>();
+    if stem.is_empty() {
+        stem.push_str("Mesh");
+    }
+
+    // urls without an asset id still need a name that can't collide
+    let id = extract_assetid(asset_id.to_string())
+        .unwrap_or_else(|_| format!("{:032x}", digest_bytes(asset_id.as_bytes())));
+    format!("{}_{}", stem, id)
+}
+
+fn write_mesh(
+    mut data: Cursor<Vec<u8>>,
+    name: &str,
+    output_path: &Path,
+    format: MeshFormat,
+) -> Result<(), FixerError> {
+    if format == MeshFormat::Raw {
+        return write_atomic(output_path, data.get_ref());
+    }
+
+    let mesh = RobloxMesh::from_cursor(&mut data)?;
+    let output_path = output_path.to_string_lossy();
+    match format {
+        MeshFormat::Obj => export_obj(&mesh, name, &output_path),
+        _ => export_glb(&mesh, name, &output_path),
+    }
+}
+
+// writes every mesh the place references to `out_dir`, once per asset and
+// named after the first part that uses it
+pub async fn extract_meshes(
+    provider: &dyn AssetProvider,
+    place: &Place,
+    out_dir: &Path,
+    format: MeshFormat,
+    bar: &ProgressBar,
+) -> Result<ExtractResult, FixerError> {
+    fs::create_dir_all(out_dir)?;
+
+    let mut meshes = BTreeMap::<String, String>::new();
+    for referent in place.mesh_parts() {
+        let instance = place.dom.get_by_ref(referent).expect("mesh-part");
+        let asset_id = normalize_content(&get_mesh_id(instance));
+        if asset_id.trim() == "" {
+            continue;
+        }
+        meshes
+            .entry(asset_id)
+            .or_insert_with(|| instance_path(&place.dom, referent));
+    }
+    bar.set_length(meshes.len() as u64);
+
+    let mut result = ExtractResult {
+        assets: meshes.len(),
+        ..ExtractResult::default()
+    };
+    let mut extracts = futures::stream::iter(meshes)
+        .filter(|_| futures::future::ready(!interrupted()))
+        .map(|(asset_id, path)| async move {
+            let stem = file_stem(&path, &asset_id);
+            let output_path = out_dir.join(format!("{}.{}", stem, format.extension()));
+            let written = match provider.fetch(asset_id.clone()).await {
+                Ok(data) => write_mesh(data, &stem, &output_path, format),
+                Err(err) => Err(err),
+            };
+            (asset_id, output_path, written)
+        })
+        .buffer_unordered(EXTRACT_CONCURRENCY);
+
+    while let Some((asset_id, output_path, written)) = extracts.next().await {
+        match written {
+            Ok(()) => result.written.push((asset_id, output_path)),
+            Err(err) => {
+                warn!("Could not extract {:?}: {}", asset_id, err);
+                result.failed.push((asset_id, err.to_string()));
+            }
+        }
+        bar.inc(1);
+    }
+    bar.finish();
+
+    result.written.sort();
+    Ok(result)
+}
+
+// the image properties of an instance, as property name and content
+fn texture_properties(place: &Place, referent: Ref) -> Vec<(&'static str, String)> {
+    let instance = match place.dom.get_by_ref(referent) {
+        Some(instance) => instance,
+        None => return Vec::new(),
+    };
+    let names: &[&str] = if is_a(&instance.class, "MeshPart") {
+        &["TextureID"]
+    } else if is_a(&instance.class, "Decal") {
+        // Texture inherits from Decal
+        &["Texture"]
+    } else if instance.class == "SurfaceAppearance" {
+        &SURFACE_APPEARANCE_MAPS
+    } else {
+        &[]
+    };
+
+    let mut properties = Vec::new();
+    for name in names {
+        if let Some(Variant::Content(content)) = instance.properties.get(*name) {
+            let asset_id = normalize_content(content.as_str());
+            if asset_id.trim() != "" {
+                properties.push((*name, asset_id));
+            }
+        }
+    }
+    properties
+}
+
+// the image a wrapped decal points at, the first Texture found in the model
+fn container_texture(data: &[u8]) -> Option<String> {
+    let dom: WeakDom = match rbx_binary::from_reader_default(data) {
+        Ok(dom) => dom,
+        Err(_) => rbx_xml::from_reader_default(data).ok()?,
+    };
+
+    let mut stack = dom.root().children().to_vec();
+    while let Some(referent) = stack.pop() {
+        let instance = dom.get_by_ref(referent)?;
+        if let Some(Variant::Content(content)) = instance.properties.get("Texture") {
+            let asset_id = normalize_content(content.as_str());
+            if asset_id.trim() != "" {
+                return Some(asset_id);
+            }
+        }
+        stack.extend(instance.children().iter().rev());
+    }
+    None
+}
+
+// the image behind an asset id, unwrapping a decal model when it is one
+async fn fetch_texture(
+    provider: &dyn AssetProvider,
+    mut asset_id: String,
+) -> Result<(Vec<u8>, TextureFormat), FixerError> {
+    let mut depth = 0;
+    loop {
+        let data = provider.fetch(asset_id.clone()).await?.into_inner();
+        match TextureHeader::read(&data) {
+            Ok(header) => return Ok((data, header.format)),
+            Err(err) if depth >= MAX_CONTAINER_DEPTH => return Err(err),
+            Err(err) => match container_texture(&data) {
+                Some(inner) => asset_id = inner,
+                None => return Err(err),
+            },
+        }
+        depth += 1;
+    }
+}
+
+// writes every image the place's parts, decals and surface appearances use
+// to `out_dir`, once per asset and named after the first property using it.
+// the image is kept in the format roblox serves it in
+pub async fn extract_textures(
+    provider: &dyn AssetProvider,
+    place: &Place,
+    out_dir: &Path,
+    bar: &ProgressBar,
+) -> Result<ExtractResult, FixerError> {
+    fs::create_dir_all(out_dir)?;
+
+    let mut textures = BTreeMap::<String, String>::new();
+    for (path, referent) in place.descendant_paths() {
+        for (property, asset_id) in texture_properties(place, referent) {
+            textures
+                .entry(asset_id)
+                .or_insert_with(|| format!("{}/{}", path, property));
+        }
+    }
+    bar.set_length(textures.len() as u64);
+
+    let mut result = ExtractResult {
+        assets: textures.len(),
+        ..ExtractResult::default()
+    };
+    let mut extracts = futures::stream::iter(textures)
+        .filter(|_| futures::future::ready(!interrupted()))
+        .map(|(asset_id, path)| async move {
+            let stem = file_stem(&path, &asset_id);
+            let written = match fetch_texture(provider, asset_id.clone()).await {
+                Ok((data, format)) => {
+                    let extension = match format {
+                        TextureFormat::Png => "png",
+                        TextureFormat::Jpeg => "jpg",
+                    };
+                    let output_path = out_dir.join(format!("{}.{}", stem, extension));
+                    write_atomic(&output_path, &data).map(|_| output_path)
+                }
+                Err(err) => Err(err),
+            };
+            (asset_id, written)
+        })
+        .buffer_unordered(EXTRACT_CONCURRENCY);
+
+    while let Some((asset_id, written)) = extracts.next().await {
+        match written {
+            Ok(output_path) => result.written.push((asset_id, output_path)),
+            Err(err) => {
+                warn!("Could not extract {:?}: {}", asset_id, err);
+                result.failed.push((asset_id, err.to_string()));
+            }
+        }
+        bar.inc(1);
+    }
+    bar.finish();
+
+    result.written.sort();
+    Ok(result)
+}
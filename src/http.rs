@@ -0,0 +1,109 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tracing::warn;
+
+// a place file or a place worth of parts fits comfortably, anything past
+// this is a mistake
+const MAX_BODY_BYTES: usize = 256 * 1024 * 1024;
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub authorization: Option<String>,
+    pub body: Vec<u8>,
+}
+
+// just enough http/1.1 for studio's HttpService and curl: one request per
+// connection, bodies always come with a Content-Length
+pub async fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut buffer = Vec::<u8>::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(end) = buffer.windows(4).position(|x| x == b"\r\n\r\n") {
+            break end;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Err("headers too large".to_string());
+        }
+        let read = stream.read(&mut chunk).await.map_err(|x| x.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers = lines.filter_map(|x| x.split_once(':')).collect::<Vec<_>>();
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| value.trim())
+    };
+    let length = header("content-length")
+        .map(|x| x.parse::<usize>())
+        .transpose()
+        .map_err(|_| "invalid Content-Length".to_string())?
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Err("body too large".to_string());
+    }
+
+    // curl holds large uploads back until it's told to go ahead
+    if header("expect").map(|x| x.eq_ignore_ascii_case("100-continue")) == Some(true) {
+        stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .await
+            .map_err(|x| x.to_string())?;
+    }
+
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut chunk).await.map_err(|x| x.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_string());
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(length);
+
+    Ok(Request {
+        method,
+        path,
+        authorization: header("authorization").map(|x| x.to_string()),
+        body,
+    })
+}
+
+pub async fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    let written = async {
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body).await
+    };
+    if let Err(err) = written.await {
+        warn!("Could not answer the request: {}", err);
+    }
+}
+
+pub async fn write_json(stream: &mut TcpStream, status: &str, body: serde_json::Value) {
+    write_response(
+        stream,
+        status,
+        "application/json",
+        body.to_string().as_bytes(),
+    )
+    .await;
+}
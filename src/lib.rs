@@ -0,0 +1,41 @@
+// the parsing, hashing and stats core builds anywhere, including wasm32.
+// everything that needs tokio, the network or a database sits behind the
+// native feature, which the binary turns on
+#[cfg(feature = "native")]
+pub mod bridge;
+#[cfg(feature = "native")]
+pub mod combine;
+#[cfg(feature = "native")]
+pub mod dedupe;
+pub mod divergence;
+#[cfg(feature = "native")]
+pub mod extract;
+#[cfg(feature = "native")]
+pub mod optimize;
+pub mod place;
+#[cfg(feature = "native")]
+pub mod prefetch;
+#[cfg(feature = "native")]
+pub mod rojo;
+pub mod sizes;
+pub mod stats;
+#[cfg(feature = "native")]
+pub mod summary;
+pub mod utils;
+
+#[cfg(feature = "native")]
+pub use dedupe::{
+    AssetFailure, ClusterProposal, ClusterReviewer, DedupeOptions, DedupeResult, DedupeScope,
+    MeshDeduplicator,
+};
+pub use place::Place;
+#[cfg(feature = "native")]
+pub use prefetch::{prefetch_place, PrefetchResult};
+pub use stats::{collect_stats, PlaceStats};
+#[cfg(feature = "native")]
+pub use utils::{asset_downloader::AssetDownloader, canonical::CanonicalPolicy};
+pub use utils::{
+    asset_provider::{AssetProvider, MemoryProvider},
+    mesh_reader::{MeshError, MeshSignature, RobloxMesh},
+    FixerError,
+};
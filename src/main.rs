@@ -1,261 +1,1086 @@
-use core::panic;
-use futures::future::Future;
-use rbx_dom_weak::{
-    types::{Ref, Variant},
-    WeakDom,
+use futures::StreamExt;
+use rbxlx_mesh_fixer::{
+    collect_stats,
+    combine::{combine_place, CombineOptions},
+    divergence::find_divergence,
+    extract::{extract_meshes, extract_textures},
+    optimize::{decimate_place, strip_place_lods},
+    place::STDIO_PATH,
+    prefetch_place,
+    rojo::dedupe_project,
+    sizes::fix_place_sizes,
+    summary::{RunSummary, EXIT_FATAL, EXIT_OK},
+    utils::{
+        asset_provider::LocalContentProvider,
+        atomic_file::{backup_file, write_atomic},
+        attributes::{restore_originals, ORIGINAL_MESH_ID},
+        cleanup::{cleanup_mesh, DEFAULT_WELD_EPSILON},
+        content_url::normalize_content,
+        fidelity::apply_policies,
+        gltf::export_glb,
+        journal::ChangeJournal,
+        luau::write_migration_script,
+        mapping::{apply_mapping, read_mapping, write_mapping, AssetMapping},
+        obj_reader::read_obj_file,
+        open_cloud::OpenCloudClient,
+        progress::Progress,
+        rate_limit::RateLimiter,
+        remote_cache::RemoteCache,
+        report::DedupeReport,
+        sanitize::sanitize_parts,
+        shutdown,
+        signature_cache::SignatureCache,
+        thumbnails::fetch_thumbnails,
+        timings::Timings,
+        verify::verify_round_trip,
+    },
+    AssetDownloader, AssetProvider, DedupeOptions, DedupeResult, FixerError, MeshDeduplicator,
+    Place, RobloxMesh,
 };
-use rbx_types::{CFrame, Matrix3, Vector3};
 use std::{
-    collections::{BTreeMap, HashMap},
-    error::Error,
-    fs::File,
-    io::{BufReader, BufWriter, Write},
+    fmt,
+    io::Cursor,
     path::Path,
     sync::Arc,
+    time::{Duration, Instant},
 };
+use tracing::{error, info, info_span, warn, Instrument};
+use tracing_subscriber::EnvFilter;
 
-mod utils;
-use utils::{
-    asset_downloader::download_asset, cframe::CFrameExt, mesh_reader::RobloxMesh, GenericError,
-};
+mod config;
+mod http;
+mod options;
+mod review;
+mod serve;
+mod server;
+mod watch;
+use options::{Command, LogFormat, Options};
 
-macro_rules! get_content {
-    ($props:expr, $name:expr) => {
-        match &$props[$name] {
-            Variant::Content(prop) => {
-                let raw_prop = prop.to_owned();
-                raw_prop.into_string()
-            }
-            _ => panic!("Property has invalid type"),
+fn init_logging(options: &Options) {
+    let filter = EnvFilter::try_new(&options.log_level).expect("log-level");
+    // stdout may be carrying the place itself, so logs always go to stderr
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match options.log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+fn save_place(place: &Place, options: &Options) {
+    let output_path = &options.output_path;
+    info_span!("write").in_scope(|| {
+        if options.in_place {
+            let backup = backup_file(output_path)
+                .unwrap_or_else(|err| fail(options, format!("Could not back up place: {}", err)));
+            info!("Backed up original place to {:?}", backup);
         }
-    };
+        place
+            .save(output_path)
+            .unwrap_or_else(|err| fail(options, format!("Could not save place: {}", err)));
+        info!("Finished, saved to path: {:?}", output_path);
+    });
 }
 
-macro_rules! get_size {
-    ($props:expr, $name:expr) => {
-        match &$props[$name] {
-            Variant::Vector3(prop) => prop.to_owned(),
-            _ => panic!("Property has invalid type"),
+fn build_downloader(options: &Options) -> AssetDownloader {
+    let mut downloader = AssetDownloader::new(&options.cache_dir)
+        .with_offline(options.offline)
+        .with_revalidate(options.revalidate)
+        .with_rate_limiter(Arc::new(RateLimiter::new(options.rate_limit)));
+    if let Some(url) = &options.asset_delivery_url {
+        downloader = downloader.with_base_url(url);
+    }
+    if let Some(seconds) = options.deadline {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(seconds);
+        downloader = downloader.with_deadline(deadline);
+    }
+    match &options.remote_cache {
+        Some(url) => {
+            let remote = RemoteCache::new(url)
+                .unwrap_or_else(|err| {
+                    fail(options, format!("Invalid remote cache {:?}: {}", url, err))
+                })
+                .with_token(options.remote_cache_token.clone());
+            downloader.with_remote_cache(Arc::new(remote))
         }
-    };
+        None => downloader,
+    }
+}
+
+fn build_provider(options: &Options, downloader: &AssetDownloader) -> Arc<dyn AssetProvider> {
+    let remote = Arc::new(downloader.clone());
+    match &options.content_dir {
+        Some(content_dir) => Arc::new(LocalContentProvider::new(content_dir, remote)),
+        None => remote,
+    }
+}
+
+fn open_signature_cache(options: &Options) -> Option<Arc<SignatureCache>> {
+    options
+        .signature_cache
+        .as_ref()
+        .map(|path| Arc::new(SignatureCache::open(path)))
 }
 
-macro_rules! get_cframe {
-    ($props:expr) => {
-        match &$props["CFrame"] {
-            Variant::CFrame(prop) => prop.to_owned(),
-            _ => panic!("Property has invalid type"),
+// fatal errors still leave a summary behind when one was asked for, so a
+// build script never has to tell a crash from a missing file
+fn fail(options: &Options, err: impl fmt::Display) -> ! {
+    error!("{}", err);
+    if let Some(summary_path) = &options.summary_path {
+        if let Err(err) = RunSummary::fatal(err.to_string()).write(summary_path) {
+            error!("Could not write run summary {:?}: {}", summary_path, err);
         }
-    };
+    }
+    std::process::exit(EXIT_FATAL);
 }
 
-macro_rules! modify_property {
-    ($props:expr, $prop:expr, $value:expr) => {
-        if let Some(mut_prop) = $props.get_mut($prop) {
-            *mut_prop = $value;
-        // Variant::Content(rbx_types::Content::from(new_mesh.asset_id.clone()))
-        } else {
-            panic!(&format!("Failed to modify property {:?} on mesh", $prop))
+// runs that got to the end leave through here, the summary is written
+// before anything but a clean run exits with its code
+fn exit_with(options: &Options, mut summary: RunSummary) {
+    let exit_code = summary.finish();
+    if let Some(summary_path) = &options.summary_path {
+        if let Err(err) = summary.write(summary_path) {
+            error!("Could not write run summary {:?}: {}", summary_path, err);
         }
+        info!("Wrote run summary to {:?}", summary_path);
+    }
+    if exit_code != EXIT_OK {
+        std::process::exit(exit_code);
+    }
+}
+
+fn open_place(options: &Options, input_path: &str) -> Place {
+    Place::open(input_path).unwrap_or_else(|err| {
+        fail(
+            options,
+            format!("Could not open place {:?}: {}", input_path, err),
+        )
+    })
+}
+
+// an offline run that had to skip assets isn't trusted, the list is what
+// needs priming with one online run
+fn exit_if_missing(options: &Options, downloader: &AssetDownloader) {
+    let missing = downloader.missing_assets();
+    if missing.is_empty() {
+        return;
+    }
+
+    for asset_id in missing.iter() {
+        error!("  {}", asset_id);
+    }
+    fail(
+        options,
+        format!(
+            "{:?} assets are not in the cache, run once without --offline to fetch them",
+            missing.len()
+        ),
+    );
+}
+
+// mesh commands take either a file on disk or anything a MeshId can hold
+async fn load_mesh(options: &Options, target: &str) -> RobloxMesh {
+    let mesh = if Path::new(target).is_file() {
+        let data = std::fs::read(target).unwrap_or_else(|err| {
+            fail(
+                options,
+                format!("Could not read mesh {:?}: {}", target, err),
+            )
+        });
+        RobloxMesh::from_cursor(&mut Cursor::new(data))
+    } else {
+        let provider = build_provider(options, &build_downloader(options));
+        RobloxMesh::from_provider(provider.as_ref(), normalize_content(target)).await
     };
+
+    match mesh {
+        Ok(mesh) => mesh,
+        Err(err) => fail(
+            options,
+            format!("Could not load mesh {:?}: {}", target, err),
+        ),
+    }
 }
 
-struct CachedMesh {
-    cframe: CFrame,
-    mesh: RobloxMesh,
-    asset_id: String,
-    init_size: Vector3,
-    size: Vector3,
+async fn run_inspect(options: &Options) {
+    let target = &options.input_path;
+    let mesh = load_mesh(options, target).await;
+
+    let header = &mesh.header;
+    let bounds = &mesh.bounding_box_size;
+    println!("{}", target);
+    println!("  meshes:         {:?}", header.num_meshes);
+    println!("  vertices:       {:?}", header.num_verts);
+    println!("  faces:          {:?}", header.num_faces);
+    println!("  lods:           {:?} {:?}", header.num_lods, mesh.lods);
+    println!("  bones:          {:?}", header.num_bones);
+    println!("  skin data:      {:?}", header.num_skin_data);
+    println!("  name table:     {:?} bytes", header.name_table_size);
+    println!("  triangles:      {:?}", mesh.triangles);
+    println!(
+        "  bounds:         ({:?}, {:?}, {:?}) to ({:?}, {:?}, {:?})",
+        bounds.min.x, bounds.min.y, bounds.min.z, bounds.max.x, bounds.max.y, bounds.max.z
+    );
+    println!("  extremes:       {:?}", mesh.bounding_box);
+    println!("  geometry bytes: {:?}", mesh.geometry_bytes());
+    println!("  volume:         {:.4}", mesh.volume());
+    println!("  surface area:   {:.4}", mesh.surface_area());
+    println!("  hash:           {:?}", mesh.hash);
+    println!("  uv hash:        {:08x}", mesh.uv_hash);
 }
 
-fn open_rbx_place(input_path: String) -> Result<WeakDom, Box<dyn Error + 'static>> {
-    let input_fp = Path::new(&input_path);
-    let input_file = BufReader::new(File::open(input_fp)?);
-    Ok(rbx_binary::from_reader_default(input_file)?)
+fn write_mesh(options: &Options, mesh: &RobloxMesh) {
+    let data = mesh
+        .to_bytes()
+        .unwrap_or_else(|err| fail(options, format!("Could not serialize mesh: {}", err)));
+    write_atomic(&options.output_path, &data)
+        .unwrap_or_else(|err| fail(options, format!("Could not write mesh: {}", err)));
 }
 
-fn save_rbx_place(output_path: String, dom: &WeakDom) -> Result<(), Box<dyn Error + 'static>> {
-    let output_fp = Path::new(&output_path);
-    let output_file = BufWriter::new(File::create(output_fp)?);
-    // write_log(format!("{:#?}", dom));
+async fn run_export(options: &Options) {
+    let mesh = load_mesh(options, &options.input_path).await;
+    let name = Path::new(&options.input_path)
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or("Mesh");
 
-    Ok(rbx_binary::to_writer_default(
-        output_file,
-        dom,
-        dom.root().children(),
-    )?)
+    export_glb(&mesh, name, &options.output_path)
+        .unwrap_or_else(|err| fail(options, format!("Could not write glTF: {}", err)));
+    info!(
+        "Exported {:?} vertices and {:?} bones to {:?}",
+        mesh.vertices.len(),
+        mesh.header.num_bones,
+        options.output_path
+    );
 }
 
-fn write_log(string: String) -> Result<(), Box<dyn Error + 'static>> {
-    let output_fp = Path::new("log.txt");
-    let mut output_file = File::create(output_fp)?;
-    output_file.write_all(string.as_bytes())?;
-    Ok(())
+async fn run_fix_mesh(options: &Options) {
+    let mut mesh = load_mesh(options, &options.input_path).await;
+    let stats = cleanup_mesh(&mut mesh, DEFAULT_WELD_EPSILON);
+    write_mesh(options, &mesh);
+    info!(
+        "Welded {:?} vertices and dropped {:?} degenerate faces, saved to {:?}",
+        stats.welded_vertices, stats.degenerate_faces, options.output_path
+    );
 }
 
-fn get_children_recursive(children: &mut Vec<Ref>, dom: &WeakDom, referent: Ref) {
-    let ref_inst = dom.get_by_ref(referent).expect("ref-inst");
-    let ref_children = ref_inst.children();
-    for child in ref_children {
-        children.push(child.clone());
-        get_children_recursive(children, dom, child.clone());
-    }
+fn run_import(options: &Options) {
+    let mesh = read_obj_file(&options.input_path)
+        .unwrap_or_else(|err| fail(options, format!("Could not import obj: {}", err)));
+    write_mesh(options, &mesh);
+    info!(
+        "Imported {:?} vertices and {:?} faces to {:?}",
+        mesh.vertices.len(),
+        mesh.faces.len(),
+        options.output_path
+    );
 }
 
-fn filter_mesh_parts(dom: &WeakDom, refs: Vec<Ref>) -> Vec<Ref> {
-    let mut mesh_parts = Vec::<Ref>::new();
+async fn run_optimize(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let downloader = build_downloader(options);
+    let provider = build_provider(options, &downloader);
+    let uploader = OpenCloudClient::new(options.api_key.clone().expect("api-key"), downloader)
+        .with_creator(options.upload_creator.expect("upload-creator"));
 
-    for referent in refs {
-        let child = dom.get_by_ref(referent).expect("referent");
-        if !child.properties.contains_key("TextureID") || !child.properties.contains_key("MeshId") {
-            continue;
+    let result = match options.command {
+        Command::Decimate => {
+            decimate_place(
+                provider.as_ref(),
+                &uploader,
+                &mut place,
+                options.max_triangles,
+            )
+            .await
         }
+        _ => strip_place_lods(provider.as_ref(), &uploader, &mut place).await,
+    };
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => fail(options, err),
+    };
+    info!(
+        "Optimized {:?} meshes ({:?} faces, {:?} bytes removed), rewrote {:?} parts",
+        result.meshes, result.faces_removed, result.bytes_saved, result.rewritten
+    );
 
-        mesh_parts.push(referent);
+    if let Some(mapping_path) = &options.mapping_path {
+        write_mapping(mapping_path, &result.mapping).unwrap_or_else(|err| {
+            fail(
+                options,
+                format!("Could not write mapping {:?}: {}", mapping_path, err),
+            )
+        });
     }
 
-    mesh_parts
+    save_place(&place, &options);
 }
 
-fn get_workspace_children(dom: &WeakDom) -> Vec<Ref> {
-    let data_model = dom.root();
-    let mut children = Vec::<Ref>::new();
-    let workspace = *data_model
-        .children()
-        .iter()
-        .find(|x| dom.get_by_ref(*x.to_owned()).unwrap().name == "Workspace")
-        .expect("workspace");
+async fn run_combine(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let downloader = build_downloader(options);
+    let provider = build_provider(options, &downloader);
+    let uploader = OpenCloudClient::new(options.api_key.clone().expect("api-key"), downloader)
+        .with_creator(options.upload_creator.expect("upload-creator"));
+    let combine_options = CombineOptions {
+        max_part_size: options.max_part_size,
+        cell_size: options.combine_cell,
+        path_filter: options.path_filter.clone(),
+    };
+
+    let result =
+        match combine_place(provider.as_ref(), &uploader, &mut place, &combine_options).await {
+            Ok(result) => result,
+            Err(err) => fail(options, err),
+        };
+    info!(
+        "Combined {:?} parts into {:?} meshes ({:?} triangles), removed {:?} parts",
+        result.parts_combined, result.meshes, result.triangles, result.parts_removed
+    );
+    if result.failed_groups > 0 {
+        warn!("{:?} groups could not be uploaded", result.failed_groups);
+    }
 
-    get_children_recursive(&mut children, dom, workspace);
-    filter_mesh_parts(dom, children)
+    save_place(&place, &options);
 }
 
-async fn download_meshs(dom: &WeakDom, refs: Vec<Ref>) -> Result<(), ()> {
-    let mut handles = vec![];
-    let master_semaphore = Arc::new(tokio::sync::Semaphore::new(4));
+async fn run_fix_sizes(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let downloader = build_downloader(options);
+    let provider = build_provider(options, &downloader);
 
-    for referent in refs {
-        if let Some(child) = dom.get_by_ref(referent) {
-            let mesh_id = get_content!(child.properties, "MeshId").clone();
-            let semaphore = master_semaphore.clone();
-            handles.push(tokio::spawn(async move {
-                match semaphore.acquire().await {
-                    Ok(_) => download_asset(mesh_id).await.is_ok(),
-                    Err(_) => false,
-                }
-            }));
-        } else {
-            return Err(());
-        }
+    let result = match fix_place_sizes(provider.as_ref(), &mut place).await {
+        Ok(result) => result,
+        Err(err) => fail(options, err.to_string()),
+    };
+    info!(
+        "Checked {:?} mesh parts, repaired {:?}",
+        result.checked, result.repaired
+    );
+    if result.failed > 0 {
+        warn!("{:?} mesh parts could not be checked", result.failed);
     }
+    exit_if_missing(options, &downloader);
 
-    let futures = futures::future::join_all(handles).await;
-    if let Some(_) = futures.iter().find(|x| match x {
-        Ok(item) => !item.clone(),
-        Err(_) => true,
-    }) {
-        Err(())
-    } else {
-        Ok(())
+    save_place(&place, &options);
+}
+
+fn run_sanitize(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let mesh_parts = place.mesh_parts();
+
+    let report = sanitize_parts(&mut place.dom, &mesh_parts, true);
+    for issue in report.issues.iter() {
+        warn!("{}.{} {}", issue.path, issue.property, issue.problem);
     }
+    info!(
+        "Checked {:?} mesh parts, fixed {:?} values",
+        report.checked,
+        report.issues.len()
+    );
+
+    save_place(&place, &options);
 }
 
-#[tokio::main]
-async fn main() {
-    let input_path = std::env::args().nth(1).expect("input-path");
-    let output_path = std::env::args().nth(2).expect("output-path");
-
-    println!("Opening place..");
-    let mut dom = open_rbx_place(input_path).expect("could not open place");
-    println!("Getting children...");
-    let children = get_workspace_children(&dom);
-    println!("Found {:?} meshes", children.len());
-
-    print!("Downloading meshes... ");
-    if let Err(_) = download_meshs(&dom, children.clone()).await {
-        eprintln!("Error downloading one or more assets");
-        return;
+fn run_divergence(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let fix = !options.output_path.is_empty();
+
+    let report = find_divergence(&mut place, fix);
+    for divergence in report.divergences.iter() {
+        let values = divergence
+            .values
+            .iter()
+            .map(|(value, count)| format!("{} x{}", value, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        warn!(
+            "{} {} differs: {}",
+            divergence.mesh_id, divergence.property, values
+        );
     }
-    println!("Done!");
+    info!(
+        "Found {:?} divergent properties across {:?} shared meshes",
+        report.divergences.len(),
+        report.meshes
+    );
 
-    let mut textures = BTreeMap::<i32, CachedMesh>::new();
-    let _ = std::fs::create_dir("cache");
+    if fix {
+        info!("Normalized {:?} values", report.normalized);
+        save_place(&place, &options);
+    }
+}
 
-    for child_ref in children {
-        let child = dom.get_by_ref_mut(child_ref).expect("workspace-child");
-        let texture_id = get_content!(child.properties, "TextureID");
-        let mesh_id = get_content!(child.properties, "MeshId");
-        let init_size = get_size!(child.properties, "InitialSize");
-        let size = get_size!(child.properties, "Size");
-        let cframe = get_cframe!(child.properties);
+fn run_policy(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let mesh_parts = place.mesh_parts();
 
-        if texture_id.trim() == "" || mesh_id.trim() == "" {
-            println!(
-                "Instance {:?}, does not have valid textureId/meshId, skipping",
-                child.name.clone()
-            );
-            continue;
-        }
+    let result = apply_policies(&mut place.dom, &mesh_parts, &options.policies);
+    info!(
+        "Applied {:?} policies to {:?} mesh parts, changed {:?}",
+        options.policies.len(),
+        result.parts,
+        result.changed
+    );
 
-        let mesh = RobloxMesh::from_asset_id(mesh_id.clone())
-            .await
-            .expect("download-mesh");
+    save_place(&place, &options);
+}
 
+async fn run_stats(options: &Options, downloader: &AssetDownloader) {
+    info!("Opening place..");
+    let place = open_place(options, &options.input_path);
+    let provider = build_provider(options, downloader);
+    let cache = open_signature_cache(options);
+    let stats = match collect_stats(provider.as_ref(), cache.as_deref(), &place, options.top).await
+    {
+        Ok(stats) => stats,
+        Err(err) => fail(options, err),
+    };
+    exit_if_missing(options, downloader);
+
+    println!("{}", options.input_path);
+    println!("  mesh parts:         {:?}", stats.mesh_parts);
+    println!("  unique mesh ids:    {:?}", stats.unique_mesh_ids);
+    println!("  unique texture ids: {:?}", stats.unique_texture_ids);
+    println!(
+        "  duplicate clusters: {:?} ({:?} redundant assets)",
+        stats.duplicate_clusters, stats.duplicate_assets
+    );
+    println!("  total triangles:    {:?}", stats.total_triangles);
+    println!("  download size:      {:?} bytes", stats.download_bytes);
+    if stats.failed > 0 {
+        println!("  unavailable meshes: {:?}", stats.failed);
+    }
+
+    println!("  heaviest meshes:");
+    for mesh in stats.heaviest.iter() {
         println!(
-            "num_meshes={:?}, num_verts={:?}, num_faces={:?}, num_lod={:?}, num_bones={:?}, nts={:?}, nsd={:?}, stub={:?}, triangles={:?}, hash={:?}",
-            mesh.header.num_meshes, mesh.header.num_verts, mesh.header.num_faces, mesh.header.num_lods, mesh.header.num_bones, mesh.header.name_table_size, mesh.header.num_skin_data, mesh.header.stub,
-            mesh.triangles, mesh.hash
+            "    {} {:?} triangles, {:?} instances, {:?} bytes, volume {:.2}, area {:.2}",
+            mesh.asset_id,
+            mesh.triangles,
+            mesh.instances,
+            mesh.download_bytes,
+            mesh.volume,
+            mesh.surface_area
         );
-        println!("bounding_box={:#?}", mesh.bounding_box);
+    }
 
-        if textures.contains_key(&mesh.hash) {
-            let new_mesh = &textures[&mesh.hash];
+    println!("  texture memory:     {:?} bytes", stats.texture_memory);
+    println!(
+        "  texture download:   {:?} bytes",
+        stats.texture_download_bytes
+    );
+    if stats.failed_textures > 0 {
+        println!("  unavailable textures: {:?}", stats.failed_textures);
+    }
+    println!("  heaviest textures:");
+    for texture in stats.heaviest_textures.iter() {
+        println!(
+            "    {} {}x{} {}, {:?} bytes, {:?} instances",
+            texture.asset_id,
+            texture.width,
+            texture.height,
+            texture.format,
+            texture.memory_bytes,
+            texture.instances
+        );
+    }
 
-            modify_property!(
-                child.properties,
-                "MeshId",
-                Variant::Content(rbx_types::Content::from(new_mesh.asset_id.clone()))
-            );
+    println!(
+        "  clusters sharing one material: {:?} of {:?}",
+        stats.shared_material_clusters, stats.duplicate_clusters
+    );
+    for cluster in stats.cluster_materials.iter() {
+        println!(
+            "    {} {:?} assets, {:?} instances, {:?} materials",
+            cluster.canonical, cluster.assets, cluster.instances, cluster.materials
+        );
+    }
+}
 
-            modify_property!(child.properties, "Size", Variant::Vector3(new_mesh.size));
-            modify_property!(
-                child.properties,
-                "InitialSize",
-                Variant::Vector3(new_mesh.init_size)
-            );
+// only fills the asset cache, so a later run (or --offline on another
+// machine with the cache copied over) never waits on the network
+async fn run_prefetch(options: &Options) {
+    info!("Opening place..");
+    let place = open_place(options, &options.input_path);
+    let provider = build_provider(options, &build_downloader(options));
+    let progress = Progress::new(options.quiet);
+    let bar = progress.phase("Fetching", 0);
+    let result = match prefetch_place(provider.as_ref(), &place, &bar).await {
+        Ok(result) => result,
+        Err(err) => fail(options, err),
+    };
+
+    info!(
+        "Prefetched {:?} of {:?} assets ({:?} bytes)",
+        result.fetched, result.assets, result.bytes
+    );
+    for (asset_id, err) in result.failed.iter() {
+        error!("  {}: {}", asset_id, err);
+    }
 
-            /*let rotation = mesh.calculate_rotation(&new_mesh.mesh);
-            modify_property!(
-                child.properties,
-                "CFrame",
-                Variant::CFrame(cframe.mult(CFrame::angles(0.0, rotation.y, 0.0)))
+    let mut summary = RunSummary::default();
+    summary.failed = result.failed.len();
+    summary.failed_assets = result.failed.iter().map(|x| x.0.clone()).collect();
+    summary.interrupted = shutdown::interrupted();
+    exit_with(options, summary);
+}
+
+// for artists auditing a map's geometry or textures outside of studio, the
+// place itself is left alone
+async fn run_extract(options: &Options) {
+    info!("Opening place..");
+    let place = open_place(options, &options.input_path);
+    let downloader = build_downloader(options);
+    let provider = build_provider(options, &downloader);
+    let out_dir = options.extract_dir.as_ref().expect("out");
+    let progress = Progress::new(options.quiet);
+    let bar = progress.phase("Extracting", 0);
+    let (extracted, kind) = match options.command {
+        Command::ExtractTextures => (
+            extract_textures(provider.as_ref(), &place, Path::new(out_dir), &bar).await,
+            "textures",
+        ),
+        _ => (
+            extract_meshes(
+                provider.as_ref(),
+                &place,
+                Path::new(out_dir),
+                options.mesh_format,
+                &bar,
+            )
+            .await,
+            "meshes",
+        ),
+    };
+    let result = match extracted {
+        Ok(result) => result,
+        Err(err) => fail(options, err),
+    };
+    exit_if_missing(options, &downloader);
+
+    info!(
+        "Extracted {:?} of {:?} {} to {:?}",
+        result.written.len(),
+        result.assets,
+        kind,
+        out_dir
+    );
+    for (asset_id, err) in result.failed.iter() {
+        error!("  {}: {}", asset_id, err);
+    }
+
+    let mut summary = RunSummary::default();
+    summary.failed = result.failed.len();
+    summary.failed_assets = result.failed.iter().map(|x| x.0.clone()).collect();
+    summary.interrupted = shutdown::interrupted();
+    exit_with(options, summary);
+}
+
+fn run_apply(options: &Options) {
+    let mapping_path = options.mapping_path.as_ref().expect("mapping-path");
+    let mapping = read_mapping(mapping_path).unwrap_or_else(|err| {
+        fail(
+            options,
+            format!("Could not read mapping {:?}: {}", mapping_path, err),
+        )
+    });
+
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let children = place.mesh_parts();
+
+    let rewritten = apply_mapping(&mut place.dom, &children, &mapping);
+    info!(
+        "Rewrote {:?} of {:?} meshes using {:?} mappings",
+        rewritten,
+        children.len(),
+        mapping.len()
+    );
+
+    save_place(&place, &options);
+}
+
+fn run_revert(options: &Options) {
+    info!("Opening place..");
+    let mut place = open_place(options, &options.input_path);
+    let descendants = place.descendants();
+
+    if let Some(journal_path) = &options.journal_path {
+        let journal = ChangeJournal::read(journal_path).unwrap_or_else(|err| {
+            fail(
+                options,
+                format!("Could not read journal {:?}: {}", journal_path, err),
+            )
+        });
+        let reverted = journal.revert(&mut place.dom, &descendants);
+        info!(
+            "Reverted {:?} of {:?} journaled changes",
+            reverted,
+            journal.entries.len()
+        );
+    } else {
+        // without a journal only the MeshIds stamped into attributes can be
+        // brought back, sizes and cframes stay as they are
+        let restored = restore_originals(&mut place.dom, &descendants);
+        info!(
+            "Restored {:?} MeshIds from {} attributes",
+            restored, ORIGINAL_MESH_ID
+        );
+    }
+
+    save_place(&place, &options);
+}
+
+fn build_deduplicator(options: &Options, downloader: &AssetDownloader) -> MeshDeduplicator {
+    let mut deduplicator = MeshDeduplicator::new(DedupeOptions {
+        compensate_attachments: options.compensate_attachments,
+        canonical_policy: options.canonical_policy.clone(),
+        preferred_owner: options.preferred_owner,
+        stamp_attributes: options.stamp_attributes,
+        database_path: options.database_path.clone(),
+        fail_fast: options.fail_fast,
+        quiet: options.quiet,
+        weld: options.weld,
+        uv_signature: options.uv_signature,
+        path_filter: options.path_filter.clone(),
+        tag_filter: options.tag_filter.clone(),
+        ignored_assets: options.ignored_assets.clone(),
+        merge_groups: options.merge_groups.clone(),
+        min_duplicates: options.min_duplicates,
+        scope: options.scope,
+        fuzzy: options.fuzzy,
+        min_confidence: options.min_confidence,
+        min_triangles: options.min_triangles,
+    })
+    .with_provider(build_provider(options, downloader))
+    .with_downloader(downloader.clone());
+    // a creator alone is there for reupload and publish, uploading canonicals
+    // has to be asked for
+    if let (true, Some(creator)) = (options.upload_canonicals, options.upload_creator) {
+        let api_key = options.api_key.clone().expect("api-key");
+        deduplicator = deduplicator.with_uploader(Arc::new(
+            OpenCloudClient::new(api_key, downloader.clone()).with_creator(creator),
+        ));
+    }
+    if options.interactive {
+        deduplicator = deduplicator.with_reviewer(Arc::new(review::TerminalReviewer));
+    }
+    if let Some(cache) = open_signature_cache(options) {
+        deduplicator = deduplicator.with_signature_cache(cache);
+    }
+    deduplicator
+}
+
+// thumbnails go into a folder next to the report, `report.html` gets
+// `report_thumbnails/`, which the html report then links to
+async fn write_report(
+    options: &Options,
+    downloader: &AssetDownloader,
+    mut report: DedupeReport,
+    report_path: &str,
+) {
+    if options.thumbnails {
+        let report_fp = Path::new(report_path);
+        let stem = report_fp
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = report_fp.with_file_name(format!("{}_thumbnails", stem));
+        match fetch_thumbnails(downloader, &report, &dir).await {
+            Ok(fetched) => {
+                info!("Fetched {:?} thumbnails into {:?}", fetched, dir);
+                report.thumbnail_dir = Some(dir);
+            }
+            Err(err) => warn!("Could not fetch thumbnails: {}", err),
+        }
+    }
+
+    report.write(report_path).unwrap_or_else(|err| {
+        fail(
+            options,
+            format!("Could not write report {:?}: {}", report_path, err),
+        )
+    });
+    info!("Wrote duplicate report to {:?}", report_path);
+}
+
+fn log_result(result: &DedupeResult) {
+    let memory = &result.report.memory;
+    info!(
+        "Estimated {:?} bytes of client memory saved ({:?} vertex, {:?} index, {:?} texture, {:?} union)",
+        memory.total(),
+        memory.vertex_bytes,
+        memory.index_bytes,
+        memory.texture_bytes,
+        memory.union_bytes
+    );
+    info!(
+        "Compensated {:?} joint offsets and {:?} attachments",
+        result.updated_joints, result.updated_attachments
+    );
+
+    for (map, savings) in result.map_savings.iter() {
+        info!(
+            "{}: replaced {:?} references to {:?} duplicate assets, saving {:?} bytes",
+            map, savings.replaced, savings.unique_removed, savings.bytes_saved
+        );
+    }
+
+    for cluster in result.union_clusters.iter() {
+        info!(
+            "Union {:?} used by {:?} instances, replaced {:?}",
+            cluster.canonical_id, cluster.instances, cluster.duplicate_ids
+        );
+    }
+
+    if !result.failures.is_empty() {
+        info!(
+            "{:?} meshes failed and their instances were left untouched:",
+            result.failures.len()
+        );
+        for failure in result.failures.iter() {
+            info!(
+                "  {} ({:?} instances): {}",
+                failure.asset_id, failure.instances, failure.error
             );
+        }
+    }
+}
 
-            println!("rotation={:?}", rotation);
-            println!(
-                "Converting {:?} to {:?}",
-                mesh_id,
-                new_mesh.asset_id.clone()
-            );*/
-            println!("Id: {:?}", child.properties["MeshId"]);
+// goes to stderr with the logs, the place itself may be on stdout
+fn print_timings(timings: &Timings) {
+    let total = timings.total().as_secs_f64();
+    eprintln!("timings:");
+    for (phase, elapsed) in timings.phases() {
+        let elapsed = elapsed.as_secs_f64();
+        let share = if total > 0.0 {
+            elapsed / total * 100.0
         } else {
-            textures.insert(
-                mesh.hash,
-                CachedMesh {
-                    mesh: mesh,
-                    asset_id: mesh_id.clone(),
-                    cframe: cframe,
-                    init_size: init_size,
-                    size: size,
-                },
-            );
-            println!("Cached {:?}", mesh_id);
+            0.0
+        };
+        eprintln!("  {:<20}{:>9.3}s {:>5.1}%", phase, elapsed, share);
+    }
+    eprintln!("  {:<20}{:>9.3}s", "total", total);
+}
+
+// reads the written file back so a serializer that drops or mangles
+// properties fails the run instead of shipping a broken place
+fn verify_output(options: &Options, place: &Place, output_path: &str, journal: &ChangeJournal) {
+    let reopened = open_place(options, output_path);
+    let report = verify_round_trip(&place.dom, &reopened.dom, journal);
+    if !report.is_ok() {
+        for problem in report.problems.iter() {
+            error!("{}", problem);
+        }
+        fail(
+            options,
+            format!("{:?} failed round-trip verification", output_path),
+        );
+    }
+
+    info!(
+        "Verified {:?} instances and {:?} changes in {:?}",
+        report.instances, report.checked_changes, output_path
+    );
+}
+
+async fn run_rojo(
+    options: &Options,
+    downloader: &AssetDownloader,
+    deduplicator: &MeshDeduplicator,
+) {
+    info!("Scanning project {:?}..", options.input_path);
+    let result = match dedupe_project(deduplicator, &options.input_path).await {
+        Ok(result) => result,
+        Err(err) => fail(options, err),
+    };
+    exit_if_missing(options, downloader);
+
+    if let Some(report_path) = &options.report_path {
+        write_report(
+            options,
+            downloader,
+            result.dedupe.report.clone(),
+            report_path,
+        )
+        .await;
+    }
+
+    if let Some(mapping_path) = &options.mapping_path {
+        write_mapping(mapping_path, &result.dedupe.mapping).unwrap_or_else(|err| {
+            fail(
+                options,
+                format!("Could not write mapping {:?}: {}", mapping_path, err),
+            )
+        });
+    }
+
+    log_result(&result.dedupe);
+    info!(
+        "Rewrote {:?} of {:?} model files and {:?} MeshIds across {:?} json models",
+        result.rewritten_models, result.models, result.json_rewritten, result.json_models
+    );
+
+    let mut summary = RunSummary::default();
+    summary.add(&result.dedupe);
+    exit_with(options, summary);
+}
+
+// every place shares the deduplicator, so downloads land in one cache and the
+// signature database keeps canonicals consistent across the whole batch
+async fn run_batch(
+    options: &Options,
+    downloader: &AssetDownloader,
+    deduplicator: &MeshDeduplicator,
+    out_dir: &str,
+) {
+    std::fs::create_dir_all(out_dir)
+        .unwrap_or_else(|err| fail(options, format!("Could not create {:?}: {}", out_dir, err)));
+
+    let runs = options.inputs.iter().map(|input_path| async move {
+        let file_name = Path::new(input_path)
+            .file_name()
+            .expect("input-file-name")
+            .to_string_lossy()
+            .to_string();
+        let output_path = Path::new(out_dir).join(&file_name);
+
+        let span = info_span!("place", path = %input_path);
+        let result = async {
+            if shutdown::interrupted() {
+                return Err("interrupted before starting".into());
+            }
+            let open_start = Instant::now();
+            let mut place = Place::open(input_path)?;
+            let open_elapsed = open_start.elapsed();
+            let mut result = deduplicator.run(&mut place).await?;
+            let mut timings = Timings::default();
+            timings.add("open", open_elapsed);
+            timings.merge(&result.timings);
+            result.timings = timings;
+            if result.interrupted {
+                return Err("interrupted, place was not written".into());
+            }
+            let save_start = Instant::now();
+            place.save(&output_path.to_string_lossy())?;
+            result.timings.record("save", save_start);
+            Ok::<_, FixerError>(result)
         }
+        .instrument(span)
+        .await;
+        (file_name, output_path, result)
+    });
+    let results = futures::stream::iter(runs)
+        .buffered(options.jobs.max(1))
+        .collect::<Vec<_>>()
+        .await;
+    exit_if_missing(options, downloader);
+
+    let mut report = DedupeReport::default();
+    let mut mapping = AssetMapping::new();
+    let mut timings = Timings::default();
+    let mut summary = RunSummary::default();
+    let mut failed = 0;
+    for (file_name, output_path, result) in results {
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => {
+                error!("{}: {}", file_name, err);
+                summary.add_failed_place();
+                failed += 1;
+                continue;
+            }
+        };
+        summary.add(&result);
+
+        info!("{}: saved to {:?}", file_name, output_path);
+        log_result(&result);
+        timings.merge(&result.timings);
+        report.merge(&file_name, result.report);
+        mapping.extend(result.mapping);
+    }
+
+    if let Some(report_path) = &options.report_path {
+        write_report(options, downloader, report, report_path).await;
+    }
+
+    if let Some(mapping_path) = &options.mapping_path {
+        write_mapping(mapping_path, &mapping).unwrap_or_else(|err| {
+            fail(
+                options,
+                format!("Could not write mapping {:?}: {}", mapping_path, err),
+            )
+        });
+        info!("Wrote {:?} mappings to {:?}", mapping.len(), mapping_path);
+    }
+
+    info!(
+        "Processed {:?} of {:?} places",
+        options.inputs.len() - failed,
+        options.inputs.len()
+    );
+    if options.timings {
+        print_timings(&timings);
+    }
+    summary.interrupted = shutdown::interrupted();
+    exit_with(options, summary);
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+    init_logging(&options);
+    shutdown::install_handler(options.summary_path.clone());
+    match options.command {
+        Command::Apply => return run_apply(&options),
+        Command::Revert => return run_revert(&options),
+        Command::Inspect => return run_inspect(&options).await,
+        Command::Stats => return run_stats(&options, &build_downloader(&options)).await,
+        Command::Prefetch => return run_prefetch(&options).await,
+        Command::ExtractMeshes | Command::ExtractTextures => return run_extract(&options).await,
+        Command::Export => return run_export(&options).await,
+        Command::Import => return run_import(&options),
+        Command::FixMesh => return run_fix_mesh(&options).await,
+        Command::StripLods | Command::Decimate => return run_optimize(&options).await,
+        Command::Combine => return run_combine(&options).await,
+        Command::FixSizes => return run_fix_sizes(&options).await,
+        Command::Sanitize => return run_sanitize(&options),
+        Command::Divergence => return run_divergence(&options),
+        Command::Policy => return run_policy(&options),
+        Command::Dedupe | Command::Rojo | Command::Serve | Command::Server => {}
+    }
+
+    let downloader = build_downloader(&options);
+    let deduplicator = build_deduplicator(&options, &downloader);
+    if options.command == Command::Rojo {
+        return run_rojo(&options, &downloader, &deduplicator).await;
+    }
+    if options.command == Command::Serve {
+        return serve::run_serve(&deduplicator, options.port).await;
+    }
+    if options.command == Command::Server {
+        let token = options.server_token.as_deref();
+        return server::run_server(&deduplicator, &options.bind, options.port, token).await;
+    }
+    if let Some(watch_dir) = &options.watch_dir {
+        return watch::run_watch(&deduplicator, watch_dir).await;
+    }
+    if let Some(out_dir) = &options.out_dir {
+        return run_batch(&options, &downloader, &deduplicator, out_dir).await;
+    }
 
-        println!("{:?}", child.name.clone());
+    info!("Opening place..");
+    let open_start = Instant::now();
+    let mut place = open_place(&options, &options.input_path);
+    let open_elapsed = open_start.elapsed();
+    let mut result = match deduplicator.run(&mut place).await {
+        Ok(result) => result,
+        Err(err) => fail(&options, err),
+    };
+    exit_if_missing(&options, &downloader);
+    // the open happens before the pipeline, so it goes in front of the rest
+    let mut timings = Timings::default();
+    timings.add("open", open_elapsed);
+    timings.merge(&result.timings);
+    result.timings = timings;
+
+    if let Some(report_path) = &options.report_path {
+        write_report(&options, &downloader, result.report.clone(), report_path).await;
+    }
+
+    if let Some(mapping_path) = &options.mapping_path {
+        write_mapping(mapping_path, &result.mapping).unwrap_or_else(|err| {
+            fail(
+                &options,
+                format!("Could not write mapping {:?}: {}", mapping_path, err),
+            )
+        });
+        info!(
+            "Wrote {:?} mappings to {:?}",
+            result.mapping.len(),
+            mapping_path
+        );
+    }
+
+    log_result(&result);
+
+    if let Some(journal_path) = &options.journal_path {
+        result.journal.write(journal_path).unwrap_or_else(|err| {
+            fail(
+                &options,
+                format!("Could not write journal {:?}: {}", journal_path, err),
+            )
+        });
+        info!(
+            "Wrote {:?} journal entries to {:?}",
+            result.journal.entries.len(),
+            journal_path
+        );
+    }
+
+    // the reports above cover what got done, but a half deduped place is
+    // never written out
+    if result.interrupted {
+        warn!("Interrupted, {:?} was not written", options.output_path);
+        let mut summary = RunSummary::default();
+        summary.add(&result);
+        summary.interrupted = true;
+        return exit_with(&options, summary);
+    }
+
+    if let Some(luau_path) = &options.luau_path {
+        write_migration_script(&result.journal, luau_path).unwrap_or_else(|err| {
+            fail(
+                &options,
+                format!("Could not write {:?}: {}", luau_path, err),
+            )
+        });
+        info!(
+            "Wrote a migration script for {:?} changes to {:?}",
+            result.journal.entries.len(),
+            luau_path
+        );
+    }
+
+    // with only --luau the script is the output
+    if !options.output_path.is_empty() {
+        let save_start = Instant::now();
+        save_place(&place, &options);
+        result.timings.record("save", save_start);
+    }
+    if options.verify && !options.output_path.is_empty() && options.output_path != STDIO_PATH {
+        let verify_start = Instant::now();
+        verify_output(&options, &place, &options.output_path, &result.journal);
+        result.timings.record("verify", verify_start);
+    }
+    if options.timings {
+        print_timings(&result.timings);
+    }
+
+    if let Some((universe_id, place_id)) = options.publish {
+        let client = OpenCloudClient::new(
+            options.api_key.clone().expect("api-key"),
+            downloader.clone(),
+        );
+        match client
+            .publish_place(universe_id, place_id, &options.output_path)
+            .await
+        {
+            Ok(version) => info!("Published place {:?} as version {:?}", place_id, version),
+            Err(err) => fail(&options, err),
+        }
     }
 
-    println!("Done, converting it back to a place now.");
-    save_rbx_place(output_path.clone(), &dom).expect("Failed to save place to file");
-    println!("Finished, saved to path: {:?}", output_path);
+    // asset failures were skipped over, the place is written but not clean
+    let mut summary = RunSummary::default();
+    summary.add(&result);
+    exit_with(&options, summary);
 }
@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+use tracing::{info, warn};
+
+use crate::{
+    place::Place,
+    utils::{
+        asset_provider::AssetProvider,
+        content_url::{is_local_asset, normalize_content},
+        mapping::{apply_mapping, AssetMapping},
+        mesh_content::get_mesh_id,
+        mesh_reader::RobloxMesh,
+        open_cloud::OpenCloudClient,
+        simplify::simplify,
+        FixerError,
+    },
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct OptimizeResult {
+    pub mapping: AssetMapping,
+    pub meshes: usize,
+    pub faces_removed: usize,
+    pub bytes_saved: usize,
+    pub rewritten: usize,
+}
+
+// fetches every mesh the place uses, runs `transform` over it and re-uploads
+// the ones it changed. `transform` returns how many faces it removed
+async fn reupload_meshes<F>(
+    provider: &dyn AssetProvider,
+    uploader: &OpenCloudClient,
+    place: &mut Place,
+    label: &str,
+    transform: F,
+) -> Result<OptimizeResult, FixerError>
+where
+    F: Fn(&mut RobloxMesh) -> usize,
+{
+    let mesh_parts = place.mesh_parts();
+    let mut mesh_ids = BTreeSet::<String>::new();
+    for referent in mesh_parts.iter() {
+        let instance = place.dom.get_by_ref(*referent).expect("mesh-part");
+        let mesh_id = normalize_content(&get_mesh_id(instance));
+        if mesh_id.trim() != "" && !is_local_asset(&mesh_id) {
+            mesh_ids.insert(mesh_id);
+        }
+    }
+
+    let mut result = OptimizeResult::default();
+    for mesh_id in mesh_ids {
+        let mut data = match provider.fetch(mesh_id.clone()).await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Skipping {:?}: {}", mesh_id, err);
+                continue;
+            }
+        };
+        let original_bytes = data.get_ref().len();
+        let mut mesh = match RobloxMesh::from_cursor(&mut data) {
+            Ok(mesh) => mesh,
+            Err(err) => {
+                warn!("Skipping {:?}: {}", mesh_id, err);
+                continue;
+            }
+        };
+
+        let faces_removed = transform(&mut mesh);
+        if faces_removed == 0 {
+            continue;
+        }
+
+        let rebuilt = match mesh.to_bytes() {
+            Ok(rebuilt) => rebuilt,
+            Err(err) => {
+                warn!("Skipping {:?}: {}", mesh_id, err);
+                continue;
+            }
+        };
+        let rebuilt_bytes = rebuilt.len();
+        let name = format!(
+            "{} ({})",
+            mesh_id.trim_start_matches("rbxassetid://"),
+            label
+        );
+        let new_id = uploader.upload_mesh(&name, rebuilt).await?;
+        info!(
+            "Removed {:?} faces from {:?}, uploaded as {:?}",
+            faces_removed, mesh_id, new_id
+        );
+
+        result.meshes += 1;
+        result.faces_removed += faces_removed;
+        result.bytes_saved += original_bytes.saturating_sub(rebuilt_bytes);
+        result.mapping.insert(mesh_id, new_id);
+    }
+
+    result.rewritten = apply_mapping(&mut place.dom, &mesh_parts, &result.mapping);
+    Ok(result)
+}
+
+// drops every LOD below full detail, re-uploads the smaller meshes and points
+// the place at them
+pub async fn strip_place_lods(
+    provider: &dyn AssetProvider,
+    uploader: &OpenCloudClient,
+    place: &mut Place,
+) -> Result<OptimizeResult, FixerError> {
+    reupload_meshes(provider, uploader, place, "no LODs", |mesh| {
+        mesh.strip_lods()
+    })
+    .await
+}
+
+// simplifies every mesh above `max_triangles` down to that budget
+pub async fn decimate_place(
+    provider: &dyn AssetProvider,
+    uploader: &OpenCloudClient,
+    place: &mut Place,
+    max_triangles: usize,
+) -> Result<OptimizeResult, FixerError> {
+    let label = format!("{} tris", max_triangles);
+    reupload_meshes(provider, uploader, place, &label, |mesh| {
+        // the simplifier rebuilds the vertex buffer and would lose the rig
+        if mesh.header.num_bones > 0 {
+            return 0;
+        }
+
+        mesh.strip_lods();
+        if mesh.faces.len() <= max_triangles {
+            return 0;
+        }
+
+        let before = mesh.faces.len();
+        match simplify(mesh, max_triangles) {
+            Ok(simplified) => {
+                *mesh = simplified;
+                before - mesh.faces.len()
+            }
+            Err(err) => {
+                warn!("Could not simplify mesh: {}", err);
+                0
+            }
+        }
+    })
+    .await
+}
@@ -0,0 +1,832 @@
+use crate::{
+    config::{ConfigFile, PolicyConfig},
+    serve::DEFAULT_PORT,
+};
+use rbxlx_mesh_fixer::{extract::MeshFormat, place::STDIO_PATH, utils::path_filter::glob_match};
+use rbxlx_mesh_fixer::{
+    utils::{
+        asset_downloader::DEFAULT_CACHE_DIR,
+        asset_lists::{read_ignore_list, read_merge_list},
+        canonical::PreferredOwner,
+        content_url::normalize_content,
+        fidelity::PropertyPolicy,
+        open_cloud::Creator,
+        path_filter::PathFilter,
+        rate_limit::DEFAULT_REQUESTS_PER_SECOND,
+        signature_cache::{DEFAULT_SIGNATURE_CACHE, SIGNATURE_CACHE_FILE},
+        similarity::{DistanceMetric, FuzzyMatch},
+        tags::TagFilter,
+    },
+    CanonicalPolicy, DedupeScope,
+};
+use std::{collections::BTreeSet, fs, net::IpAddr, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Dedupe,
+    Apply,
+    Revert,
+    Inspect,
+    Stats,
+    Export,
+    Import,
+    StripLods,
+    Decimate,
+    Combine,
+    FixSizes,
+    Sanitize,
+    Divergence,
+    Policy,
+    Serve,
+    Server,
+    FixMesh,
+    Rojo,
+    Prefetch,
+    ExtractMeshes,
+    ExtractTextures,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub input_path: String,
+    pub output_path: String,
+    pub compensate_attachments: bool,
+    pub canonical_policy: CanonicalPolicy,
+    pub preferred_owner: Option<PreferredOwner>,
+    pub report_path: Option<String>,
+    pub summary_path: Option<String>,
+    pub mapping_path: Option<String>,
+    pub database_path: Option<String>,
+    pub journal_path: Option<String>,
+    pub luau_path: Option<String>,
+    pub stamp_attributes: bool,
+    pub content_dir: Option<String>,
+    pub cache_dir: String,
+    pub signature_cache: Option<String>,
+    pub fail_fast: bool,
+    pub offline: bool,
+    pub revalidate: bool,
+    pub rate_limit: f64,
+    pub deadline: Option<u64>,
+    pub asset_delivery_url: Option<String>,
+    pub quiet: bool,
+    pub log_level: String,
+    pub log_format: LogFormat,
+    pub top: usize,
+    pub upload_creator: Option<Creator>,
+    pub upload_canonicals: bool,
+    pub api_key: Option<String>,
+    pub remote_cache: Option<String>,
+    pub remote_cache_token: Option<String>,
+    pub publish: Option<(u64, u64)>,
+    pub max_triangles: usize,
+    pub max_part_size: f32,
+    pub combine_cell: f32,
+    pub min_duplicates: usize,
+    pub scope: DedupeScope,
+    pub fuzzy: Option<FuzzyMatch>,
+    pub min_confidence: f32,
+    pub min_triangles: i32,
+    pub weld: bool,
+    pub uv_signature: bool,
+    pub path_filter: PathFilter,
+    pub tag_filter: TagFilter,
+    pub ignored_assets: BTreeSet<String>,
+    pub merge_groups: Vec<Vec<String>>,
+    pub policies: Vec<PropertyPolicy>,
+    pub inputs: Vec<String>,
+    pub out_dir: Option<String>,
+    pub extract_dir: Option<String>,
+    pub mesh_format: MeshFormat,
+    pub jobs: usize,
+    pub port: u16,
+    pub bind: String,
+    pub server_token: Option<String>,
+    pub watch_dir: Option<String>,
+    pub in_place: bool,
+    pub verify: bool,
+    pub thumbnails: bool,
+    pub interactive: bool,
+    pub timings: bool,
+}
+
+fn is_place_file(path: &Path) -> bool {
+    path.is_file()
+        && matches!(
+            path.extension().and_then(|x| x.to_str()),
+            Some("rbxl") | Some("rbxlx")
+        )
+}
+
+fn is_loopback(bind: &str) -> bool {
+    match bind.parse::<IpAddr>() {
+        Ok(address) => address.is_loopback(),
+        Err(_) => bind.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+// shells on windows don't expand globs, so `builds/*.rbxl` is handled here.
+// only the file name may contain wildcards, directories expand to the places
+// directly inside them
+fn expand_input(input: &str) -> Vec<String> {
+    let path = Path::new(input);
+    let (dir, pattern) = if path.is_dir() {
+        (path, "*")
+    } else if input.contains('*') || input.contains('?') {
+        let pattern = path.file_name().and_then(|x| x.to_str()).unwrap_or("*");
+        (path.parent().unwrap_or_else(|| Path::new(".")), pattern)
+    } else {
+        return vec![input.to_string()];
+    };
+
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut inputs = fs::read_dir(dir)
+        .expect("could not read input directory")
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| is_place_file(x))
+        .filter(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .map(|x| glob_match(pattern, x))
+                .unwrap_or(false)
+        })
+        .map(|x| x.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    inputs.sort();
+    inputs
+}
+
+fn parse_policy(config: &PolicyConfig) -> PropertyPolicy {
+    let mut policy = PropertyPolicy::default();
+    for pattern in config.include.iter() {
+        policy.path_filter.include(pattern);
+    }
+    for pattern in config.exclude.iter() {
+        policy.path_filter.exclude(pattern);
+    }
+    policy.tag_filter.required = config.tags.clone();
+    policy.tag_filter.skipped = config.skip_tags.clone();
+    policy.render_fidelity = config
+        .render_fidelity
+        .as_ref()
+        .map(|x| x.parse().expect("render-fidelity"));
+    policy.collision_fidelity = config
+        .collision_fidelity
+        .as_ref()
+        .map(|x| x.parse().expect("collision-fidelity"));
+    policy.double_sided = config.double_sided;
+    policy
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        // the config file only provides defaults, so it has to be read before
+        // any of the other flags are looked at
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        let config_path = args
+            .iter()
+            .position(|x| x == "--config")
+            .map(|x| args.get(x + 1).expect("config-path").as_str());
+        let config = ConfigFile::load(config_path).expect("could not read config file");
+
+        let mut positional = Vec::<String>::new();
+        let mut options = Options {
+            command: Command::Dedupe,
+            input_path: String::new(),
+            output_path: String::new(),
+            compensate_attachments: false,
+            canonical_policy: CanonicalPolicy::default(),
+            preferred_owner: None,
+            report_path: None,
+            summary_path: None,
+            mapping_path: None,
+            database_path: None,
+            journal_path: None,
+            luau_path: None,
+            stamp_attributes: false,
+            content_dir: None,
+            cache_dir: String::from(DEFAULT_CACHE_DIR),
+            signature_cache: Some(String::from(DEFAULT_SIGNATURE_CACHE)),
+            fail_fast: false,
+            offline: false,
+            revalidate: false,
+            rate_limit: DEFAULT_REQUESTS_PER_SECOND,
+            deadline: None,
+            asset_delivery_url: std::env::var("ASSET_DELIVERY_URL").ok(),
+            quiet: false,
+            log_level: String::from("info"),
+            log_format: LogFormat::Text,
+            top: 10,
+            upload_creator: None,
+            upload_canonicals: false,
+            api_key: std::env::var("ROBLOX_API_KEY").ok(),
+            remote_cache: None,
+            remote_cache_token: std::env::var("REMOTE_CACHE_TOKEN").ok(),
+            publish: None,
+            max_triangles: 5000,
+            max_part_size: 8.0,
+            combine_cell: 64.0,
+            min_duplicates: 0,
+            scope: DedupeScope::default(),
+            fuzzy: None,
+            min_confidence: 0.0,
+            min_triangles: 0,
+            weld: false,
+            uv_signature: false,
+            path_filter: PathFilter::default(),
+            tag_filter: TagFilter::default(),
+            ignored_assets: BTreeSet::new(),
+            merge_groups: Vec::new(),
+            policies: Vec::new(),
+            inputs: Vec::new(),
+            out_dir: None,
+            extract_dir: None,
+            mesh_format: MeshFormat::default(),
+            jobs: 1,
+            port: DEFAULT_PORT,
+            bind: String::from("127.0.0.1"),
+            server_token: std::env::var("SERVER_TOKEN").ok(),
+            watch_dir: None,
+            in_place: false,
+            verify: false,
+            thumbnails: false,
+            interactive: false,
+            timings: false,
+        };
+
+        options.apply_config(config);
+
+        // the metric may come before or after the tolerance
+        let mut fuzzy_metric = None::<DistanceMetric>;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    args.next();
+                }
+                "--compensate-attachments" => options.compensate_attachments = true,
+                "--stamp-attributes" => options.stamp_attributes = true,
+                "--fail-fast" => options.fail_fast = true,
+                "--offline" => options.offline = true,
+                "--revalidate" => options.revalidate = true,
+                "--asset-delivery-url" => {
+                    options.asset_delivery_url = Some(args.next().expect("asset-delivery-url"))
+                }
+                "--rate-limit" => {
+                    let limit = args.next().expect("rate-limit");
+                    options.rate_limit = limit.parse().expect("rate-limit");
+                }
+                "--deadline" => {
+                    let seconds = args.next().expect("deadline");
+                    options.deadline = Some(seconds.parse().expect("deadline"));
+                }
+                "--weld" => options.weld = true,
+                "--uv-signature" => options.uv_signature = true,
+                "--in-place" => options.in_place = true,
+                "--verify" => options.verify = true,
+                "--thumbnails" => options.thumbnails = true,
+                "--interactive" | "-i" => options.interactive = true,
+                "--timings" => options.timings = true,
+                "--quiet" | "-q" => options.quiet = true,
+                "--canonical" => {
+                    let policy = args.next().expect("canonical-policy");
+                    options.canonical_policy = policy.parse().expect("canonical-policy");
+                }
+                "--prefer-owner" => {
+                    let owner = args.next().expect("prefer-owner");
+                    options.preferred_owner = Some(owner.parse().expect("prefer-owner"));
+                }
+                "--report" => options.report_path = Some(args.next().expect("report-path")),
+                "--summary-json" => options.summary_path = Some(args.next().expect("summary-path")),
+                "--mapping" => options.mapping_path = Some(args.next().expect("mapping-path")),
+                "--database" => options.database_path = Some(args.next().expect("database-path")),
+                "--journal" => options.journal_path = Some(args.next().expect("journal-path")),
+                "--luau" => options.luau_path = Some(args.next().expect("luau-path")),
+                "--content-dir" => options.content_dir = Some(args.next().expect("content-dir")),
+                "--cache-dir" => options.cache_dir = args.next().expect("cache-dir"),
+                "--signature-cache" => {
+                    options.signature_cache = Some(args.next().expect("signature-cache"))
+                }
+                "--no-signature-cache" => options.signature_cache = None,
+                "--top" => options.top = args.next().expect("top").parse().expect("top"),
+                "--upload-user" => {
+                    let id = args
+                        .next()
+                        .expect("upload-user")
+                        .parse()
+                        .expect("upload-user");
+                    options.upload_creator = Some(Creator::User(id));
+                }
+                "--upload-group" => {
+                    let id = args
+                        .next()
+                        .expect("upload-group")
+                        .parse()
+                        .expect("upload-group");
+                    options.upload_creator = Some(Creator::Group(id));
+                }
+                "--upload-canonicals" => options.upload_canonicals = true,
+                "--publish" => {
+                    let universe_id = args
+                        .next()
+                        .expect("universe-id")
+                        .parse()
+                        .expect("universe-id");
+                    let place_id = args.next().expect("place-id").parse().expect("place-id");
+                    options.publish = Some((universe_id, place_id));
+                }
+                "--max-triangles" => {
+                    let max = args.next().expect("max-triangles");
+                    options.max_triangles = max.parse().expect("max-triangles");
+                }
+                "--max-part-size" => {
+                    let max = args.next().expect("max-part-size");
+                    options.max_part_size = max.parse().expect("max-part-size");
+                }
+                "--combine-cell" => {
+                    let size = args.next().expect("combine-cell");
+                    options.combine_cell = size.parse().expect("combine-cell");
+                }
+                "--scope" => {
+                    let scope = args.next().expect("scope");
+                    options.scope = scope.parse().expect("scope");
+                }
+                "--fuzzy" => {
+                    let tolerance = args.next().expect("fuzzy-tolerance");
+                    let tolerance = tolerance.parse().expect("fuzzy-tolerance");
+                    let metric = options.fuzzy.map(|x| x.metric).unwrap_or_default();
+                    options.fuzzy = Some(FuzzyMatch { tolerance, metric });
+                }
+                "--fuzzy-metric" => {
+                    let metric = args.next().expect("fuzzy-metric");
+                    fuzzy_metric = Some(metric.parse().expect("fuzzy-metric"));
+                }
+                "--min-confidence" => {
+                    let min = args.next().expect("min-confidence");
+                    options.min_confidence = min.parse().expect("min-confidence");
+                }
+                "--min-duplicates" => {
+                    let min = args.next().expect("min-duplicates");
+                    options.min_duplicates = min.parse().expect("min-duplicates");
+                }
+                "--min-triangles" => {
+                    let min = args.next().expect("min-triangles");
+                    options.min_triangles = min.parse().expect("min-triangles");
+                }
+                "--include" => options.path_filter.include(&args.next().expect("include")),
+                "--exclude" => options.path_filter.exclude(&args.next().expect("exclude")),
+                "--tag" => options.tag_filter.required.push(args.next().expect("tag")),
+                "--skip-tag" => options
+                    .tag_filter
+                    .skipped
+                    .push(args.next().expect("skip-tag")),
+                "--ignore-list" => {
+                    let path = args.next().expect("ignore-list");
+                    let ignored = read_ignore_list(&path).expect("could not read ignore list");
+                    options.ignored_assets.extend(ignored);
+                }
+                "--merge-list" => {
+                    let path = args.next().expect("merge-list");
+                    let groups = read_merge_list(&path).expect("could not read merge list");
+                    options.merge_groups.extend(groups);
+                }
+                "--out-dir" => options.out_dir = Some(args.next().expect("out-dir")),
+                "--out" => options.extract_dir = Some(args.next().expect("out")),
+                "--format" => {
+                    let format = args.next().expect("format");
+                    options.mesh_format = format.parse().expect("format");
+                }
+                "--jobs" | "-j" => options.jobs = args.next().expect("jobs").parse().expect("jobs"),
+                "--port" => options.port = args.next().expect("port").parse().expect("port"),
+                "--bind" => options.bind = args.next().expect("bind"),
+                "--token" => options.server_token = Some(args.next().expect("token")),
+                "--watch" => options.watch_dir = Some(args.next().expect("watch-dir")),
+                "--api-key" => options.api_key = Some(args.next().expect("api-key")),
+                "--remote-cache" => options.remote_cache = Some(args.next().expect("remote-cache")),
+                "--remote-cache-token" => {
+                    options.remote_cache_token = Some(args.next().expect("remote-cache-token"))
+                }
+                "--log-level" => options.log_level = args.next().expect("log-level"),
+                "--log-format" => {
+                    options.log_format = match args.next().expect("log-format").as_str() {
+                        "text" => LogFormat::Text,
+                        "json" => LogFormat::Json,
+                        other => panic!("unknown log format {:?}, expected text or json", other),
+                    }
+                }
+                _ => positional.push(arg),
+            }
+        }
+        // the signature cache follows the cache dir unless it was moved itself
+        if options.cache_dir != DEFAULT_CACHE_DIR
+            && options.signature_cache.as_deref() == Some(DEFAULT_SIGNATURE_CACHE)
+        {
+            let path = Path::new(&options.cache_dir).join(SIGNATURE_CACHE_FILE);
+            options.signature_cache = Some(path.to_string_lossy().into_owned());
+        }
+        if let Some(metric) = fuzzy_metric {
+            match options.fuzzy.as_mut() {
+                Some(fuzzy) => fuzzy.metric = metric,
+                None => panic!("--fuzzy-metric needs --fuzzy <tolerance>"),
+            }
+        }
+
+        let mut positional = positional.into_iter().peekable();
+        match positional.peek().map(|x| x.as_str()) {
+            Some("dedupe") => {
+                positional.next();
+            }
+            Some("apply") => {
+                positional.next();
+                options.command = Command::Apply;
+            }
+            Some("revert") => {
+                positional.next();
+                options.command = Command::Revert;
+            }
+            Some("inspect") => {
+                positional.next();
+                options.command = Command::Inspect;
+            }
+            Some("stats") => {
+                positional.next();
+                options.command = Command::Stats;
+            }
+            Some("export") => {
+                positional.next();
+                options.command = Command::Export;
+            }
+            Some("import") => {
+                positional.next();
+                options.command = Command::Import;
+            }
+            Some("strip-lods") => {
+                positional.next();
+                options.command = Command::StripLods;
+            }
+            Some("decimate") => {
+                positional.next();
+                options.command = Command::Decimate;
+            }
+            Some("combine") => {
+                positional.next();
+                options.command = Command::Combine;
+            }
+            Some("fix-sizes") => {
+                positional.next();
+                options.command = Command::FixSizes;
+            }
+            Some("sanitize") => {
+                positional.next();
+                options.command = Command::Sanitize;
+            }
+            Some("divergence") => {
+                positional.next();
+                options.command = Command::Divergence;
+            }
+            Some("policy") => {
+                positional.next();
+                options.command = Command::Policy;
+            }
+            Some("serve") => {
+                positional.next();
+                options.command = Command::Serve;
+            }
+            Some("server") => {
+                positional.next();
+                options.command = Command::Server;
+            }
+            Some("fix-mesh") => {
+                positional.next();
+                options.command = Command::FixMesh;
+            }
+            Some("rojo") => {
+                positional.next();
+                options.command = Command::Rojo;
+            }
+            Some("prefetch") => {
+                positional.next();
+                options.command = Command::Prefetch;
+            }
+            Some("extract-meshes") => {
+                positional.next();
+                options.command = Command::ExtractMeshes;
+            }
+            Some("extract-textures") => {
+                positional.next();
+                options.command = Command::ExtractTextures;
+            }
+            _ => {}
+        }
+
+        // watch mode picks its inputs up from the watched directory
+        if options.watch_dir.is_some() {
+            if options.command != Command::Dedupe {
+                panic!("--watch is only supported by dedupe");
+            }
+            if options.journal_path.is_some() || options.publish.is_some() {
+                panic!("--journal and --publish only work on a single place");
+            }
+        } else if options.command == Command::Serve || options.command == Command::Server {
+            // places come in over http, there is none on disk
+            if options.journal_path.is_some()
+                || options.publish.is_some()
+                || options.luau_path.is_some()
+                || options.interactive
+            {
+                panic!("--journal, --luau, --publish and --interactive need a place on disk");
+            }
+            // anyone who can reach the server can spend the api key's upload
+            // quota, so only loopback is open without a token
+            if options.command == Command::Server
+                && !is_loopback(&options.bind)
+                && options.server_token.is_none()
+            {
+                panic!(
+                    "--bind on a non-loopback address requires --token <secret> or SERVER_TOKEN"
+                );
+            }
+        } else if options.out_dir.is_some() {
+            // with --out-dir every remaining argument is an input place
+            if options.command != Command::Dedupe {
+                panic!("--out-dir is only supported by dedupe");
+            }
+            if options.journal_path.is_some() || options.publish.is_some() {
+                panic!("--journal and --publish only work on a single place");
+            }
+            if options.jobs > 1 && options.database_path.is_some() {
+                panic!("--database can't be shared between parallel --jobs");
+            }
+
+            options.inputs = positional.flat_map(|x| expand_input(&x)).collect();
+            if options.inputs.is_empty() {
+                panic!("no input places matched");
+            }
+            options.input_path = options.inputs[0].clone();
+        } else {
+            options.input_path = positional.next().expect("input-path");
+            // read-only commands never write a place back out, rojo projects
+            // are rewritten in place
+            let writes_output = options.command != Command::Inspect
+                && options.command != Command::Stats
+                && options.command != Command::Prefetch
+                && options.command != Command::ExtractMeshes
+                && options.command != Command::ExtractTextures
+                && options.command != Command::Rojo;
+            if writes_output && options.in_place {
+                let edits_place = matches!(
+                    options.command,
+                    Command::Dedupe
+                        | Command::Apply
+                        | Command::Revert
+                        | Command::StripLods
+                        | Command::Decimate
+                        | Command::Combine
+                        | Command::FixSizes
+                        | Command::Sanitize
+                        | Command::Divergence
+                        | Command::Policy
+                );
+                if !edits_place {
+                    panic!("--in-place only works with commands that rewrite a place");
+                }
+                if options.input_path == STDIO_PATH {
+                    panic!("--in-place needs a place file, not stdin");
+                }
+                options.output_path = options.input_path.clone();
+            } else if writes_output && options.luau_path.is_some() {
+                // the script can stand in for the rewritten place
+                options.output_path = positional.next().unwrap_or_default();
+            } else if writes_output && options.command == Command::Divergence {
+                // only reported unless there is somewhere to put the fix
+                options.output_path = positional.next().unwrap_or_default();
+            } else if writes_output {
+                options.output_path = positional.next().expect("output-path");
+            }
+        }
+
+        // the review screen takes over the terminal, it can't share it with
+        // a place streaming through stdout or with unattended runs
+        if options.interactive
+            && (options.output_path == STDIO_PATH
+                || options.watch_dir.is_some()
+                || options.jobs > 1)
+        {
+            panic!("--interactive can't be combined with stdout output, --watch or --jobs");
+        }
+
+        if options.thumbnails && options.report_path.is_none() {
+            panic!("--thumbnails are stored next to the report, pass --report <path>");
+        }
+
+        if let Some(url) = &options.asset_delivery_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                panic!(
+                    "--asset-delivery-url has to be an http(s) url, got {:?}",
+                    url
+                );
+            }
+        }
+
+        if !(options.combine_cell > 0.0) || !(options.max_part_size > 0.0) {
+            panic!("--combine-cell and --max-part-size have to be positive sizes in studs");
+        }
+
+        if let Some(fuzzy) = &options.fuzzy {
+            if !(fuzzy.tolerance >= 0.0) {
+                panic!("--fuzzy has to be a tolerance of zero or more");
+            }
+        }
+
+        if !(0.0..=1.0).contains(&options.min_confidence) {
+            panic!("--min-confidence has to be between 0 and 1");
+        }
+
+        if options.rate_limit.is_nan() || options.rate_limit <= 0.0 {
+            panic!("--rate-limit has to be a positive number of requests per second");
+        }
+
+        // the deadline counts from startup, long running modes would hit it
+        // once and then fail every download after
+        if options.deadline.is_some()
+            && (options.watch_dir.is_some()
+                || options.command == Command::Serve
+                || options.command == Command::Server)
+        {
+            panic!("--deadline only applies to one-off runs, not --watch, serve or server");
+        }
+
+        // a creator from the config file is only there for the commands that
+        // upload, on its own it never changes what a run does
+        let reuploads = matches!(
+            options.command,
+            Command::StripLods | Command::Decimate | Command::Combine
+        );
+        let uploads = reuploads || options.upload_canonicals;
+
+        // everything here except the asset cache needs roblox's apis
+        if options.offline {
+            if options.command == Command::Prefetch {
+                panic!("prefetch needs the network, drop --offline");
+            }
+            if options.revalidate {
+                panic!("--revalidate needs the network, drop --offline");
+            }
+            if uploads || options.publish.is_some() {
+                panic!("--offline can't upload or publish");
+            }
+            if options.thumbnails {
+                panic!("--offline can't fetch --thumbnails");
+            }
+            match options.canonical_policy {
+                CanonicalPolicy::Newest | CanonicalPolicy::Owner(_) => {
+                    panic!("--offline can't look up asset details for --canonical")
+                }
+                _ => {}
+            }
+            if options.preferred_owner.is_some() {
+                panic!("--offline can't look up asset owners for --prefer-owner");
+            }
+        }
+
+        if options.luau_path.is_some() && options.command != Command::Dedupe {
+            panic!("--luau is only supported by dedupe");
+        }
+        if options.luau_path.is_some() && (options.watch_dir.is_some() || options.out_dir.is_some())
+        {
+            panic!("--luau only works on a single place");
+        }
+
+        if options.publish.is_some() && options.output_path.is_empty() {
+            panic!("--publish needs an output place to publish");
+        }
+        if options.publish.is_some() && options.output_path == STDIO_PATH {
+            panic!("--publish needs the place written to a file, not stdout");
+        }
+
+        if options.command == Command::Policy && options.policies.is_empty() {
+            panic!("policy needs at least one [[policy]] table in the config file");
+        }
+
+        // extracted files go next to each other, never into the place
+        let extracts = matches!(
+            options.command,
+            Command::ExtractMeshes | Command::ExtractTextures
+        );
+        if extracts && options.extract_dir.is_none() {
+            panic!("extract-meshes and extract-textures require --out <dir>");
+        }
+        if options.extract_dir.is_some() && !extracts {
+            panic!("--out is only supported by extract-meshes and extract-textures, dedupe takes --out-dir");
+        }
+
+        if options.command == Command::Apply && options.mapping_path.is_none() {
+            panic!("apply requires --mapping <path>");
+        }
+
+        if reuploads && options.upload_creator.is_none() {
+            panic!("this command requires --upload-user <id> or --upload-group <id>");
+        }
+        if options.upload_canonicals && options.upload_creator.is_none() {
+            panic!("--upload-canonicals requires --upload-user <id> or --upload-group <id>");
+        }
+        if options.upload_canonicals
+            && !matches!(
+                options.command,
+                Command::Dedupe | Command::Rojo | Command::Serve | Command::Server
+            )
+        {
+            panic!("--upload-canonicals is only supported by dedupe, rojo, serve and server");
+        }
+
+        if (uploads || options.publish.is_some()) && options.api_key.is_none() {
+            panic!("uploading requires --api-key <key> or ROBLOX_API_KEY");
+        }
+
+        options
+    }
+
+    fn apply_config(&mut self, config: ConfigFile) {
+        if let Some(policy) = config.canonical {
+            self.canonical_policy = policy.parse().expect("canonical-policy");
+        }
+        if let Some(owner) = config.prefer_owner {
+            self.preferred_owner = Some(owner.parse().expect("prefer-owner"));
+        }
+        if let Some(level) = config.log_level {
+            self.log_level = level;
+        }
+        if let Some(dir) = config.cache_dir {
+            self.cache_dir = dir;
+        }
+        self.content_dir = config.content_dir;
+        self.remote_cache = config.remote_cache;
+        self.database_path = config.database;
+        self.compensate_attachments = config.compensate_attachments.unwrap_or(false);
+        self.stamp_attributes = config.stamp_attributes.unwrap_or(false);
+        self.weld = config.weld.unwrap_or(false);
+        self.uv_signature = config.uv_signature.unwrap_or(false);
+        self.fail_fast = config.fail_fast.unwrap_or(false);
+        self.offline = config.offline.unwrap_or(false);
+        self.revalidate = config.revalidate.unwrap_or(false);
+        self.rate_limit = config.rate_limit.unwrap_or(DEFAULT_REQUESTS_PER_SECOND);
+        self.deadline = config.deadline;
+        self.min_duplicates = config.min_duplicates.unwrap_or(0);
+        self.min_confidence = config.min_confidence.unwrap_or(0.0);
+        if let Some(scope) = &config.scope {
+            self.scope = scope.parse().expect("scope");
+        }
+        if let Some(tolerance) = config.fuzzy {
+            let metric = match &config.fuzzy_metric {
+                Some(metric) => metric.parse().expect("fuzzy-metric"),
+                None => DistanceMetric::default(),
+            };
+            self.fuzzy = Some(FuzzyMatch { tolerance, metric });
+        }
+        self.min_triangles = config.min_triangles.unwrap_or(0);
+        self.jobs = config.jobs.unwrap_or(1);
+
+        for pattern in config.filters.include.iter() {
+            self.path_filter.include(pattern);
+        }
+        for pattern in config.filters.exclude.iter() {
+            self.path_filter.exclude(pattern);
+        }
+        self.tag_filter.required = config.filters.tags;
+        self.tag_filter.skipped = config.filters.skip_tags;
+        self.ignored_assets = config.ignore.iter().map(|x| normalize_content(x)).collect();
+        self.merge_groups = config
+            .merge
+            .iter()
+            .map(|x| x.iter().map(|x| normalize_content(x)).collect())
+            .collect();
+        self.policies = config.policy.iter().map(parse_policy).collect();
+
+        // keys in the environment beat keys committed to the repo
+        if self.api_key.is_none() {
+            self.api_key = config.auth.api_key;
+        }
+        if self.asset_delivery_url.is_none() {
+            self.asset_delivery_url = config.asset_delivery_url;
+        }
+        if self.remote_cache_token.is_none() {
+            self.remote_cache_token = config.auth.remote_cache_token;
+        }
+        self.upload_creator = match (config.auth.upload_user, config.auth.upload_group) {
+            (Some(id), _) => Some(Creator::User(id)),
+            (None, Some(id)) => Some(Creator::Group(id)),
+            (None, None) => None,
+        };
+    }
+}
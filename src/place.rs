@@ -0,0 +1,219 @@
+use rbx_dom_weak::{types::Ref, Instance, WeakDom};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::utils::{
+    atomic_file::AtomicFile, mesh_content::has_mesh_property, path_filter::PathFilter,
+    reflection::is_a, union_operation::filter_unions, FixerError,
+};
+
+// `-` reads the place from stdin or writes it to stdout
+pub const STDIO_PATH: &str = "-";
+
+// accessories and tools live outside of Workspace, so their handles have to be
+// picked up from the services the character gets built from as well
+const SEARCHED_SERVICES: [&str; 5] = [
+    "Workspace",
+    "ReplicatedStorage",
+    "ServerStorage",
+    "StarterPack",
+    "StarterPlayer",
+];
+
+pub struct Place {
+    pub dom: WeakDom,
+}
+
+// the extension decides the format on disk, stdin and stdout are always binary
+fn is_xml_path(path: &str) -> bool {
+    matches!(
+        Path::new(path).extension().and_then(|x| x.to_str()),
+        Some("rbxlx") | Some("rbxmx")
+    )
+}
+
+// depth first with an explicit stack so deep hierarchies can't overflow it.
+// each path is built from its parent's rather than walking back up to the
+// root for every instance, and `keep` decides what's collected as it goes
+fn walk_descendants<F>(
+    dom: &WeakDom,
+    root: Ref,
+    include_root: bool,
+    keep: &mut F,
+    found: &mut Vec<(String, Ref)>,
+) where
+    F: FnMut(&str, Ref) -> bool,
+{
+    let root_inst = dom.get_by_ref(root).expect("ref-inst");
+    let mut stack = vec![(root_inst.name.clone(), root, include_root)];
+
+    while let Some((path, referent, visit)) = stack.pop() {
+        let instance = dom.get_by_ref(referent).expect("ref-inst");
+        // pushed in reverse so children come off the stack in order
+        for child in instance.children().iter().rev() {
+            let name = &dom.get_by_ref(*child).expect("ref-inst").name;
+            stack.push((format!("{}/{}", path, name), *child, true));
+        }
+
+        if visit && keep(&path, referent) {
+            found.push((path, referent));
+        }
+    }
+}
+
+// MeshParts with a default TextureID or MeshId may not have them serialized
+// at all, so the class decides. the property check catches classes the
+// reflection database doesn't know yet
+pub fn is_mesh_part(instance: &Instance) -> bool {
+    is_a(&instance.class, "MeshPart")
+        || (instance.properties.contains_key("TextureID") && has_mesh_property(instance))
+}
+
+pub fn filter_mesh_parts(dom: &WeakDom, refs: Vec<Ref>) -> Vec<Ref> {
+    let mut mesh_parts = Vec::<Ref>::new();
+
+    for referent in refs {
+        let child = dom.get_by_ref(referent).expect("referent");
+        if !is_mesh_part(child) {
+            continue;
+        }
+
+        mesh_parts.push(referent);
+    }
+
+    mesh_parts
+}
+
+pub fn has_wrap_instance(dom: &WeakDom, referent: Ref) -> bool {
+    // layered clothing cages are authored against the part's current size, so
+    // wrapped parts only ever get their MeshId swapped
+    dom.get_by_ref(referent)
+        .map(|instance| {
+            instance.children().iter().any(|child| {
+                dom.get_by_ref(*child)
+                    .map(|x| x.class == "WrapLayer" || x.class == "WrapTarget")
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+impl Place {
+    pub fn from_dom(dom: WeakDom) -> Self {
+        Place { dom }
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, FixerError> {
+        Ok(Place::from_dom(rbx_binary::from_reader_default(reader)?))
+    }
+
+    pub fn open(input_path: &str) -> Result<Self, FixerError> {
+        if input_path == STDIO_PATH {
+            return Place::from_reader(BufReader::new(io::stdin().lock()));
+        }
+
+        let input_fp = Path::new(input_path);
+        let input_file = BufReader::new(File::open(input_fp)?);
+        match is_xml_path(input_path) {
+            true => Ok(Place::from_dom(rbx_xml::from_reader_default(input_file)?)),
+            false => Place::from_reader(input_file),
+        }
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), FixerError> {
+        Ok(rbx_binary::to_writer_default(
+            writer,
+            &self.dom,
+            self.dom.root().children(),
+        )?)
+    }
+
+    pub fn save(&self, output_path: &str) -> Result<(), FixerError> {
+        if output_path == STDIO_PATH {
+            let mut output = BufWriter::new(io::stdout().lock());
+            self.to_writer(&mut output)?;
+            output.flush()?;
+            return Ok(());
+        }
+
+        let mut output_file = AtomicFile::create(output_path)?;
+        match is_xml_path(output_path) {
+            true => {
+                rbx_xml::to_writer_default(&mut output_file, &self.dom, self.dom.root().children())?
+            }
+            false => self.to_writer(&mut output_file)?,
+        }
+        output_file.commit()
+    }
+
+    // searched descendants `keep` accepts, paired with their full instance
+    // path
+    pub fn descendant_paths_where<F>(&self, mut keep: F) -> Vec<(String, Ref)>
+    where
+        F: FnMut(&str, Ref) -> bool,
+    {
+        let data_model = self.dom.root();
+        let mut found = Vec::<(String, Ref)>::new();
+        let services = data_model
+            .children()
+            .iter()
+            .copied()
+            .filter(|x| {
+                let name = &self.dom.get_by_ref(*x).expect("service").name;
+                SEARCHED_SERVICES.contains(&name.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        for service in services.iter() {
+            walk_descendants(&self.dom, *service, false, &mut keep, &mut found);
+        }
+
+        // model files have no services, their top level instances are the
+        // content itself
+        if services.is_empty() {
+            for child in data_model.children() {
+                walk_descendants(&self.dom, *child, true, &mut keep, &mut found);
+            }
+        }
+
+        // referents and child order aren't stable between loads, so everything
+        // downstream works off path order to keep runs reproducible. the sort
+        // is stable, siblings sharing a path keep their traversal order
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        found
+    }
+
+    pub fn descendant_paths(&self) -> Vec<(String, Ref)> {
+        self.descendant_paths_where(|_, _| true)
+    }
+
+    pub fn descendants(&self) -> Vec<Ref> {
+        self.descendant_paths()
+            .into_iter()
+            .map(|(_, x)| x)
+            .collect()
+    }
+
+    pub fn filtered_descendants(&self, filter: &PathFilter) -> Vec<Ref> {
+        self.descendant_paths_where(|path, _| filter.matches(path))
+            .into_iter()
+            .map(|(_, x)| x)
+            .collect()
+    }
+
+    pub fn mesh_parts(&self) -> Vec<Ref> {
+        self.descendant_paths_where(|_, referent| {
+            is_mesh_part(self.dom.get_by_ref(referent).expect("referent"))
+        })
+        .into_iter()
+        .map(|(_, x)| x)
+        .collect()
+    }
+
+    pub fn unions(&self) -> Vec<Ref> {
+        filter_unions(&self.dom, &self.descendants())
+    }
+}
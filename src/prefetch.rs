@@ -0,0 +1,90 @@
+use futures::StreamExt;
+use indicatif::ProgressBar;
+use rbx_dom_weak::types::Variant;
+use std::collections::BTreeSet;
+use tracing::warn;
+
+use crate::{
+    place::Place,
+    utils::{
+        asset_provider::AssetProvider,
+        content_url::{is_local_asset, normalize_content},
+        mesh_content::get_mesh_id,
+        shutdown::interrupted,
+        FixerError,
+    },
+};
+
+// nothing gets parsed, so the only limit is how hard we hit assetdelivery
+const PREFETCH_CONCURRENCY: usize = 32;
+
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchResult {
+    pub assets: usize,
+    pub fetched: usize,
+    pub bytes: usize,
+    pub failed: Vec<(String, String)>,
+}
+
+// every mesh and texture the place's mesh parts point at, local content is
+// left out since it never comes from the network
+pub fn referenced_assets(place: &Place) -> BTreeSet<String> {
+    let mut assets = BTreeSet::new();
+    for referent in place.mesh_parts() {
+        let instance = match place.dom.get_by_ref(referent) {
+            Some(instance) => instance,
+            None => continue,
+        };
+
+        let mut ids = vec![get_mesh_id(instance)];
+        if let Some(Variant::Content(content)) = instance.properties.get("TextureID") {
+            ids.push(content.clone().into_string());
+        }
+        for asset_id in ids {
+            let asset_id = normalize_content(&asset_id);
+            if asset_id.trim() != "" && !is_local_asset(&asset_id) {
+                assets.insert(asset_id);
+            }
+        }
+    }
+    assets
+}
+
+pub async fn prefetch_place(
+    provider: &dyn AssetProvider,
+    place: &Place,
+    bar: &ProgressBar,
+) -> Result<PrefetchResult, FixerError> {
+    let assets = referenced_assets(place);
+    bar.set_length(assets.len() as u64);
+
+    let mut result = PrefetchResult {
+        assets: assets.len(),
+        ..PrefetchResult::default()
+    };
+    // content_hash lets a caching provider skip reading back what it stores
+    let mut fetches = futures::stream::iter(assets)
+        .filter(|_| futures::future::ready(!interrupted()))
+        .map(|asset_id| async move {
+            let fetched = provider.content_hash(asset_id.clone()).await;
+            (asset_id, fetched)
+        })
+        .buffer_unordered(PREFETCH_CONCURRENCY);
+
+    while let Some((asset_id, fetched)) = fetches.next().await {
+        match fetched {
+            Ok((_, size)) => {
+                result.fetched += 1;
+                result.bytes += size;
+            }
+            Err(err) => {
+                warn!("Could not fetch {:?}: {}", asset_id, err);
+                result.failed.push((asset_id, err.to_string()));
+            }
+        }
+        bar.inc(1);
+    }
+    bar.finish();
+
+    Ok(result)
+}
@@ -0,0 +1,137 @@
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use rbxlx_mesh_fixer::{ClusterProposal, ClusterReviewer};
+use std::io::{self, Stdout};
+
+const HELP: &str =
+    "up/down move  space toggle  a accept all  r reject all  enter apply  q reject all and quit";
+
+// every cluster starts accepted, the reviewer only has to untick the ones
+// that look wrong
+pub struct TerminalReviewer;
+
+struct ReviewState {
+    decisions: Vec<bool>,
+    list: ListState,
+}
+
+fn draw(frame: &mut Frame, proposals: &[ClusterProposal], state: &mut ReviewState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let items = proposals
+        .iter()
+        .zip(state.decisions.iter())
+        .map(|(proposal, accepted)| {
+            let mark = if *accepted { "[x]" } else { "[ ]" };
+            ListItem::new(format!(
+                "{} {:>11}  {} assets  {} instances  {} triangles",
+                mark,
+                proposal.hash,
+                proposal.candidates.len(),
+                proposal.instances,
+                proposal.triangles
+            ))
+        })
+        .collect::<Vec<_>>();
+    let accepted = state.decisions.iter().filter(|x| **x).count();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Proposed merges ({} of {} accepted)",
+            accepted,
+            proposals.len()
+        )))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_stateful_widget(list, chunks[0], &mut state.list);
+
+    let details = match state.list.selected().and_then(|x| proposals.get(x)) {
+        Some(proposal) => format!(
+            "{} vertices, {} triangles, {} bytes of geometry per copy\n{}",
+            proposal.vertices,
+            proposal.triangles,
+            proposal.geometry_bytes,
+            proposal.candidates.join("\n")
+        ),
+        None => String::new(),
+    };
+    frame.render_widget(
+        Paragraph::new(details).block(Block::default().borders(Borders::ALL).title("Assets")),
+        chunks[1],
+    );
+    frame.render_widget(Paragraph::new(HELP), chunks[2]);
+}
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    proposals: &[ClusterProposal],
+) -> io::Result<Vec<bool>> {
+    let mut state = ReviewState {
+        decisions: vec![true; proposals.len()],
+        list: ListState::default(),
+    };
+    state.list.select(Some(0));
+
+    loop {
+        terminal.draw(|frame| draw(frame, proposals, &mut state))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        let selected = state.list.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.list.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state
+                    .list
+                    .select(Some((selected + 1).min(proposals.len() - 1)));
+            }
+            KeyCode::Char(' ') => state.decisions[selected] = !state.decisions[selected],
+            KeyCode::Char('a') => state.decisions.iter_mut().for_each(|x| *x = true),
+            KeyCode::Char('r') => state.decisions.iter_mut().for_each(|x| *x = false),
+            KeyCode::Enter => return Ok(state.decisions),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(vec![false; proposals.len()]),
+            _ => {}
+        }
+    }
+}
+
+impl ClusterReviewer for TerminalReviewer {
+    fn review(&self, proposals: &[ClusterProposal]) -> Vec<bool> {
+        // the review blocks on the keyboard, keep it off the async workers
+        tokio::task::block_in_place(|| {
+            enable_raw_mode().expect("raw-mode");
+            let mut stdout = io::stdout();
+            execute!(stdout, EnterAlternateScreen).expect("alternate-screen");
+            let mut terminal = Terminal::new(CrosstermBackend::new(stdout)).expect("terminal");
+
+            let decisions = run(&mut terminal, proposals);
+
+            disable_raw_mode().expect("raw-mode");
+            execute!(terminal.backend_mut(), LeaveAlternateScreen).expect("alternate-screen");
+            decisions.expect("review")
+        })
+    }
+}
@@ -0,0 +1,266 @@
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    InstanceBuilder, WeakDom,
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+};
+use tracing::{debug, info};
+
+use crate::{
+    dedupe::{DedupeResult, MeshDeduplicator},
+    place::Place,
+    utils::{
+        atomic_file::AtomicFile, content_url::normalize_content, mapping::AssetMapping,
+        mesh_content::MESH_PROPERTIES, FixerError,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ModelFormat {
+    Binary,
+    Xml,
+    Json,
+}
+
+struct ModelFile {
+    path: PathBuf,
+    format: ModelFormat,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProjectResult {
+    pub dedupe: DedupeResult,
+    pub models: usize,
+    pub rewritten_models: usize,
+    pub json_models: usize,
+    pub json_rewritten: usize,
+}
+
+fn model_format(path: &Path) -> Option<ModelFormat> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".model.json") {
+        Some(ModelFormat::Json)
+    } else if name.ends_with(".rbxm") {
+        Some(ModelFormat::Binary)
+    } else if name.ends_with(".rbxmx") {
+        Some(ModelFormat::Xml)
+    } else {
+        None
+    }
+}
+
+// hidden directories hold tooling state (.git, .vscode) rather than sources
+fn find_models(dir: &Path, models: &mut Vec<ModelFile>) -> Result<(), FixerError> {
+    let mut entries = std::fs::read_dir(dir)?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        let hidden = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .map(|x| x.starts_with('.'))
+            .unwrap_or(false);
+        if path.is_dir() && !hidden {
+            find_models(&path, models)?;
+        } else if let Some(format) = model_format(&path) {
+            models.push(ModelFile { path, format });
+        }
+    }
+
+    Ok(())
+}
+
+fn read_model(model: &ModelFile) -> Result<WeakDom, FixerError> {
+    let input_file = BufReader::new(File::open(&model.path)?);
+    match model.format {
+        ModelFormat::Binary => Ok(rbx_binary::from_reader_default(input_file)?),
+        ModelFormat::Xml => Ok(rbx_xml::from_reader_default(input_file)?),
+        ModelFormat::Json => Err("json models are rewritten separately".into()),
+    }
+}
+
+fn write_model(model: &ModelFile, dom: &WeakDom) -> Result<(), FixerError> {
+    let mut output_file = AtomicFile::create(&model.path)?;
+    let roots = dom.root().children();
+    match model.format {
+        ModelFormat::Binary => rbx_binary::to_writer_default(&mut output_file, dom, roots)?,
+        ModelFormat::Xml => rbx_xml::to_writer_default(&mut output_file, dom, roots)?,
+        ModelFormat::Json => return Err("json models are rewritten separately".into()),
+    }
+    output_file.commit()
+}
+
+fn copy_subtree(
+    source: &WeakDom,
+    referent: Ref,
+    dest: &mut WeakDom,
+    parent: Ref,
+    refs: &mut HashMap<Ref, Ref>,
+) {
+    let instance = source.get_by_ref(referent).expect("copied-instance");
+    let builder = InstanceBuilder::new(instance.class.clone())
+        .with_name(instance.name.clone())
+        .with_properties(instance.properties.clone());
+    let copy = dest.insert(parent, builder);
+    refs.insert(referent, copy);
+
+    for child in instance.children().to_vec() {
+        copy_subtree(source, child, dest, copy, refs);
+    }
+}
+
+// welds and constraints point at other instances, once everything has been
+// copied those pointers have to follow their targets into the new dom
+fn remap_refs(dom: &mut WeakDom, refs: &HashMap<Ref, Ref>) {
+    for copy in refs.values() {
+        let instance = dom.get_by_ref_mut(*copy).expect("remapped-instance");
+        for value in instance.properties.values_mut() {
+            if let Variant::Ref(target) = value {
+                *target = refs.get(target).cloned().unwrap_or_else(Ref::none);
+            }
+        }
+    }
+}
+
+fn copy_children(source: &WeakDom, from: Ref, dest: &mut WeakDom, to: Ref) {
+    let mut refs = HashMap::<Ref, Ref>::new();
+    let children = source
+        .get_by_ref(from)
+        .expect("copy-root")
+        .children()
+        .to_vec();
+    for child in children {
+        copy_subtree(source, child, dest, to, &mut refs);
+    }
+    remap_refs(dest, &refs);
+}
+
+fn rewrite_json_content(value: &mut Value, mapping: &AssetMapping) -> usize {
+    let current = match value {
+        Value::String(content) => content,
+        Value::Object(explicit) => match explicit.get_mut("Content") {
+            Some(Value::String(content)) => content,
+            _ => return 0,
+        },
+        _ => return 0,
+    };
+
+    match mapping.get(&normalize_content(current)) {
+        Some(new_id) => {
+            *current = normalize_content(new_id);
+            1
+        }
+        None => 0,
+    }
+}
+
+fn rewrite_json_instance(instance: &mut Value, mapping: &AssetMapping) -> usize {
+    let mut rewritten = 0;
+    for name in MESH_PROPERTIES.iter() {
+        if let Some(mesh_id) = instance.pointer_mut(&format!("/Properties/{}", name)) {
+            rewritten += rewrite_json_content(mesh_id, mapping);
+        }
+    }
+    if let Some(Value::Array(children)) = instance.get_mut("Children") {
+        for child in children.iter_mut() {
+            rewritten += rewrite_json_instance(child, mapping);
+        }
+    }
+    rewritten
+}
+
+fn rewrite_json_model(path: &Path, mapping: &AssetMapping) -> Result<usize, FixerError> {
+    let mut model: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let rewritten = rewrite_json_instance(&mut model, mapping);
+    if rewritten > 0 {
+        let mut output_file = AtomicFile::create(path)?;
+        serde_json::to_writer_pretty(&mut output_file, &model)?;
+        writeln!(output_file)?;
+        output_file.commit()?;
+    }
+    Ok(rewritten)
+}
+
+// every binary and xml model in the project is loaded into one dom, one
+// folder per file, so duplicates are found across files. the result is split
+// back out and written over the sources. `.model.json` files only get their
+// MeshIds swapped through the mapping, like `apply` does
+pub async fn dedupe_project(
+    deduplicator: &MeshDeduplicator,
+    project_dir: &str,
+) -> Result<ProjectResult, FixerError> {
+    let mut models = Vec::<ModelFile>::new();
+    find_models(Path::new(project_dir), &mut models)?;
+
+    let mut combined = WeakDom::new(InstanceBuilder::new("DataModel"));
+    let combined_root = combined.root_ref();
+    let mut folders = Vec::<(usize, String, Ref)>::new();
+    for (index, model) in models.iter().enumerate() {
+        if model.format == ModelFormat::Json {
+            continue;
+        }
+
+        let dom = read_model(model)?;
+        let name = model
+            .path
+            .strip_prefix(project_dir)
+            .unwrap_or(&model.path)
+            .to_string_lossy()
+            .replace('/', ":")
+            .replace('\\', ":");
+        let folder = combined.insert(
+            combined_root,
+            InstanceBuilder::new("Folder").with_name(name.clone()),
+        );
+        copy_children(&dom, dom.root_ref(), &mut combined, folder);
+        folders.push((index, name, folder));
+        debug!("Loaded {:?}", model.path);
+    }
+
+    let mut place = Place::from_dom(combined);
+    let mut result = ProjectResult {
+        dedupe: deduplicator.run(&mut place).await?,
+        models: folders.len(),
+        ..ProjectResult::default()
+    };
+
+    // untouched files are left alone so source control only sees real changes
+    for (index, name, folder) in folders {
+        let prefix = format!("{}/", name);
+        let changed = result
+            .dedupe
+            .journal
+            .entries
+            .iter()
+            .any(|x| x.path.starts_with(&prefix));
+        if !changed {
+            continue;
+        }
+
+        let model = &models[index];
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        copy_children(&place.dom, folder, &mut dom, root);
+        write_model(model, &dom)?;
+        result.rewritten_models += 1;
+    }
+
+    for model in models.iter().filter(|x| x.format == ModelFormat::Json) {
+        result.json_models += 1;
+        let rewritten = rewrite_json_model(&model.path, &result.dedupe.mapping)?;
+        if rewritten > 0 {
+            info!("Rewrote {:?} MeshIds in {:?}", rewritten, model.path);
+        }
+        result.json_rewritten += rewritten;
+    }
+
+    Ok(result)
+}
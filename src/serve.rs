@@ -0,0 +1,83 @@
+use rbxlx_mesh_fixer::{
+    bridge::{plan_merge, PlanRequest},
+    MeshDeduplicator,
+};
+use serde_json::json;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+
+use crate::http::{read_request, write_json};
+
+pub const DEFAULT_PORT: u16 = 34873;
+
+async fn handle(deduplicator: &MeshDeduplicator, mut stream: TcpStream) {
+    let request = match read_request(&mut stream).await {
+        Ok(request) => request,
+        Err(err) => {
+            write_json(&mut stream, "400 Bad Request", json!({ "error": err })).await;
+            return;
+        }
+    };
+    debug!("{} {}", request.method, request.path);
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => {
+            let version = env!("CARGO_PKG_VERSION");
+            write_json(&mut stream, "200 OK", json!({ "version": version })).await;
+        }
+        ("POST", "/plan") => {
+            let plan = match serde_json::from_slice::<PlanRequest>(&request.body) {
+                Ok(plan) => plan,
+                Err(err) => {
+                    let error = json!({ "error": err.to_string() });
+                    write_json(&mut stream, "400 Bad Request", error).await;
+                    return;
+                }
+            };
+            info!("Planning a merge for {:?} parts", plan.parts.len());
+            match plan_merge(deduplicator, &plan).await {
+                Ok(response) => {
+                    info!(
+                        "Sending {:?} changes across {:?} clusters",
+                        response.changes.len(),
+                        response.clusters
+                    );
+                    let body = serde_json::to_value(&response).expect("plan-json");
+                    write_json(&mut stream, "200 OK", body).await;
+                }
+                Err(err) => {
+                    error!("{}", err);
+                    let error = json!({ "error": err.to_string() });
+                    write_json(&mut stream, "500 Internal Server Error", error).await;
+                }
+            }
+        }
+        _ => {
+            let error = json!({ "error": "not found" });
+            write_json(&mut stream, "404 Not Found", error).await;
+        }
+    }
+}
+
+// only ever bound to loopback, the plugin runs on the same machine. requests
+// are handled one at a time so two plans never race on the database
+pub async fn run_serve(deduplicator: &MeshDeduplicator, port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .expect("could not bind the plugin port");
+    info!("Waiting for the studio plugin on http://127.0.0.1:{}", port);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!("Could not accept a connection: {}", err);
+                    continue;
+                }
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        handle(deduplicator, stream).await;
+    }
+}
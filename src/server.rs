@@ -0,0 +1,120 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rbxlx_mesh_fixer::{summary::RunSummary, FixerError, MeshDeduplicator, Place};
+use serde_json::json;
+use std::time::Instant;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{error, info, warn};
+
+use crate::http::{read_request, write_json, Request};
+
+async fn fix_place(
+    deduplicator: &MeshDeduplicator,
+    data: &[u8],
+) -> Result<serde_json::Value, FixerError> {
+    let mut place = Place::from_reader(data)?;
+    let result = deduplicator.run(&mut place).await?;
+    let mut output = Vec::<u8>::new();
+    place.to_writer(&mut output)?;
+
+    let mut summary = RunSummary::default();
+    summary.add(&result);
+    summary.finish();
+    Ok(json!({
+        "summary": summary,
+        "report": result.report,
+        "place": STANDARD.encode(&output),
+    }))
+}
+
+// compares every byte whatever the first mismatch, so the time taken gives
+// nothing about the token away
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let token = match token {
+        Some(token) => token,
+        None => return true,
+    };
+    let expected = format!("Bearer {}", token);
+    let given = request.authorization.as_deref().unwrap_or_default();
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn handle(deduplicator: &MeshDeduplicator, token: Option<&str>, mut stream: TcpStream) {
+    let request = match read_request(&mut stream).await {
+        Ok(request) => request,
+        Err(err) => {
+            write_json(&mut stream, "400 Bad Request", json!({ "error": err })).await;
+            return;
+        }
+    };
+    if request.path != "/health" && !authorized(&request, token) {
+        let error = json!({ "error": "missing or wrong Authorization bearer token" });
+        write_json(&mut stream, "401 Unauthorized", error).await;
+        return;
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => {
+            let version = env!("CARGO_PKG_VERSION");
+            write_json(&mut stream, "200 OK", json!({ "version": version })).await;
+        }
+        // the place goes in as the raw rbxl body, the fixed one comes back
+        // base64 encoded next to the report
+        ("POST", "/fix") => {
+            let start = Instant::now();
+            match fix_place(deduplicator, &request.body).await {
+                Ok(body) => {
+                    info!(
+                        "Fixed a {:?} byte place in {:.2?}",
+                        request.body.len(),
+                        start.elapsed()
+                    );
+                    write_json(&mut stream, "200 OK", body).await;
+                }
+                Err(err) => {
+                    error!("{}", err);
+                    let body = json!({ "summary": RunSummary::fatal(err.to_string()) });
+                    write_json(&mut stream, "500 Internal Server Error", body).await;
+                }
+            }
+        }
+        _ => {
+            let error = json!({ "error": "not found" });
+            write_json(&mut stream, "404 Not Found", error).await;
+        }
+    }
+}
+
+// one deduplicator for the lifetime of the process, so the asset cache,
+// signature cache, database and http client stay warm between places.
+// places are fixed one at a time in the order they arrive. with a token set
+// every request but /health has to carry it as a bearer token
+pub async fn run_server(
+    deduplicator: &MeshDeduplicator,
+    bind: &str,
+    port: u16,
+    token: Option<&str>,
+) {
+    let listener = TcpListener::bind((bind, port))
+        .await
+        .expect("could not bind the server port");
+    info!("Accepting places on http://{}:{}/fix", bind, port);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!("Could not accept a connection: {}", err);
+                    continue;
+                }
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        handle(deduplicator, token, stream).await;
+    }
+}
@@ -0,0 +1,140 @@
+use glam::Vec3;
+use rbx_dom_weak::types::{Ref, Variant};
+use rbx_types::Vector3;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::{
+    place::{has_wrap_instance, Place},
+    utils::{
+        asset_provider::AssetProvider,
+        content_url::normalize_content,
+        instance_path,
+        math::{vec3, vector3},
+        mesh_content::get_mesh_id,
+        mesh_reader::RobloxMesh,
+        reflection::property_or_default,
+        FixerError,
+    },
+};
+
+// relative difference per axis before an InitialSize counts as stale,
+// studio itself rounds the imported bounds
+const SIZE_TOLERANCE: f32 = 0.01;
+// flat meshes have no extent along one axis to compare against
+const MIN_EXTENT: f32 = 1e-4;
+
+#[derive(Debug, Clone, Default)]
+pub struct SizeFixResult {
+    pub checked: usize,
+    pub repaired: usize,
+    pub failed: usize,
+}
+
+fn get_vector3(place: &Place, referent: Ref, name: &str) -> Option<Vector3> {
+    let instance = place.dom.get_by_ref(referent)?;
+    match property_or_default(instance, name) {
+        Some(Variant::Vector3(value)) => Some(value),
+        _ => None,
+    }
+}
+
+// the InitialSize the mesh would have been imported with, axes the mesh is
+// flat along keep the one the part has
+fn expected_initial_size(extent: Vec3, initial_size: Vec3) -> Vec3 {
+    Vec3::select(extent.cmpgt(Vec3::splat(MIN_EXTENT)), extent, initial_size)
+}
+
+fn is_stale(expected: Vec3, initial_size: Vec3) -> bool {
+    let difference = (expected - initial_size).abs();
+    difference.cmpgt(expected.abs() * SIZE_TOLERANCE).any()
+}
+
+// points every MeshPart's InitialSize back at its mesh's real bounds. Size
+// keeps the scale the part had relative to its old InitialSize, so a part
+// that was stretched stays stretched by the same amount
+pub async fn fix_place_sizes(
+    provider: &dyn AssetProvider,
+    place: &mut Place,
+) -> Result<SizeFixResult, FixerError> {
+    let mut result = SizeFixResult::default();
+    let mut extents = HashMap::<String, Option<Vec3>>::new();
+
+    for referent in place.mesh_parts() {
+        // cages are authored against the current size, same as in dedupe
+        if has_wrap_instance(&place.dom, referent) {
+            continue;
+        }
+        let instance = place.dom.get_by_ref(referent).expect("mesh-part");
+        let mesh_id = normalize_content(&get_mesh_id(instance));
+        if mesh_id.trim() == "" {
+            continue;
+        }
+        // parts that were never rescaled may not carry an InitialSize, their
+        // size is the one the mesh was imported at
+        let size = match get_vector3(place, referent, "Size") {
+            Some(size) => vec3(size),
+            None => continue,
+        };
+        let initial_size = match instance.properties.get("InitialSize") {
+            Some(Variant::Vector3(initial_size)) => vec3(*initial_size),
+            _ => size,
+        };
+
+        if !extents.contains_key(&mesh_id) {
+            let extent = match provider.fetch(mesh_id.clone()).await {
+                Ok(mut data) => match RobloxMesh::read_signature(&mut data) {
+                    Ok(signature) => Some(
+                        vec3(signature.bounding_box_size.max)
+                            - vec3(signature.bounding_box_size.min),
+                    ),
+                    Err(err) => {
+                        warn!("Could not parse {:?}: {}", mesh_id, err);
+                        None
+                    }
+                },
+                Err(err) => {
+                    warn!("Could not fetch {:?}: {}", mesh_id, err);
+                    None
+                }
+            };
+            extents.insert(mesh_id.clone(), extent);
+        }
+        let extent = match extents[&mesh_id] {
+            Some(extent) => extent,
+            None => {
+                result.failed += 1;
+                continue;
+            }
+        };
+
+        result.checked += 1;
+        let expected = expected_initial_size(extent, initial_size);
+        if !is_stale(expected, initial_size) {
+            continue;
+        }
+
+        let scale = size / initial_size.max(Vec3::splat(MIN_EXTENT));
+        let new_size = expected * scale;
+        info!(
+            "{}: InitialSize {:?} doesn't match {:?}, resizing {:?} to {:?}",
+            instance_path(&place.dom, referent),
+            vector3(initial_size),
+            mesh_id,
+            vector3(size),
+            vector3(new_size)
+        );
+
+        let instance = place.dom.get_by_ref_mut(referent).expect("mesh-part");
+        instance.properties.insert(
+            String::from("InitialSize"),
+            Variant::Vector3(vector3(expected)),
+        );
+        instance
+            .properties
+            .insert(String::from("Size"), Variant::Vector3(vector3(new_size)));
+        result.repaired += 1;
+    }
+
+    Ok(result)
+}
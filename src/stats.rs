@@ -0,0 +1,310 @@
+use rbx_dom_weak::types::{Ref, Variant};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::warn;
+
+use crate::{
+    place::Place,
+    utils::{
+        asset_provider::AssetProvider,
+        content_url::{is_local_asset, normalize_content},
+        mesh_content::get_mesh_id,
+        mesh_reader::RobloxMesh,
+        signature_cache::{SignatureCache, SignatureRecord},
+        surface_appearance::{find_surface_appearances, SURFACE_APPEARANCE_MAPS},
+        texture_header::{TextureFormat, TextureHeader},
+        FixerError,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct MeshStats {
+    pub asset_id: String,
+    pub instances: usize,
+    pub triangles: i32,
+    pub volume: f32,
+    pub surface_area: f32,
+    pub download_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureStats {
+    pub asset_id: String,
+    pub instances: usize,
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub memory_bytes: usize,
+}
+
+// everything that decides whether two mesh parts can be drawn in one batch
+// once their meshes are merged
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MaterialKey {
+    pub texture_id: String,
+    pub material: u32,
+    pub material_variant: String,
+    pub surface_appearance: Vec<String>,
+}
+
+// a duplicate cluster and how many distinct materials its instances use.
+// a cluster with a single material collapses to one draw call after dedupe
+#[derive(Debug, Clone)]
+pub struct ClusterMaterials {
+    pub canonical: String,
+    pub assets: usize,
+    pub instances: usize,
+    pub materials: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaceStats {
+    pub mesh_parts: usize,
+    pub unique_mesh_ids: usize,
+    pub unique_texture_ids: usize,
+    pub duplicate_clusters: usize,
+    pub duplicate_assets: usize,
+    pub total_triangles: i64,
+    pub download_bytes: usize,
+    pub heaviest: Vec<MeshStats>,
+    pub failed: usize,
+    // textures from TextureID and SurfaceAppearance maps alike
+    pub texture_memory: usize,
+    pub texture_download_bytes: usize,
+    pub heaviest_textures: Vec<TextureStats>,
+    pub failed_textures: usize,
+    pub shared_material_clusters: usize,
+    pub cluster_materials: Vec<ClusterMaterials>,
+}
+
+fn get_content(place: &Place, referent: Ref, name: &str) -> String {
+    match place
+        .dom
+        .get_by_ref(referent)
+        .and_then(|x| x.properties.get(name))
+    {
+        Some(Variant::Content(content)) => content.clone().into_string(),
+        _ => String::new(),
+    }
+}
+
+fn material_key(place: &Place, referent: Ref) -> MaterialKey {
+    let instance = place.dom.get_by_ref(referent);
+    let property = |name: &str| instance.and_then(|x| x.properties.get(name));
+
+    let material = match property("Material") {
+        Some(Variant::Enum(material)) => material.to_u32(),
+        _ => 0,
+    };
+    let material_variant = match property("MaterialVariant") {
+        Some(Variant::String(variant)) => variant.clone(),
+        _ => String::new(),
+    };
+    let surface_appearance = find_surface_appearances(&place.dom, &[referent])
+        .into_iter()
+        .flat_map(|x| {
+            SURFACE_APPEARANCE_MAPS
+                .iter()
+                .map(move |map| normalize_content(&get_content(place, x, map)))
+        })
+        .collect();
+
+    MaterialKey {
+        texture_id: normalize_content(&get_content(place, referent, "TextureID")),
+        material,
+        material_variant,
+        surface_appearance,
+    }
+}
+
+async fn collect_texture_stats(
+    provider: &dyn AssetProvider,
+    textures: &BTreeMap<String, usize>,
+    stats: &mut PlaceStats,
+    top: usize,
+) {
+    let mut heaviest = Vec::<TextureStats>::new();
+    for (asset_id, count) in textures.iter() {
+        let data = match provider.fetch(asset_id.clone()).await {
+            Ok(data) => data.into_inner(),
+            Err(err) => {
+                warn!("Could not fetch {:?}: {}", asset_id, err);
+                stats.failed_textures += 1;
+                continue;
+            }
+        };
+        let header = match TextureHeader::read(&data) {
+            Ok(header) => header,
+            Err(err) => {
+                warn!("Could not read {:?}: {}", asset_id, err);
+                stats.failed_textures += 1;
+                continue;
+            }
+        };
+
+        // a texture is uploaded to the gpu once however many parts use it
+        stats.texture_memory += header.memory_bytes();
+        if !is_local_asset(asset_id) {
+            stats.texture_download_bytes += data.len();
+        }
+        heaviest.push(TextureStats {
+            asset_id: asset_id.clone(),
+            instances: *count,
+            format: header.format,
+            width: header.width,
+            height: header.height,
+            memory_bytes: header.memory_bytes(),
+        });
+    }
+
+    heaviest.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes));
+    heaviest.truncate(top);
+    stats.heaviest_textures = heaviest;
+}
+
+pub async fn collect_stats(
+    provider: &dyn AssetProvider,
+    cache: Option<&SignatureCache>,
+    place: &Place,
+    top: usize,
+) -> Result<PlaceStats, FixerError> {
+    let mesh_parts = place.mesh_parts();
+    let mut instances = BTreeMap::<String, usize>::new();
+    let mut texture_ids = BTreeSet::<String>::new();
+    let mut textures = BTreeMap::<String, usize>::new();
+    let mut materials = BTreeMap::<String, BTreeSet<MaterialKey>>::new();
+    let mut stats = PlaceStats {
+        mesh_parts: mesh_parts.len(),
+        ..PlaceStats::default()
+    };
+
+    for referent in mesh_parts.iter() {
+        let mesh_id = place
+            .dom
+            .get_by_ref(*referent)
+            .map(get_mesh_id)
+            .unwrap_or_default();
+        let material = material_key(place, *referent);
+        if mesh_id.trim() != "" {
+            let mesh_id = normalize_content(&mesh_id);
+            *instances.entry(mesh_id.clone()).or_insert(0) += 1;
+            materials
+                .entry(mesh_id)
+                .or_default()
+                .insert(material.clone());
+        }
+
+        if material.texture_id.trim() != "" {
+            texture_ids.insert(material.texture_id.clone());
+        }
+        for texture_id in
+            std::iter::once(&material.texture_id).chain(material.surface_appearance.iter())
+        {
+            if texture_id.trim() != "" {
+                *textures.entry(texture_id.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+    stats.unique_mesh_ids = instances.len();
+    stats.unique_texture_ids = texture_ids.len();
+
+    // clustered with the same hash the dedupe pass uses, so the counts here
+    // match what a real run would merge
+    let mut clusters = BTreeMap::<i32, Vec<String>>::new();
+    let mut meshes = Vec::<MeshStats>::new();
+    for (asset_id, count) in instances.iter() {
+        let mut data = match provider.fetch(asset_id.clone()).await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Could not fetch {:?}: {}", asset_id, err);
+                stats.failed += 1;
+                continue;
+            }
+        };
+        let download_bytes = data.get_ref().len();
+        let key = cache.map(|_| SignatureCache::key(data.get_ref(), false));
+        let cached = match (cache, &key) {
+            (Some(cache), Some(key)) => cache.get(key),
+            _ => None,
+        };
+        // only the hash and triangle count are needed, so the geometry is
+        // streamed past instead of parsed
+        let mesh = match cached {
+            Some(record) => record,
+            None => match RobloxMesh::read_signature(&mut data) {
+                Ok(signature) => {
+                    let record = SignatureRecord::from_signature(&signature);
+                    if let (Some(cache), Some(key)) = (cache, key) {
+                        cache.insert(key, record.clone());
+                    }
+                    record
+                }
+                Err(err) => {
+                    warn!("Could not parse {:?}: {}", asset_id, err);
+                    stats.failed += 1;
+                    continue;
+                }
+            },
+        };
+
+        clusters
+            .entry(mesh.hash)
+            .or_default()
+            .push(asset_id.clone());
+        stats.total_triangles += mesh.triangles as i64 * *count as i64;
+        if !is_local_asset(asset_id) {
+            stats.download_bytes += download_bytes;
+        }
+        meshes.push(MeshStats {
+            asset_id: asset_id.clone(),
+            instances: *count,
+            triangles: mesh.triangles,
+            volume: mesh.volume,
+            surface_area: mesh.surface_area,
+            download_bytes,
+        });
+    }
+
+    let mut cluster_materials = Vec::<ClusterMaterials>::new();
+    for assets in clusters.values().filter(|x| x.len() > 1) {
+        stats.duplicate_clusters += 1;
+        stats.duplicate_assets += assets.len() - 1;
+
+        let keys = assets
+            .iter()
+            .filter_map(|x| materials.get(x))
+            .flatten()
+            .collect::<BTreeSet<_>>();
+        if keys.len() == 1 {
+            stats.shared_material_clusters += 1;
+        }
+        cluster_materials.push(ClusterMaterials {
+            canonical: assets[0].clone(),
+            assets: assets.len(),
+            instances: assets.iter().filter_map(|x| instances.get(x)).sum(),
+            materials: keys.len(),
+        });
+    }
+    // the clusters that leave the most draw calls behind after a dedupe
+    // are the ones worth looking at first
+    cluster_materials.sort_by(|a, b| {
+        b.materials
+            .cmp(&a.materials)
+            .then(b.instances.cmp(&a.instances))
+    });
+    cluster_materials.truncate(top);
+    stats.cluster_materials = cluster_materials;
+
+    collect_texture_stats(provider, &textures, &mut stats, top).await;
+
+    if let Some(cache) = cache {
+        if let Err(err) = cache.save() {
+            warn!("Could not save signature cache {:?}: {}", cache.path(), err);
+        }
+    }
+
+    meshes.sort_by(|a, b| b.triangles.cmp(&a.triangles));
+    meshes.truncate(top);
+    stats.heaviest = meshes;
+
+    Ok(stats)
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+
+use crate::{
+    dedupe::DedupeResult,
+    utils::{atomic_file::AtomicFile, FixerError},
+};
+
+// exit codes build scripts can rely on, an interrupted run is a partial one
+// and says so in the summary's status
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_PARTIAL: i32 = 1;
+pub const EXIT_FATAL: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Ok,
+    Partial,
+    Interrupted,
+    Fatal,
+}
+
+impl Default for RunStatus {
+    fn default() -> Self {
+        RunStatus::Ok
+    }
+}
+
+// instance counts across every place in the run. scanned is every mesh part
+// the filters let through, merged the ones pointed at a canonical mesh,
+// failed the ones whose mesh couldn't be fetched or parsed and skipped the
+// rest, which were unique or already canonical
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub status: RunStatus,
+    pub exit_code: i32,
+    pub places: usize,
+    pub failed_places: usize,
+    pub scanned: usize,
+    pub merged: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub clusters: usize,
+    // clusters under --min-confidence, listed in the report but not applied
+    pub needs_review: usize,
+    pub failed_assets: Vec<String>,
+    pub error: Option<String>,
+    // set when Ctrl-C stopped the run before it got through everything
+    pub interrupted: bool,
+}
+
+impl RunSummary {
+    pub fn fatal(error: String) -> Self {
+        RunSummary {
+            status: RunStatus::Fatal,
+            exit_code: EXIT_FATAL,
+            error: Some(error),
+            ..RunSummary::default()
+        }
+    }
+
+    // what is left to say when a second Ctrl-C gives up on winding down
+    pub fn aborted() -> Self {
+        RunSummary {
+            status: RunStatus::Interrupted,
+            exit_code: EXIT_PARTIAL,
+            interrupted: true,
+            ..RunSummary::default()
+        }
+    }
+
+    pub fn add(&mut self, result: &DedupeResult) {
+        let merged: usize = result
+            .report
+            .clusters
+            .iter()
+            .filter(|x| x.applied)
+            .map(|x| x.instances.len())
+            .sum();
+        let failed: usize = result.failures.iter().map(|x| x.instances).sum();
+
+        self.places += 1;
+        self.scanned += result.scanned;
+        self.merged += merged;
+        self.failed += failed;
+        self.skipped += result.scanned.saturating_sub(merged + failed);
+        self.clusters += result.report.clusters.iter().filter(|x| x.applied).count();
+        self.needs_review += result.report.clusters.iter().filter(|x| !x.applied).count();
+        self.failed_assets
+            .extend(result.failures.iter().map(|x| x.asset_id.clone()));
+    }
+
+    pub fn add_failed_place(&mut self) {
+        self.places += 1;
+        self.failed_places += 1;
+    }
+
+    // settles the status once everything is added and returns the exit code
+    pub fn finish(&mut self) -> i32 {
+        self.status = if self.interrupted {
+            RunStatus::Interrupted
+        } else if self.places > 0 && self.failed_places == self.places {
+            RunStatus::Fatal
+        } else if self.failed_places > 0 || self.failed > 0 {
+            RunStatus::Partial
+        } else {
+            RunStatus::Ok
+        };
+        self.exit_code = match self.status {
+            RunStatus::Ok => EXIT_OK,
+            RunStatus::Partial | RunStatus::Interrupted => EXIT_PARTIAL,
+            RunStatus::Fatal => EXIT_FATAL,
+        };
+        self.exit_code
+    }
+
+    pub fn write(&self, output_path: &str) -> Result<(), FixerError> {
+        let mut output_file = AtomicFile::create(output_path)?;
+        serde_json::to_writer_pretty(&mut output_file, self)?;
+        output_file.commit()
+    }
+}
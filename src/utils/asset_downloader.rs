@@ -1,46 +1,664 @@
 use std::{
+    collections::BTreeSet,
     fs::{metadata, File},
     io::{self, Cursor, Read},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
 };
+use tokio::time::{timeout, timeout_at, Instant};
 
-use regex::Regex;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use md5::{Digest, Md5};
+use reqwest::{
+    header::{
+        HeaderName, ACCEPT_ENCODING, CONTENT_ENCODING, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        IF_RANGE, LAST_MODIFIED, RANGE, RETRY_AFTER,
+    },
+    Client, RequestBuilder, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+use super::{
+    atomic_file::write_atomic,
+    blob_store::{BlobId, BlobStore, Validators},
+    cache_lock::CacheLock,
+    content_url::{parse_content_url, ContentUrl},
+    rate_limit::{RateLimiter, DEFAULT_REQUESTS_PER_SECOND},
+    remote_cache::RemoteCache,
+    FixerError,
+};
+
+// these used to live here, kept so existing imports keep working
+pub use super::{
+    content_url::{extract_assetid, is_local_asset},
+    unavailable::{as_unavailable, AssetUnavailable, UnavailableReason},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssetCreator {
+    pub id: u64,
+    pub name: String,
+    pub creator_type: String,
+    pub creator_target_id: u64,
+}
 
-use super::GenericError;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssetDetails {
+    pub asset_id: u64,
+    pub name: String,
+    pub creator: AssetCreator,
+    pub created: String,
+    pub updated: String,
+}
+
+pub const DEFAULT_ASSET_DELIVERY_URL: &str = "https://assetdelivery.roblox.com";
 
-pub fn extract_assetid(asset_id: String) -> Result<String, GenericError> {
-    let regex = Regex::new(r"(?m)(\d+)")?;
-    let result = regex.find(&asset_id).ok_or("Invalid regex")?;
-    Ok(result.as_str().to_string())
+// blobs, partial downloads and their locks all live under here
+pub const DEFAULT_CACHE_DIR: &str = "cache";
+// a 429 is retried this many times, each one slowing the host down further
+const MAX_THROTTLED_RETRIES: usize = 5;
+// a connection that goes quiet for this long is given up on, whatever it
+// already wrote to the partial file is resumed on the next attempt
+pub(crate) const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// uploads send the whole body before the server answers at all
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+// clones share the list of assets an offline run had to go without
+#[derive(Debug, Clone)]
+pub struct AssetDownloader {
+    cache_dir: PathBuf,
+    base_url: String,
+    offline: bool,
+    revalidate: bool,
+    revalidated: Arc<Mutex<BTreeSet<String>>>,
+    missing: Arc<Mutex<BTreeSet<String>>>,
+    remote: Option<Arc<RemoteCache>>,
+    client: Client,
+    limiter: Arc<RateLimiter>,
+    deadline: Option<Instant>,
 }
 
-pub async fn download_asset(asset_id: String) -> Result<Cursor<Vec<u8>>, GenericError> {
-    let extracted_asset_id = extract_assetid(asset_id)?;
-    let asset_path = format!("cache/{}", extracted_asset_id);
-    let asset_url = format!(
-        "https://assetdelivery.roblox.com/v1/asset?id={}",
-        extracted_asset_id
-    );
+impl Default for AssetDownloader {
+    fn default() -> Self {
+        AssetDownloader::new(DEFAULT_CACHE_DIR)
+    }
+}
+
+impl AssetDownloader {
+    pub fn new<P: Into<PathBuf>>(cache_dir: P) -> Self {
+        AssetDownloader {
+            cache_dir: cache_dir.into(),
+            base_url: DEFAULT_ASSET_DELIVERY_URL.to_string(),
+            offline: false,
+            revalidate: false,
+            revalidated: Arc::new(Mutex::new(BTreeSet::new())),
+            missing: Arc::new(Mutex::new(BTreeSet::new())),
+            remote: None,
+            // decoding is done by hand after the transfer, Range offsets
+            // count encoded bytes and resuming has to line up with them
+            client: Client::builder()
+                .no_gzip()
+                .no_deflate()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("http-client"),
+            limiter: Arc::new(RateLimiter::new(DEFAULT_REQUESTS_PER_SECOND)),
+            deadline: None,
+        }
+    }
+
+    // checked after the local store and before roblox, anything downloaded
+    // from roblox gets pushed there for the next runner
+    pub fn with_remote_cache(mut self, remote: Arc<RemoteCache>) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    // for proxies, caching mirrors or regional endpoints that serve the same
+    // `/v1/asset` api
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // clones share the limiter, so parallel places still count as one client
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    // cached copies of unpinned ids get a conditional request, so assets
+    // re-uploaded under the same id are picked up without a full download
+    pub fn with_revalidate(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    // past the deadline nothing new is fetched, cached assets are still served
+    // and everything else is reported as unavailable. a download still in
+    // flight when it passes is cut off
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    // never touches the network, anything not already cached is reported as
+    // unavailable and remembered for `missing_assets`
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn missing_assets(&self) -> Vec<String> {
+        let missing = self.missing.lock().expect("offline-missing");
+        missing.iter().cloned().collect()
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    pub fn store(&self) -> BlobStore {
+        BlobStore::new(&self.cache_dir)
+    }
+
+    pub async fn download(&self, asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError> {
+        let id = self.download_blob(asset_id).await?;
+        Ok(Cursor::new(self.store().read(id)?))
+    }
+
+    // makes sure the asset is in the store and returns which blob holds it,
+    // without reading the blob back when it was already cached
+    pub async fn download_blob(&self, asset_id: String) -> Result<BlobId, FixerError> {
+        if is_local_asset(&asset_id) {
+            return Err(format!("{} is a local content path, not an asset id", asset_id).into());
+        }
+
+        // pinned versions are indexed separately from the latest upload since
+        // the geometry behind the same id can change between versions
+        let (cache_key, asset_url, pinned) = match parse_content_url(&asset_id)? {
+            ContentUrl::AssetId {
+                id,
+                version: Some(version),
+            } => (
+                format!("{}-v{}", id, version),
+                format!("{}/v1/asset?id={}&version={}", self.base_url, id, version),
+                true,
+            ),
+            ContentUrl::AssetId { id, version: None } => (
+                id.to_string(),
+                format!("{}/v1/asset?id={}", self.base_url, id),
+                false,
+            ),
+            _ => return Err(format!("{:?} does not reference an asset id", asset_id).into()),
+        };
 
-    let path = Path::new(&asset_path);
-    if !metadata(path).is_ok() {
-        let mut response = reqwest::get(&asset_url).await?;
-        assert!(response.status().is_success());
+        let store = self.store();
+        let cached = match store.lookup(&cache_key) {
+            Some(id) => Some(id),
+            None => store.import_legacy(&cache_key)?,
+        };
+        if let Some(id) = cached {
+            // a pinned version can never change, and each asset is only
+            // asked about once per run
+            if !self.revalidate || self.offline || pinned || !self.mark_revalidated(&cache_key) {
+                return Ok(id);
+            }
 
-        let mut file = tokio::fs::File::create(path)
+            let _lock = CacheLock::acquire(&self.partial_dir(), &cache_key).await?;
+            let validators = store.validators(&cache_key).unwrap_or_default();
+            let fetched = self
+                .before_deadline(
+                    &asset_id,
+                    self.fetch_resumable(&asset_id, &cache_key, &asset_url, Some(&validators)),
+                )
+                .await;
+            let (data, validators) = match fetched {
+                Ok(Some(fetched)) => fetched,
+                Ok(None) => return Ok(id),
+                Err(err) => {
+                    warn!(
+                        "Could not revalidate {:?}, keeping the cached copy: {}",
+                        asset_id, err
+                    );
+                    return Ok(id);
+                }
+            };
+            info!("{:?} changed upstream, refreshed the cached copy", asset_id);
+            return self
+                .store_download(&store, &asset_id, &cache_key, data, validators)
+                .await;
+        }
+
+        if self.offline {
+            let mut missing = self.missing.lock().expect("offline-missing");
+            missing.insert(asset_id.clone());
+            return Err(AssetUnavailable {
+                asset_id,
+                reason: UnavailableReason::NotCached,
+            }
+            .into());
+        }
+
+        // another run sharing the cache may be fetching the same asset, once
+        // it lets go the asset is usually there already
+        let _lock = self
+            .before_deadline(
+                &asset_id,
+                CacheLock::acquire(&self.partial_dir(), &cache_key),
+            )
+            .await?;
+        if let Some(id) = store.lookup(&cache_key) {
+            return Ok(id);
+        }
+
+        // a broken remote cache only costs the shortcut, never the run
+        if let Some(remote) = &self.remote {
+            let fetched = remote.get(&cache_key).await.map_err(|x| x.to_string());
+            match fetched {
+                Ok(Some(data)) => return store.insert(&cache_key, &data),
+                Ok(None) => {}
+                Err(err) => warn!("Remote cache lookup for {:?} failed: {}", asset_id, err),
+            }
+        }
+
+        let (data, validators) = self
+            .before_deadline(
+                &asset_id,
+                self.fetch_resumable(&asset_id, &cache_key, &asset_url, None),
+            )
+            .await?
+            .expect("unconditional-fetch");
+        self.store_download(&store, &asset_id, &cache_key, data, validators)
             .await
-            .expect("Unable to cache file");
+    }
 
-        while let Some(chunk) = response.chunk().await? {
-            file.write(&chunk).await?;
+    async fn before_deadline<T, F>(&self, asset_id: &str, future: F) -> Result<T, FixerError>
+    where
+        F: std::future::Future<Output = Result<T, FixerError>>,
+    {
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => return future.await,
+        };
+        match timeout_at(deadline, future).await {
+            Ok(result) => result,
+            Err(_) => Err(AssetUnavailable {
+                asset_id: asset_id.to_string(),
+                reason: UnavailableReason::DeadlineExceeded,
+            }
+            .into()),
         }
+    }
 
+    // requests to roblox's other apis share the client, the rate limiter, the
+    // 429 backoff and the deadline with downloads. `build` is called again
+    // for every retry since a sent request can't be reused
+    pub async fn send<F>(&self, build: F) -> Result<Response, FixerError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        self.send_within(build, READ_TIMEOUT).await
+    }
+
+    pub async fn send_upload<F>(&self, build: F) -> Result<Response, FixerError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        self.send_within(build, UPLOAD_TIMEOUT).await
+    }
+
+    async fn send_within<F>(&self, build: F, limit: Duration) -> Result<Response, FixerError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let mut throttled = 0;
+        loop {
+            let request = build(&self.client).build()?;
+            let url = request.url().to_string();
+            let host = request.url().host_str().unwrap_or_default().to_string();
+
+            let sent = async {
+                self.limiter.acquire(&host).await;
+                match timeout(limit, self.client.execute(request)).await {
+                    Ok(response) => response.map_err(FixerError::from),
+                    Err(_) => Err(FixerError::from(format!(
+                        "{} did not answer within {:?}",
+                        url, limit
+                    ))),
+                }
+            };
+            let response = self.before_deadline(&url, sent).await?;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS
+                && throttled < MAX_THROTTLED_RETRIES
+            {
+                throttled += 1;
+                let retry_after = header_value(&response, RETRY_AFTER)
+                    .and_then(|x| x.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                self.limiter.throttled(&host, retry_after);
+                continue;
+            }
+            if response.status().is_success() {
+                self.limiter.succeeded(&host);
+            }
+            return Ok(response);
+        }
+    }
+
+    // the body of a `send` response, given up on once it stalls
+    pub async fn read_body(&self, mut response: Response) -> Result<Vec<u8>, FixerError> {
+        let url = response.url().to_string();
+        let mut data = Vec::new();
+        loop {
+            let chunk = timeout(READ_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| format!("Reading {} stalled for {:?}", url, READ_TIMEOUT))??;
+            match chunk {
+                Some(chunk) => data.extend_from_slice(&chunk),
+                None => return Ok(data),
+            }
+        }
+    }
+
+    pub async fn read_json<T: DeserializeOwned>(
+        &self,
+        response: Response,
+    ) -> Result<T, FixerError> {
+        Ok(serde_json::from_slice(&self.read_body(response).await?)?)
+    }
+
+    pub async fn asset_details(&self, asset_id: String) -> Result<AssetDetails, FixerError> {
+        let extracted_asset_id = extract_assetid(asset_id)?;
+        let details_url = format!(
+            "https://economy.roblox.com/v2/assets/{}/details",
+            extracted_asset_id
+        );
+
+        let response = self.send(|client| client.get(&details_url)).await?;
+        if !response.status().is_success() {
+            return Err(FixerError::http_status(&details_url, response.status()));
+        }
+        self.read_json(response).await
+    }
+
+    fn partial_dir(&self) -> PathBuf {
+        self.cache_dir.join("partial")
+    }
+
+    fn mark_revalidated(&self, cache_key: &str) -> bool {
+        let mut revalidated = self.revalidated.lock().expect("revalidated");
+        revalidated.insert(cache_key.to_string())
+    }
+
+    async fn store_download(
+        &self,
+        store: &BlobStore,
+        asset_id: &str,
+        cache_key: &str,
+        data: Vec<u8>,
+        validators: Validators,
+    ) -> Result<BlobId, FixerError> {
+        let id = store.insert(cache_key, &data)?;
+        store.set_validators(cache_key, &validators)?;
+        if let Some(remote) = &self.remote {
+            let pushed = remote
+                .put(cache_key, &data)
+                .await
+                .map_err(|x| x.to_string());
+            if let Err(err) = pushed {
+                warn!("Could not push {:?} to the remote cache: {}", asset_id, err);
+            }
+        }
+        Ok(id)
+    }
+
+    // downloads land in `partial/<cache key>` and only reach the store once
+    // complete, so an interrupted transfer never looks like a cached asset.
+    // the next attempt picks up where it stopped with a Range request. with
+    // validators the request is conditional and None means not modified.
+    // callers hold the key's CacheLock for the whole fetch
+    async fn fetch_resumable(
+        &self,
+        asset_id: &str,
+        cache_key: &str,
+        asset_url: &str,
+        conditional: Option<&Validators>,
+    ) -> Result<Option<(Vec<u8>, Validators)>, FixerError> {
+        let partial_dir = self.partial_dir();
+        tokio::fs::create_dir_all(&partial_dir).await?;
+        let part_path = partial_dir.join(cache_key);
+        let meta_path = partial_dir.join(format!("{}.json", cache_key));
+
+        let host = Url::parse(asset_url)
+            .map_err(|err| format!("Invalid asset url {:?}: {}", asset_url, err))?
+            .host_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut meta = read_partial_meta(&meta_path);
+        let mut throttled = 0;
+        let (mut response, offset) = loop {
+            let offset = match (&meta, metadata(&part_path), conditional) {
+                (Some(_), Ok(part), None) => part.len(),
+                _ => 0,
+            };
+
+            let mut request = self
+                .client
+                .get(asset_url)
+                .header(ACCEPT_ENCODING, "gzip, deflate");
+            if offset > 0 {
+                request = request.header(RANGE, format!("bytes={}-", offset));
+                // the server only honours the range if the asset is still
+                // the one we started on, otherwise it sends all of it
+                if let Some(etag) = meta.as_ref().and_then(|x| x.etag.as_ref()) {
+                    request = request.header(IF_RANGE, etag.as_str());
+                }
+            }
+            if let Some(validators) = conditional {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(IF_NONE_MATCH, etag.as_str());
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+                }
+            }
+
+            self.limiter.acquire(&host).await;
+            let response = timeout(READ_TIMEOUT, request.send())
+                .await
+                .map_err(|_| format!("{} did not answer within {:?}", asset_url, READ_TIMEOUT))??;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS
+                && throttled < MAX_THROTTLED_RETRIES
+            {
+                throttled += 1;
+                let retry_after = header_value(&response, RETRY_AFTER)
+                    .and_then(|x| x.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                self.limiter.throttled(&host, retry_after);
+                continue;
+            }
+            if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+                discard_partial(&part_path, &meta_path);
+                meta = None;
+                continue;
+            }
+            break (response, offset);
+        };
+
+        if conditional.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+            self.limiter.succeeded(&host);
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(AssetUnavailable {
+                asset_id: asset_id.to_string(),
+                reason: UnavailableReason::from_status(response.status().as_u16()),
+            }
+            .into());
+        }
+
+        self.limiter.succeeded(&host);
+
+        let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+        let meta = match (resumed, meta) {
+            (true, Some(meta)) => meta,
+            _ => PartialMeta {
+                etag: header_value(&response, ETAG),
+                total: response.content_length(),
+                encoding: header_value(&response, CONTENT_ENCODING),
+                last_modified: header_value(&response, LAST_MODIFIED),
+            },
+        };
+        if resumed {
+            debug!("Resuming {:?} from byte {:?}", asset_id, offset);
+        }
+        write_atomic(&meta_path, &serde_json::to_vec(&meta)?)?;
+
+        let mut file = if resumed {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part_path)
+                .await?
+        } else {
+            tokio::fs::File::create(&part_path).await?
+        };
+        loop {
+            let chunk = timeout(READ_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| format!("Download of {} stalled for {:?}", asset_id, READ_TIMEOUT));
+            match chunk {
+                Ok(Ok(Some(chunk))) => file.write_all(&chunk).await?,
+                Ok(Ok(None)) => break,
+                Ok(Err(err)) => return Err(err.into()),
+                // what made it to disk is resumed on the next attempt
+                Err(err) => {
+                    file.flush().await?;
+                    return Err(err.into());
+                }
+            }
+        }
         file.flush().await?;
+        drop(file);
+
+        let data = tokio::fs::read(&part_path).await?;
+        if let Some(total) = meta.total {
+            let received = data.len() as u64;
+            // a short body is kept around for the next attempt to resume
+            if received < total {
+                return Err(format!(
+                    "Download of {} stopped at {} of {} bytes",
+                    asset_id, received, total
+                )
+                .into());
+            }
+            if received > total {
+                discard_partial(&part_path, &meta_path);
+                return Err(format!(
+                    "Download of {} is {} bytes, expected {}",
+                    asset_id, received, total
+                )
+                .into());
+            }
+        }
+        if let Some(expected) = meta.etag.as_deref().and_then(etag_md5) {
+            if format!("{:x}", Md5::digest(&data)) != expected {
+                discard_partial(&part_path, &meta_path);
+                return Err(format!("Download of {} failed its checksum", asset_id).into());
+            }
+        }
+
+        discard_partial(&part_path, &meta_path);
+        let data = decode_body(data, meta.encoding.as_deref())
+            .map_err(|err| format!("Could not decode {}: {}", asset_id, err))?;
+        let validators = Validators {
+            etag: meta.etag,
+            last_modified: meta.last_modified,
+        };
+        Ok(Some((data, validators)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PartialMeta {
+    etag: Option<String>,
+    total: Option<u64>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+fn read_partial_meta(path: &Path) -> Option<PartialMeta> {
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn discard_partial(part_path: &Path, meta_path: &Path) {
+    let _ = std::fs::remove_file(part_path);
+    let _ = std::fs::remove_file(meta_path);
+}
+
+fn header_value(response: &reqwest::Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string())
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// undoes the Content-Encoding, then also unwraps bodies that are gzip without
+// saying so. the store only ever holds the bytes the mesh reader expects
+fn decode_body(data: Vec<u8>, encoding: Option<&str>) -> Result<Vec<u8>, FixerError> {
+    let mut decoded = Vec::new();
+    let data = match encoding.map(|x| x.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("identity") | Some("") => data,
+        Some("gzip") | Some("x-gzip") => {
+            MultiGzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+            decoded
+        }
+        // "deflate" is meant to be zlib wrapped, some servers send it raw
+        Some("deflate") => {
+            if ZlibDecoder::new(&data[..])
+                .read_to_end(&mut decoded)
+                .is_err()
+            {
+                decoded.clear();
+                DeflateDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+            }
+            decoded
+        }
+        Some(other) => return Err(format!("unsupported content encoding {:?}", other).into()),
+    };
+
+    if data.starts_with(&GZIP_MAGIC) {
+        let mut decoded = Vec::new();
+        MultiGzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+        return Ok(decoded);
+    }
+    Ok(data)
+}
+
+// cdn etags are usually the md5 of the body, weak or opaque ones can't be
+// checked against anything
+fn etag_md5(etag: &str) -> Option<String> {
+    let value = etag.trim_matches('"');
+    if value.len() == 32 && value.chars().all(|x| x.is_ascii_hexdigit()) {
+        Some(value.to_ascii_lowercase())
+    } else {
+        None
     }
+}
 
-    let mut file = tokio::fs::File::open(path).await?;
-    let mut buffer = Vec::<u8>::new();
-    file.read_to_end(&mut buffer).await?;
-    Ok(Cursor::new(buffer))
+pub async fn download_asset(asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError> {
+    AssetDownloader::default().download(asset_id).await
 }
@@ -0,0 +1,38 @@
+use std::{collections::BTreeSet, fs};
+
+use super::{content_url::normalize_content, FixerError};
+
+// one asset per line, anything after a # is a comment
+fn read_lines(input_path: &str) -> Result<Vec<String>, FixerError> {
+    Ok(fs::read_to_string(input_path)?
+        .lines()
+        .map(|x| x.split('#').next().unwrap_or("").trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect())
+}
+
+// assets that must never be rewritten, e.g. purchased meshes whose license
+// requires the original upload to stay referenced
+pub fn read_ignore_list(input_path: &str) -> Result<BTreeSet<String>, FixerError> {
+    Ok(read_lines(input_path)?
+        .iter()
+        .map(|x| normalize_content(x))
+        .collect())
+}
+
+// each line is a group of assets to treat as one mesh, separated by commas
+// or whitespace, e.g. `111, 222, 333`
+pub fn read_merge_list(input_path: &str) -> Result<Vec<Vec<String>>, FixerError> {
+    Ok(read_lines(input_path)?
+        .iter()
+        .map(|line| parse_merge_group(line))
+        .filter(|x| x.len() > 1)
+        .collect())
+}
+
+pub fn parse_merge_group(line: &str) -> Vec<String> {
+    line.split(|x: char| x == ',' || x.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(normalize_content)
+        .collect()
+}
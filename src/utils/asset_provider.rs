@@ -0,0 +1,114 @@
+use std::{collections::HashMap, io::Cursor};
+#[cfg(feature = "native")]
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+
+#[cfg(feature = "native")]
+use super::{
+    asset_downloader::AssetDownloader,
+    content_url::{parse_content_url, ContentUrl},
+};
+use super::{content_url::extract_assetid, digest_bytes, FixerError};
+
+// anything that can turn a content id into the raw asset bytes, the pipeline
+// never talks to the network or the cache directly
+#[async_trait]
+pub trait AssetProvider: Send + Sync {
+    async fn fetch(&self, asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError>;
+
+    // (hash, length) of the raw bytes, used by the exact-byte dedupe passes.
+    // providers that index their content can answer without reading it
+    async fn content_hash(&self, asset_id: String) -> Result<(u128, usize), FixerError> {
+        let data = self.fetch(asset_id).await?.into_inner();
+        Ok((digest_bytes(&data), data.len()))
+    }
+}
+
+#[cfg(feature = "native")]
+#[async_trait]
+impl AssetProvider for AssetDownloader {
+    async fn fetch(&self, asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError> {
+        self.download(asset_id).await
+    }
+
+    async fn content_hash(&self, asset_id: String) -> Result<(u128, usize), FixerError> {
+        let id = self.download_blob(asset_id).await?;
+        Ok((id.hash, id.size))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    assets: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> Self {
+        MemoryProvider::default()
+    }
+
+    pub fn insert(&mut self, asset_id: &str, data: Vec<u8>) {
+        let key = extract_assetid(asset_id.to_string()).unwrap_or(asset_id.to_string());
+        self.assets.insert(key, data);
+    }
+}
+
+#[async_trait]
+impl AssetProvider for MemoryProvider {
+    async fn fetch(&self, asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError> {
+        let key = extract_assetid(asset_id.clone())?;
+        match self.assets.get(&key) {
+            Some(data) => Ok(Cursor::new(data.clone())),
+            None => Err(format!("Asset {} is not in the memory provider", asset_id).into()),
+        }
+    }
+}
+
+// resolves rbxasset:// paths against a Roblox install's content folder and
+// hands everything else to the wrapped provider
+#[cfg(feature = "native")]
+pub struct LocalContentProvider {
+    content_dir: PathBuf,
+    remote: Arc<dyn AssetProvider>,
+}
+
+#[cfg(feature = "native")]
+impl LocalContentProvider {
+    pub fn new<P: Into<PathBuf>>(content_dir: P, remote: Arc<dyn AssetProvider>) -> Self {
+        LocalContentProvider {
+            content_dir: content_dir.into(),
+            remote,
+        }
+    }
+
+    pub fn resolve(&self, asset_id: &str) -> Option<PathBuf> {
+        match parse_content_url(asset_id) {
+            Ok(ContentUrl::Local(path)) => {
+                Some(self.content_dir.join(path.trim_start_matches('/')))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+#[async_trait]
+impl AssetProvider for LocalContentProvider {
+    async fn fetch(&self, asset_id: String) -> Result<Cursor<Vec<u8>>, FixerError> {
+        match self.resolve(&asset_id) {
+            Some(path) => Ok(Cursor::new(tokio::fs::read(path).await?)),
+            None => self.remote.fetch(asset_id).await,
+        }
+    }
+
+    async fn content_hash(&self, asset_id: String) -> Result<(u128, usize), FixerError> {
+        match self.resolve(&asset_id) {
+            Some(path) => {
+                let data = tokio::fs::read(path).await?;
+                Ok((digest_bytes(&data), data.len()))
+            }
+            None => self.remote.content_hash(asset_id).await,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use super::FixerError;
+
+// writes go to a temp file next to the target and only replace it on
+// `commit`, so a crash halfway through never leaves a truncated file behind.
+// dropping without committing throws the temp file away
+pub struct AtomicFile {
+    writer: Option<BufWriter<File>>,
+    temp_path: PathBuf,
+    path: PathBuf,
+}
+
+// the pid keeps two runs writing the same cache file from sharing a temp
+// file, whichever renames last wins with a complete copy
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+impl AtomicFile {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, FixerError> {
+        let path = path.as_ref().to_path_buf();
+        let temp_path = temp_path(&path);
+        Ok(AtomicFile {
+            writer: Some(BufWriter::new(File::create(&temp_path)?)),
+            temp_path,
+            path,
+        })
+    }
+
+    pub fn commit(mut self) -> Result<(), FixerError> {
+        let writer = self.writer.take().expect("atomic-writer");
+        let file = writer.into_inner().map_err(|x| x.into_error())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&self.temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.as_mut().expect("atomic-writer").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.as_mut().expect("atomic-writer").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+pub fn write_atomic<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<(), FixerError> {
+    let mut file = AtomicFile::create(path)?;
+    file.write_all(data)?;
+    file.commit()
+}
+
+// keeps the original around as `<name>.bak` before it gets replaced
+pub fn backup_file<P: AsRef<Path>>(path: P) -> Result<PathBuf, FixerError> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!("{}.bak", name));
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
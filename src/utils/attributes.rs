@@ -0,0 +1,52 @@
+use rbx_dom_weak::{
+    types::{Attributes, Ref, Variant},
+    Instance, WeakDom,
+};
+
+use super::mesh_content::{has_mesh_property, set_mesh_id};
+
+pub const ORIGINAL_MESH_ID: &str = "OriginalMeshId";
+
+fn get_attributes(instance: &mut Instance) -> Option<&mut Attributes> {
+    let attributes = instance
+        .properties
+        .entry("Attributes".to_string())
+        .or_insert_with(|| Variant::Attributes(Attributes::new()));
+
+    match attributes {
+        Variant::Attributes(attributes) => Some(attributes),
+        _ => None,
+    }
+}
+
+pub fn stamp_original(instance: &mut Instance, name: &str, value: String) {
+    // an instance rewritten twice keeps the id it had before the first run
+    if let Some(attributes) = get_attributes(instance) {
+        if attributes.get(name).is_none() {
+            attributes.insert(name.to_string(), Variant::String(value));
+        }
+    }
+}
+
+pub fn restore_originals(dom: &mut WeakDom, refs: &[Ref]) -> usize {
+    let mut restored = 0;
+
+    for referent in refs {
+        let instance = dom.get_by_ref_mut(*referent).expect("stamped-instance");
+        if !instance.properties.contains_key("Attributes") {
+            continue;
+        }
+
+        let original = match get_attributes(instance).and_then(|x| x.remove(ORIGINAL_MESH_ID)) {
+            Some(Variant::String(original)) => original,
+            _ => continue,
+        };
+
+        if has_mesh_property(instance) {
+            set_mesh_id(instance, &original);
+            restored += 1;
+        }
+    }
+
+    restored
+}
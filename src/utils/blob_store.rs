@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{atomic_file::write_atomic, digest_bytes, FixerError};
+
+// identifies a blob by what is in it, identical uploads under different ids
+// or versions end up as the same blob on disk. the digest is md5 so ids
+// stay the same across builds and machines sharing a cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlobId {
+    pub hash: u128,
+    pub size: usize,
+}
+
+impl BlobId {
+    pub fn of(data: &[u8]) -> Self {
+        BlobId {
+            hash: digest_bytes(data),
+            size: data.len(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let (hash, size) = value.trim().split_once('-')?;
+        Some(BlobId {
+            hash: u128::from_str_radix(hash, 16).ok()?,
+            size: size.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for BlobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}-{}", self.hash, self.size)
+    }
+}
+
+// what the server said about the version we cached, sent back on revalidation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+// the asset cache, laid out as `blobs/<blob id>` holding the bytes,
+// `index/<cache key>` holding the blob id each asset resolved to and
+// `validators/<cache key>.json` holding its http validators
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        BlobStore { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn blob_path(&self, id: BlobId) -> PathBuf {
+        self.root.join("blobs").join(id.to_string())
+    }
+
+    fn index_path(&self, key: &str) -> PathBuf {
+        self.root.join("index").join(key)
+    }
+
+    // an index entry whose blob went missing is treated as not cached
+    pub fn lookup(&self, key: &str) -> Option<BlobId> {
+        let id = BlobId::parse(&fs::read_to_string(self.index_path(key)).ok()?)?;
+        match self.blob_path(id).is_file() {
+            true => Some(id),
+            false => None,
+        }
+    }
+
+    pub fn read(&self, id: BlobId) -> Result<Vec<u8>, FixerError> {
+        Ok(fs::read(self.blob_path(id))?)
+    }
+
+    pub fn insert(&self, key: &str, data: &[u8]) -> Result<BlobId, FixerError> {
+        let id = BlobId::of(data);
+        let blob_path = self.blob_path(id);
+        if !blob_path.is_file() {
+            fs::create_dir_all(self.root.join("blobs"))?;
+            // another download of the same content may have beaten us to it
+            if let Err(err) = write_atomic(&blob_path, data) {
+                if !blob_path.is_file() {
+                    return Err(err);
+                }
+            }
+        }
+
+        fs::create_dir_all(self.root.join("index"))?;
+        write_atomic(self.index_path(key), id.to_string().as_bytes())?;
+        Ok(id)
+    }
+
+    pub fn validators(&self, key: &str) -> Option<Validators> {
+        let data = fs::read(self.root.join("validators").join(format!("{}.json", key))).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub fn set_validators(&self, key: &str, validators: &Validators) -> Result<(), FixerError> {
+        let dir = self.root.join("validators");
+        fs::create_dir_all(&dir)?;
+        write_atomic(
+            dir.join(format!("{}.json", key)),
+            &serde_json::to_vec(validators)?,
+        )
+    }
+
+    // caches written before the store existed kept each asset in a file named
+    // after its cache key, those are moved over the first time they are hit
+    pub fn import_legacy(&self, key: &str) -> Result<Option<BlobId>, FixerError> {
+        let legacy_path = self.root.join(key);
+        if !legacy_path.is_file() {
+            return Ok(None);
+        }
+
+        let id = self.insert(key, &fs::read(&legacy_path)?)?;
+        fs::remove_file(&legacy_path)?;
+        Ok(Some(id))
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+use super::FixerError;
+
+// a lock nobody has touched for this long belongs to a run that crashed or
+// was killed, nothing legitimately holds one through a single download
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10 * 60);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// an advisory lock on one cache key, held while it is being downloaded so two
+// runs sharing a cache never append to the same partial file. it is a plain
+// file created exclusively, which works the same on every platform and on
+// network mounts. dropping the guard releases it
+pub struct CacheLock {
+    path: PathBuf,
+}
+
+fn is_stale(path: &Path) -> bool {
+    let modified = match fs::metadata(path).and_then(|x| x.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return false,
+    };
+    match SystemTime::now().duration_since(modified) {
+        Ok(age) => age > STALE_LOCK_AGE,
+        Err(_) => false,
+    }
+}
+
+impl CacheLock {
+    // waits for whoever holds the lock, the caller should check the cache
+    // again afterwards since the other run has usually finished the work
+    pub async fn acquire(dir: &Path, key: &str) -> Result<Self, FixerError> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.lock", key));
+        let mut waited = false;
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(CacheLock { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        warn!("Removing a stale cache lock {:?}", path);
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if !waited {
+                        debug!("Waiting for another run to finish {:?}", key);
+                        waited = true;
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
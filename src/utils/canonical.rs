@@ -0,0 +1,175 @@
+use std::{fmt, str::FromStr};
+use tracing::debug;
+
+use super::{
+    asset_downloader::{extract_assetid, AssetDetails, AssetDownloader},
+    FixerError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanonicalPolicy {
+    FirstSeen,
+    LowestId,
+    MostReferenced,
+    Newest,
+    Owner(u64),
+}
+
+impl Default for CanonicalPolicy {
+    fn default() -> Self {
+        CanonicalPolicy::FirstSeen
+    }
+}
+
+impl FromStr for CanonicalPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "first-seen" => Ok(CanonicalPolicy::FirstSeen),
+            "lowest-id" => Ok(CanonicalPolicy::LowestId),
+            "most-referenced" => Ok(CanonicalPolicy::MostReferenced),
+            "newest" => Ok(CanonicalPolicy::Newest),
+            _ if policy.starts_with("owner:") => policy["owner:".len()..]
+                .parse::<u64>()
+                .map(CanonicalPolicy::Owner)
+                .map_err(|_| format!("Invalid owner id in canonical policy {:?}", policy)),
+            _ => Err(format!("Unknown canonical policy {:?}", policy)),
+        }
+    }
+}
+
+fn numeric_id(asset_id: &str) -> u64 {
+    extract_assetid(asset_id.to_string())
+        .ok()
+        .and_then(|x| x.parse::<u64>().ok())
+        .unwrap_or(u64::MAX)
+}
+
+// candidates are (asset id, reference count) pairs in the order they were
+// first seen, ties always resolve to the earliest candidate
+pub async fn select_canonical(
+    downloader: &AssetDownloader,
+    policy: &CanonicalPolicy,
+    candidates: &[(String, usize)],
+) -> Result<usize, FixerError> {
+    let mut selected = 0;
+
+    match policy {
+        CanonicalPolicy::FirstSeen => {}
+        CanonicalPolicy::LowestId => {
+            for (idx, (asset_id, _)) in candidates.iter().enumerate() {
+                if numeric_id(asset_id) < numeric_id(&candidates[selected].0) {
+                    selected = idx;
+                }
+            }
+        }
+        CanonicalPolicy::MostReferenced => {
+            for (idx, (_, references)) in candidates.iter().enumerate() {
+                if *references > candidates[selected].1 {
+                    selected = idx;
+                }
+            }
+        }
+        CanonicalPolicy::Newest => {
+            let mut newest = String::new();
+            for (idx, (asset_id, _)) in candidates.iter().enumerate() {
+                let details = downloader.asset_details(asset_id.clone()).await?;
+                // the timestamps are ISO 8601 so they compare lexicographically
+                if details.created > newest {
+                    newest = details.created;
+                    selected = idx;
+                }
+            }
+        }
+        CanonicalPolicy::Owner(owner_id) => {
+            for (idx, (asset_id, _)) in candidates.iter().enumerate() {
+                let details = downloader.asset_details(asset_id.clone()).await?;
+                if details.creator.creator_target_id == *owner_id {
+                    selected = idx;
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+// the account a game's meshes should end up on. a canonical uploaded by
+// anyone else can be taken down or made private without the game noticing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreferredOwner {
+    User(u64),
+    Group(u64),
+}
+
+impl PreferredOwner {
+    fn owns(&self, details: &AssetDetails) -> bool {
+        let (creator_type, id) = match self {
+            PreferredOwner::User(id) => ("User", id),
+            PreferredOwner::Group(id) => ("Group", id),
+        };
+        details.creator.creator_type == creator_type && details.creator.creator_target_id == *id
+    }
+}
+
+impl FromStr for PreferredOwner {
+    type Err = String;
+
+    fn from_str(owner: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = owner
+            .split_once(':')
+            .ok_or_else(|| format!("Expected user:<id> or group:<id>, got {:?}", owner))?;
+        let id = id
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid id in owner {:?}", owner))?;
+        match kind {
+            "user" => Ok(PreferredOwner::User(id)),
+            "group" => Ok(PreferredOwner::Group(id)),
+            _ => Err(format!("Expected user:<id> or group:<id>, got {:?}", owner)),
+        }
+    }
+}
+
+impl fmt::Display for PreferredOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreferredOwner::User(id) => write!(f, "user {}", id),
+            PreferredOwner::Group(id) => write!(f, "group {}", id),
+        }
+    }
+}
+
+// narrows the candidates to the owner's assets before the policy picks one.
+// assets the economy api can't describe are likely moderated or deleted and
+// are only considered when nothing else is left. the flag is set when the
+// pick had to be someone else's asset
+pub async fn select_owned_canonical(
+    downloader: &AssetDownloader,
+    policy: &CanonicalPolicy,
+    owner: &PreferredOwner,
+    candidates: &[(String, usize)],
+) -> Result<(usize, bool), FixerError> {
+    let mut owned = vec![];
+    let mut available = vec![];
+    for (idx, (asset_id, _)) in candidates.iter().enumerate() {
+        match downloader.asset_details(asset_id.clone()).await {
+            Ok(details) if owner.owns(&details) => owned.push(idx),
+            Ok(_) => available.push(idx),
+            Err(err) => debug!("No details for {:?}: {}", asset_id, err),
+        }
+    }
+
+    let (pool, third_party) = match (owned.is_empty(), available.is_empty()) {
+        (false, _) => (owned, false),
+        (true, false) => (available, true),
+        (true, true) => ((0..candidates.len()).collect(), true),
+    };
+    let subset = pool
+        .iter()
+        .map(|x| candidates[*x].clone())
+        .collect::<Vec<_>>();
+    let selected = select_canonical(downloader, policy, &subset).await?;
+    Ok((pool[selected], third_party))
+}
@@ -1,6 +1,10 @@
+use glam::{Mat3, Quat, Vec2, Vec3};
 use rbx_types::{CFrame, Matrix3, Vector2, Vector3};
 
-use super::TupleComponent;
+use super::{
+    math::{affine, cframe, mat3, matrix3, vec3, vector3},
+    TupleComponent,
+};
 
 pub trait MatrixExt {
     fn default() -> Self;
@@ -11,9 +15,15 @@ pub trait CFrameExt {
     fn components(&self) -> TupleComponent;
     fn from_components(components: &[f32]) -> Self;
     fn mult(&self, b: CFrame) -> Self;
+    fn inverse(&self) -> Self;
     fn from_xyz(x: f32, y: f32, z: f32) -> Self;
     fn from_axis_angle(axis: Vector3, theta: f32) -> Self;
+    fn to_axis_angle(&self) -> (Vector3, f32);
     fn angles(x: f32, y: f32, z: f32) -> Self;
+    fn lerp(&self, goal: CFrame, alpha: f32) -> Self;
+    fn to_euler_angles_xyz(&self) -> (f32, f32, f32);
+    fn to_euler_angles_yxz(&self) -> (f32, f32, f32);
+    fn orthonormalize(&self) -> Self;
 }
 
 pub trait Vector3Ext {
@@ -26,26 +36,38 @@ pub trait Vector3Ext {
     fn mult_vec(&self, b: Vector3) -> Vector3;
     fn cross(&self, b: Vector3) -> Vector3;
     fn dot(&self, b: Vector3) -> f32;
+    fn length(&self) -> f32;
+    fn length_squared(&self) -> f32;
     fn normalize(&self) -> Vector3;
+    fn lerp(&self, b: Vector3, t: f32) -> Vector3;
+    fn angle_between(&self, b: Vector3) -> f32;
     fn axis_angle(&self, v: Vector3, t: f32) -> Vector3;
 }
 
 pub trait Vector2Ext {
     fn dot(&self, b: Self) -> f32;
+    fn length(&self) -> f32;
+    fn length_squared(&self) -> f32;
     fn normalize(&self) -> Self;
 }
 
 impl Vector2Ext for Vector2 {
     fn dot(&self, b: Self) -> f32 {
-        self.x * b.x + self.y * b.y
+        Vec2::new(self.x, self.y).dot(Vec2::new(b.x, b.y))
     }
 
+    fn length(&self) -> f32 {
+        Vec2::new(self.x, self.y).length()
+    }
+
+    fn length_squared(&self) -> f32 {
+        Vec2::new(self.x, self.y).length_squared()
+    }
+
+    // a zero vector has no direction and is returned as is
     fn normalize(&self) -> Self {
-        let m = self.dot(self.clone());
-        Vector2 {
-            x: self.x / m,
-            y: self.y / m,
-        }
+        let v = Vec2::new(self.x, self.y).normalize_or_zero();
+        Vector2 { x: v.x, y: v.y }
     }
 }
 
@@ -75,69 +97,62 @@ impl Vector3Ext for Vector3 {
     }
 
     fn add(&self, b: Vector3) -> Vector3 {
-        Vector3 {
-            x: self.x + b.x,
-            y: self.y + b.y,
-            z: self.z + b.z,
-        }
+        vector3(vec3(*self) + vec3(b))
     }
 
     fn sub(&self, b: Vector3) -> Vector3 {
-        Vector3 {
-            x: self.x - b.x,
-            y: self.y - b.y,
-            z: self.z - b.z,
-        }
+        vector3(vec3(*self) - vec3(b))
     }
 
     fn mult(&self, i: f32) -> Vector3 {
-        Vector3 {
-            x: self.x * i,
-            y: self.y * i,
-            z: self.z * i,
-        }
+        vector3(vec3(*self) * i)
     }
 
     fn mult_vec(&self, b: Vector3) -> Vector3 {
-        Vector3 {
-            x: self.x * b.x,
-            y: self.y * b.y,
-            z: self.z * b.z,
-        }
+        vector3(vec3(*self) * vec3(b))
     }
 
     fn cross(&self, b: Vector3) -> Vector3 {
-        Vector3 {
-            x: self.y * b.z - b.y * self.z,
-            y: self.z * b.x - b.z * self.x,
-            z: self.x * b.y - b.x * self.y,
-        }
+        vector3(vec3(*self).cross(vec3(b)))
     }
 
     fn dot(&self, b: Vector3) -> f32 {
-        self.x * b.x + self.y * b.y + self.z * b.z
+        vec3(*self).dot(vec3(b))
     }
 
-    fn normalize(&self) -> Vector3 {
-        let m = self.dot(self.clone());
-        Vector3 {
-            x: self.x / m,
-            y: self.y / m,
-            z: self.z / m,
-        }
+    fn length(&self) -> f32 {
+        vec3(*self).length()
+    }
+
+    fn length_squared(&self) -> f32 {
+        vec3(*self).length_squared()
     }
 
-    fn axis_angle(&self, axis: Vector3, t: f32) -> Vector3 {
-        let unit = self.normalize();
+    // a zero vector has no direction and is returned as is
+    fn normalize(&self) -> Vector3 {
+        vector3(vec3(*self).normalize_or_zero())
+    }
 
-        let cos = t.cos();
-        let sin = t.sin();
+    fn lerp(&self, b: Vector3, t: f32) -> Vector3 {
+        vector3(vec3(*self).lerp(vec3(b), t))
+    }
 
-        let a1 = axis.mult(cos);
-        let a2 = unit.mult(axis.dot(unit) * (1.0 - cos));
-        let a3 = unit.cross(axis).mult(sin);
+    // in radians, zero when either vector has no direction
+    fn angle_between(&self, b: Vector3) -> f32 {
+        let m = self.length() * b.length();
+        if m == 0.0 {
+            return 0.0;
+        }
+        (self.dot(b) / m).clamp(-1.0, 1.0).acos()
+    }
 
-        a1.add(a2.add(a3))
+    // turns v about this axis, an axis with no direction leaves it alone
+    fn axis_angle(&self, v: Vector3, t: f32) -> Vector3 {
+        let axis = vec3(*self).normalize_or_zero();
+        if axis == Vec3::ZERO {
+            return v;
+        }
+        vector3(Quat::from_axis_angle(axis, t) * vec3(v))
     }
 }
 
@@ -179,54 +194,93 @@ impl CFrameExt for CFrame {
         }
     }
 
+    // an axis with no direction is no rotation at all
     fn from_axis_angle(axis: Vector3, theta: f32) -> Self {
-        let r = axis.axis_angle(Vector3::RIGHT(), theta);
-        let u = axis.axis_angle(Vector3::UP(), theta);
-        let b = axis.axis_angle(Vector3::BACK(), theta);
+        let axis = vec3(axis).normalize_or_zero();
+        let rotation = match axis == Vec3::ZERO {
+            true => Quat::IDENTITY,
+            false => Quat::from_axis_angle(axis, theta),
+        };
         CFrame {
             position: Vector3::new(0.0, 0.0, 0.0),
-            orientation: Matrix3 {
-                x: Vector3::new(r.x, u.x, b.x),
-                y: Vector3::new(r.y, u.y, b.y),
-                z: Vector3::new(r.z, u.z, b.z),
-            },
+            orientation: matrix3(Mat3::from_quat(rotation)),
         }
     }
 
+    fn to_axis_angle(&self) -> (Vector3, f32) {
+        let (axis, angle) = Quat::from_mat3(&mat3(&self.orientation)).to_axis_angle();
+        (vector3(axis), angle)
+    }
+
     fn angles(x: f32, y: f32, z: f32) -> Self {
-        let cfx = CFrame::from_axis_angle(Vector3::RIGHT(), x);
-        let cfy = CFrame::from_axis_angle(Vector3::UP(), y);
-        let cfz = CFrame::from_axis_angle(Vector3::BACK(), z);
-        cfx.mult(cfy).mult(cfz)
+        let rotation =
+            Mat3::from_rotation_x(x) * Mat3::from_rotation_y(y) * Mat3::from_rotation_z(z);
+        CFrame {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            orientation: matrix3(rotation),
+        }
+    }
+
+    // position is interpolated linearly, the orientation along the shortest
+    // arc between the two
+    fn lerp(&self, goal: CFrame, alpha: f32) -> Self {
+        let from = Quat::from_mat3(&mat3(&self.orientation));
+        let to = Quat::from_mat3(&mat3(&goal.orientation));
+        CFrame {
+            position: self.position.lerp(goal.position, alpha),
+            orientation: matrix3(Mat3::from_quat(from.slerp(to, alpha))),
+        }
     }
 
     fn mult(&self, b: Self) -> Self {
-        let m1 = self.components();
-        let m2 = b.components();
-
-        let (ax, ay, az, a11, a12, a13, a21, a22, a23, a31, a32, a33, _, _, _, _) = m1;
-        let (bx, by, bz, b11, b12, b13, b21, b22, b23, b31, b32, b33, _, _, _, _) = m2;
-
-        let m11 = a11 * b11 + a12 * b21 + a13 * b31;
-        let m12 = a11 * b12 + a12 * b22 + a13 * b32;
-        let m13 = a11 * b13 + a12 * b23 + a13 * b33;
-        let x = a11 * bx + a12 * by + a13 * bz + ax;
-        let m21 = a21 * b11 + a22 * b21 + a23 * b31;
-        let m22 = a21 * b12 + a22 * b22 + a23 * b32;
-        let m23 = a21 * b13 + a22 * b23 + a23 * b33;
-        let y = a21 * bx + a22 * by + a23 * bz + ay;
-        let m31 = a31 * b11 + a32 * b21 + a33 * b31;
-        let m32 = a31 * b12 + a32 * b22 + a33 * b32;
-        let m33 = a31 * b13 + a32 * b23 + a33 * b33;
-        let z = a31 * bx + a32 * by + a33 * bz + az;
+        cframe(affine(self) * affine(&b))
+    }
+
+    fn inverse(&self) -> Self {
+        cframe(affine(self).inverse())
+    }
+
+    // the angles CFrame.Angles would need to build this orientation, near
+    // +-90 degrees about y the z angle is folded into x
+    fn to_euler_angles_xyz(&self) -> (f32, f32, f32) {
+        let m = self.orientation;
+        let y = m.x.z.clamp(-1.0, 1.0).asin();
+        if m.x.z.abs() < 0.999_999 {
+            ((-m.y.z).atan2(m.z.z), y, (-m.x.y).atan2(m.x.x))
+        } else {
+            (m.z.y.atan2(m.y.y), y, 0.0)
+        }
+    }
+
+    // the same for CFrame.fromEulerAnglesYXZ, which turns about y, then x,
+    // then z. returned in x, y, z order like roblox does
+    fn to_euler_angles_yxz(&self) -> (f32, f32, f32) {
+        let m = self.orientation;
+        let x = (-m.y.z).clamp(-1.0, 1.0).asin();
+        if m.y.z.abs() < 0.999_999 {
+            (x, m.x.z.atan2(m.z.z), m.y.x.atan2(m.y.y))
+        } else {
+            (x, (-m.z.x).atan2(m.x.x), 0.0)
+        }
+    }
+
+    // float drift from chained multiplications leaves the axes slightly
+    // skewed, gram-schmidt on the right and up vectors squares them up again.
+    // an orientation with no usable axes becomes the identity
+    fn orthonormalize(&self) -> Self {
+        let m = mat3(&self.orientation);
+        let right = m.x_axis.normalize_or_zero();
+        let up = (m.y_axis - right * right.dot(m.y_axis)).normalize_or_zero();
+        if right == Vec3::ZERO || up == Vec3::ZERO {
+            return CFrame {
+                position: self.position,
+                orientation: Matrix3::default(),
+            };
+        }
 
         CFrame {
-            position: Vector3::new(x, y, z),
-            orientation: Matrix3 {
-                x: Vector3::new(m11, m12, m13),
-                y: Vector3::new(m21, m22, m23),
-                z: Vector3::new(m31, m32, m33),
-            },
+            position: self.position,
+            orientation: matrix3(Mat3::from_cols(right, up, right.cross(up))),
         }
     }
 }
@@ -240,3 +294,81 @@ impl MatrixExt for Matrix3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < EPSILON
+    }
+
+    fn close_vec(a: Vector3, b: Vector3) -> bool {
+        close(a.x, b.x) && close(a.y, b.y) && close(a.z, b.z)
+    }
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        let v = Vector3::new(3.0, 0.0, 4.0).normalize();
+        assert!(close_vec(v, Vector3::new(0.6, 0.0, 0.8)));
+        assert!(close(v.length(), 1.0));
+    }
+
+    #[test]
+    fn normalize_leaves_zero_vector_alone() {
+        let v = Vector3::new(0.0, 0.0, 0.0).normalize();
+        assert!(close_vec(v, Vector3::new(0.0, 0.0, 0.0)));
+        assert!(!v.x.is_nan() && !v.y.is_nan() && !v.z.is_nan());
+    }
+
+    #[test]
+    fn length_and_length_squared() {
+        let v = Vector3::new(1.0, 2.0, 2.0);
+        assert!(close(v.length(), 3.0));
+        assert!(close(v.length_squared(), 9.0));
+    }
+
+    #[test]
+    fn lerp_hits_both_ends() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(-4.0, 5.0, 10.0);
+        assert!(close_vec(a.lerp(b, 0.0), a));
+        assert!(close_vec(a.lerp(b, 1.0), b));
+    }
+
+    #[test]
+    fn angle_between_parallel_and_orthogonal() {
+        let right = Vector3::RIGHT();
+        assert!(close(right.angle_between(right.mult(5.0)), 0.0));
+        assert!(close(
+            right.angle_between(Vector3::UP()),
+            std::f32::consts::FRAC_PI_2
+        ));
+        assert!(close(
+            right.angle_between(right.mult(-1.0)),
+            std::f32::consts::PI
+        ));
+    }
+
+    #[test]
+    fn euler_xyz_round_trips() {
+        let (x, y, z) = (0.3, -0.7, 1.2);
+        let (rx, ry, rz) = CFrame::angles(x, y, z).to_euler_angles_xyz();
+        assert!(close(rx, x) && close(ry, y) && close(rz, z));
+    }
+
+    #[test]
+    fn euler_yxz_round_trips() {
+        let (x, y, z) = (0.4, 1.1, -0.5);
+        let rotation =
+            Mat3::from_rotation_y(y) * Mat3::from_rotation_x(x) * Mat3::from_rotation_z(z);
+        let frame = CFrame {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            orientation: matrix3(rotation),
+        };
+        let (rx, ry, rz) = frame.to_euler_angles_yxz();
+        assert!(close(rx, x) && close(ry, y) && close(rz, z));
+    }
+}
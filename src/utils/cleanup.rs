@@ -0,0 +1,120 @@
+use glam::Vec3;
+use std::collections::HashMap;
+
+use super::{
+    math::{vec3, vector3},
+    mesh_reader::{RobloxMesh, RobloxMeshVertex},
+};
+
+pub const DEFAULT_WELD_EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanupStats {
+    pub welded_vertices: usize,
+    pub degenerate_faces: usize,
+}
+
+fn quantize(value: f32, epsilon: f32) -> i64 {
+    (value / epsilon).round() as i64
+}
+
+// vertices only merge when their uvs match too, otherwise welding would tear
+// textures apart along uv seams
+fn weld_key(vertex: &RobloxMeshVertex, epsilon: f32) -> [i64; 5] {
+    [
+        quantize(vertex.position.x, epsilon),
+        quantize(vertex.position.y, epsilon),
+        quantize(vertex.position.z, epsilon),
+        quantize(vertex.uv.x, epsilon),
+        quantize(vertex.uv.y, epsilon),
+    ]
+}
+
+fn face_area(vertices: &[RobloxMeshVertex], face: &[i32; 3]) -> f32 {
+    let a = vec3(vertices[face[0] as usize].position);
+    let b = vec3(vertices[face[1] as usize].position);
+    let c = vec3(vertices[face[2] as usize].position);
+    (b - a).cross(c - a).length() * 0.5
+}
+
+// merges coincident vertices, drops zero-area triangles and recomputes
+// normals. every LOD range is cleaned on its own so the offsets stay valid
+pub fn cleanup_mesh(mesh: &mut RobloxMesh, epsilon: f32) -> CleanupStats {
+    let mut stats = CleanupStats::default();
+    // skin subsets address contiguous vertex ranges that welding would break
+    if !mesh.skin_subsets.is_empty() {
+        return stats;
+    }
+
+    let mut keys = HashMap::<[i64; 5], i32>::new();
+    let mut remap = Vec::<i32>::with_capacity(mesh.vertices.len());
+    let mut vertices = Vec::<RobloxMeshVertex>::with_capacity(mesh.vertices.len());
+
+    for vertex in mesh.vertices.iter() {
+        let index = *keys.entry(weld_key(vertex, epsilon)).or_insert_with(|| {
+            vertices.push(vertex.clone());
+            vertices.len() as i32 - 1
+        });
+        remap.push(index);
+    }
+    stats.welded_vertices = mesh.vertices.len() - vertices.len();
+
+    let mut ranges = mesh
+        .lods
+        .windows(2)
+        .map(|x| (x[0].max(0) as usize, x[1].max(0) as usize))
+        .collect::<Vec<_>>();
+    if ranges.is_empty() {
+        ranges.push((0, mesh.faces.len()));
+    }
+
+    let mut faces = Vec::<[i32; 3]>::with_capacity(mesh.faces.len());
+    let mut lods = vec![0i32];
+    for (start, end) in ranges {
+        let end = end.min(mesh.faces.len());
+        for face in mesh.faces[start.min(end)..end].iter() {
+            let face = [
+                remap[face[0] as usize],
+                remap[face[1] as usize],
+                remap[face[2] as usize],
+            ];
+
+            if face[0] == face[1] || face[1] == face[2] || face[0] == face[2] {
+                stats.degenerate_faces += 1;
+                continue;
+            }
+            if face_area(&vertices, &face) <= epsilon * epsilon {
+                stats.degenerate_faces += 1;
+                continue;
+            }
+            faces.push(face);
+        }
+        lods.push(faces.len() as i32);
+    }
+
+    // area weighted vertex normals, the cross product length is the weight
+    let mut normals = vec![Vec3::ZERO; vertices.len()];
+    for face in faces.iter() {
+        let a = vec3(vertices[face[0] as usize].position);
+        let b = vec3(vertices[face[1] as usize].position);
+        let c = vec3(vertices[face[2] as usize].position);
+        let normal = (b - a).cross(c - a);
+        for corner in face.iter() {
+            normals[*corner as usize] += normal;
+        }
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals.into_iter()) {
+        if normal.length_squared() > 0.0 {
+            vertex.normal = vector3(normal.normalize());
+        }
+    }
+
+    mesh.vertices = vertices;
+    mesh.faces = faces;
+    mesh.lods = lods;
+    if mesh.vertices.len() >= 4 && !mesh.faces.is_empty() {
+        mesh.recalculate();
+    }
+
+    stats
+}
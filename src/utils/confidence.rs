@@ -0,0 +1,44 @@
+use serde::Serialize;
+use std::fmt;
+
+// how a duplicate was matched to its canonical, from the most to the least
+// certain. a cluster is only as confident as its weakest match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchKind {
+    // the same file uploaded under another id
+    ExactBytes,
+    // a different file with the same geometry
+    Geometric,
+    // the same shape at a different size, rewritten through InitialSize
+    Scaled,
+    // the same shape turned on its side, rewritten with a CFrame correction
+    Rotated,
+    // only close, either from --fuzzy or a forced merge
+    Fuzzy,
+}
+
+impl MatchKind {
+    pub fn confidence(&self) -> f32 {
+        match self {
+            MatchKind::ExactBytes => 1.0,
+            MatchKind::Geometric => 0.9,
+            MatchKind::Scaled => 0.75,
+            MatchKind::Rotated => 0.6,
+            MatchKind::Fuzzy => 0.4,
+        }
+    }
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MatchKind::ExactBytes => "exact-bytes",
+            MatchKind::Geometric => "geometric",
+            MatchKind::Scaled => "scaled",
+            MatchKind::Rotated => "rotated",
+            MatchKind::Fuzzy => "fuzzy",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,126 @@
+use super::FixerError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentUrl {
+    Empty,
+    AssetId { id: u64, version: Option<u32> },
+    Local(String),
+    Thumbnail(String),
+}
+
+fn query_value<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        let name = parts.next()?;
+        if name.eq_ignore_ascii_case(key) {
+            parts.next()
+        } else {
+            None
+        }
+    })
+}
+
+fn strip_prefix_ignore_case<'a>(url: &'a str, prefix: &str) -> Option<&'a str> {
+    match url.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&url[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn parse_id(id: &str, url: &str) -> Result<u64, FixerError> {
+    id.trim()
+        .trim_end_matches('/')
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid asset id in content url {:?}", url).into())
+}
+
+fn parse_version(query: &str, url: &str) -> Result<Option<u32>, FixerError> {
+    match query_value(query, "version") {
+        Some(version) => {
+            Ok(Some(version.parse::<u32>().map_err(|_| {
+                format!("Invalid asset version in content url {:?}", url)
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn parse_content_url(url: &str) -> Result<ContentUrl, FixerError> {
+    let trimmed = url.trim();
+    if trimmed.is_empty() {
+        return Ok(ContentUrl::Empty);
+    }
+
+    if let Some(path) = strip_prefix_ignore_case(trimmed, "rbxasset://") {
+        return Ok(ContentUrl::Local(path.to_string()));
+    }
+
+    if let Some(params) = strip_prefix_ignore_case(trimmed, "rbxthumb://") {
+        return Ok(ContentUrl::Thumbnail(params.to_string()));
+    }
+
+    if let Some(rest) = strip_prefix_ignore_case(trimmed, "rbxassetid://") {
+        let mut parts = rest.splitn(2, '?');
+        let id = parse_id(parts.next().unwrap_or(""), url)?;
+        let version = parse_version(parts.next().unwrap_or(""), url)?;
+        return Ok(ContentUrl::AssetId { id, version });
+    }
+
+    if trimmed.chars().all(|x| x.is_ascii_digit()) {
+        return Ok(ContentUrl::AssetId {
+            id: parse_id(trimmed, url)?,
+            version: None,
+        });
+    }
+
+    // http(s)://www.roblox.com/asset/?id=123, roblox.com/asset?id=123&version=2
+    // and the assetdelivery variants all carry the id in the query string
+    let without_scheme = strip_prefix_ignore_case(trimmed, "https://")
+        .or_else(|| strip_prefix_ignore_case(trimmed, "http://"))
+        .unwrap_or(trimmed);
+    let mut parts = without_scheme.splitn(2, '?');
+    let host_and_path = parts.next().unwrap_or("").to_lowercase();
+    let query = parts.next().unwrap_or("");
+
+    if !host_and_path.contains("roblox.com") {
+        return Err(format!("Unrecognized content url {:?}", url).into());
+    }
+
+    match query_value(query, "id") {
+        Some(id) => Ok(ContentUrl::AssetId {
+            id: parse_id(id, url)?,
+            version: parse_version(query, url)?,
+        }),
+        None => Err(format!("Content url {:?} has no asset id", url).into()),
+    }
+}
+
+// asset ids are rewritten as rbxassetid://, anything else is kept as it was.
+// rbxassetid:// can't carry a version, so pinned ids keep the asset url form
+pub fn normalize_content(url: &str) -> String {
+    match parse_content_url(url) {
+        Ok(ContentUrl::AssetId {
+            id,
+            version: Some(version),
+        }) => format!(
+            "https://www.roblox.com/asset/?id={}&version={}",
+            id, version
+        ),
+        Ok(ContentUrl::AssetId { id, version: None }) => format!("rbxassetid://{}", id),
+        _ => url.trim().to_string(),
+    }
+}
+
+pub fn is_local_asset(asset_id: &str) -> bool {
+    match parse_content_url(asset_id) {
+        Ok(ContentUrl::Local(_)) => true,
+        _ => false,
+    }
+}
+
+pub fn extract_assetid(asset_id: String) -> Result<String, FixerError> {
+    match parse_content_url(&asset_id)? {
+        ContentUrl::AssetId { id, .. } => Ok(id.to_string()),
+        _ => Err(format!("{:?} does not reference an asset id", asset_id).into()),
+    }
+}
@@ -0,0 +1,256 @@
+use glam::{DVec3, Vec3};
+use rbx_types::Vector3;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{
+    math::{dvec3, dvector3, vec3, vector3},
+    mesh_reader::{RobloxBoneWeights, RobloxMesh, RobloxMeshVertex},
+    FixerError,
+};
+
+// points closer to a face than this, relative to the size of the mesh, are
+// treated as lying on it. positions are only f32 to begin with
+const PLANE_EPSILON: f64 = 1e-6;
+
+type Point = DVec3;
+
+#[derive(Debug, Clone)]
+pub struct ConvexHull {
+    pub points: Vec<Vector3>,
+    // wound counter clockwise seen from outside, the same as mesh faces
+    pub faces: Vec<[i32; 3]>,
+}
+
+struct HullFace {
+    vertices: [usize; 3],
+    normal: Point,
+    offset: f64,
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl HullFace {
+    // the winding is flipped when needed so the normal points away from a
+    // point known to be inside the hull
+    fn new(points: &[Point], mut vertices: [usize; 3], interior: Point) -> Self {
+        let [a, b, c] = vertices;
+        let mut normal = (points[b] - points[a])
+            .cross(points[c] - points[a])
+            .normalize_or_zero();
+        let mut offset = normal.dot(points[a]);
+        if normal.dot(interior) - offset > 0.0 {
+            vertices.swap(1, 2);
+            normal = -normal;
+            offset = -offset;
+        }
+
+        HullFace {
+            vertices,
+            normal,
+            offset,
+            outside: vec![],
+            alive: true,
+        }
+    }
+
+    fn distance(&self, point: Point) -> f64 {
+        self.normal.dot(point) - self.offset
+    }
+}
+
+fn assign_outside(faces: &mut [HullFace], points: &[Point], point: usize, epsilon: f64) {
+    if let Some(face) = faces
+        .iter_mut()
+        .find(|x| x.distance(points[point]) > epsilon)
+    {
+        face.outside.push(point);
+    }
+}
+
+// the four points spanning the largest starting tetrahedron, flat or
+// degenerate point sets have none
+fn initial_simplex(points: &[Point], epsilon: f64) -> Option<[usize; 4]> {
+    let mut best = (0, 0, 0.0f64);
+    for axis in 0..3 {
+        let compare = |a: &&Point, b: &&Point| a[axis].total_cmp(&b[axis]);
+        let min = points
+            .iter()
+            .enumerate()
+            .min_by(|a, b| compare(&a.1, &b.1))?;
+        let max = points
+            .iter()
+            .enumerate()
+            .max_by(|a, b| compare(&a.1, &b.1))?;
+        let spread = max.1[axis] - min.1[axis];
+        if spread > best.2 {
+            best = (min.0, max.0, spread);
+        }
+    }
+    let (a, b, spread) = best;
+    if spread <= epsilon {
+        return None;
+    }
+
+    let direction = points[b] - points[a];
+    let (c, line_distance) = points
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            (
+                i,
+                (*x - points[a]).cross(direction).length() / direction.length(),
+            )
+        })
+        .max_by(|x, y| x.1.total_cmp(&y.1))?;
+    if line_distance <= epsilon {
+        return None;
+    }
+
+    let normal = direction.cross(points[c] - points[a]).normalize();
+    let (d, plane_distance) = points
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i, (*x - points[a]).dot(normal).abs()))
+        .max_by(|x, y| x.1.total_cmp(&y.1))?;
+    if plane_distance <= epsilon {
+        return None;
+    }
+
+    Some([a, b, c, d])
+}
+
+// quickhull: start from a tetrahedron, then keep pushing the hull out to the
+// point furthest outside one of its faces until no point is left outside
+pub fn convex_hull(positions: &[Vector3]) -> Result<ConvexHull, FixerError> {
+    // vertices split along uv seams share a position, the hull needs it once.
+    // positions come straight from the asset, nan or infinite ones are dropped
+    let mut seen = HashSet::new();
+    let points = positions
+        .iter()
+        .filter(|x| x.x.is_finite() && x.y.is_finite() && x.z.is_finite())
+        .filter(|x| seen.insert([x.x.to_bits(), x.y.to_bits(), x.z.to_bits()]))
+        .map(|x| dvec3(*x))
+        .collect::<Vec<Point>>();
+
+    let extent = points
+        .iter()
+        .fold(1.0f64, |max, x| max.max(x.abs().max_element()));
+    let epsilon = extent * PLANE_EPSILON;
+    let simplex = match initial_simplex(&points, epsilon) {
+        Some(simplex) => simplex,
+        None => return Err("A convex hull needs points that aren't all on one plane".into()),
+    };
+
+    let interior = simplex.iter().fold(DVec3::ZERO, |sum, x| sum + points[*x]) / 4.0;
+
+    let [a, b, c, d] = simplex;
+    let mut faces = vec![
+        HullFace::new(&points, [a, b, c], interior),
+        HullFace::new(&points, [a, b, d], interior),
+        HullFace::new(&points, [a, c, d], interior),
+        HullFace::new(&points, [b, c, d], interior),
+    ];
+    for point in 0..points.len() {
+        if !simplex.contains(&point) {
+            assign_outside(&mut faces, &points, point, epsilon);
+        }
+    }
+
+    while let Some(current) = faces.iter().position(|x| x.alive && !x.outside.is_empty()) {
+        let face = &faces[current];
+        let eye = *face
+            .outside
+            .iter()
+            .max_by(|x, y| {
+                face.distance(points[**x])
+                    .total_cmp(&face.distance(points[**y]))
+            })
+            .expect("hull-outside");
+
+        // every face the eye can see gets replaced, the edges only one of
+        // them owns form the horizon the new faces are fanned out from
+        let visible = (0..faces.len())
+            .filter(|x| faces[*x].alive && faces[*x].distance(points[eye]) > epsilon)
+            .collect::<Vec<_>>();
+        let mut edges = BTreeSet::new();
+        for index in visible.iter() {
+            let v = faces[*index].vertices;
+            edges.insert((v[0], v[1]));
+            edges.insert((v[1], v[2]));
+            edges.insert((v[2], v[0]));
+        }
+        let horizon = edges
+            .iter()
+            .filter(|(a, b)| !edges.contains(&(*b, *a)))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let mut orphans = vec![];
+        for index in visible {
+            faces[index].alive = false;
+            orphans.append(&mut faces[index].outside);
+        }
+
+        let first_new = faces.len();
+        for (a, b) in horizon {
+            faces.push(HullFace::new(&points, [a, b, eye], interior));
+        }
+        for point in orphans {
+            if point != eye {
+                assign_outside(&mut faces[first_new..], &points, point, epsilon);
+            }
+        }
+    }
+
+    // only the points the hull ended up touching are kept
+    let mut remap = HashMap::<usize, i32>::new();
+    let mut hull = ConvexHull {
+        points: vec![],
+        faces: vec![],
+    };
+    for face in faces.iter().filter(|x| x.alive) {
+        let mut indices = [0i32; 3];
+        for (index, vertex) in indices.iter_mut().zip(face.vertices.iter()) {
+            *index = *remap.entry(*vertex).or_insert_with(|| {
+                hull.points.push(dvector3(points[*vertex]));
+                hull.points.len() as i32 - 1
+            });
+        }
+        hull.faces.push(indices);
+    }
+
+    Ok(hull)
+}
+
+impl ConvexHull {
+    // a closed, untextured mesh of the hull, normals point away from its
+    // center which is all collision geometry needs
+    pub fn to_mesh(&self) -> Result<RobloxMesh, FixerError> {
+        let center = self.points.iter().fold(Vec3::ZERO, |sum, x| sum + vec3(*x))
+            / self.points.len().max(1) as f32;
+
+        let vertices = self
+            .points
+            .iter()
+            .map(|x| RobloxMeshVertex {
+                position: *x,
+                normal: vector3((vec3(*x) - center).normalize_or_zero()),
+                uv: Vector3::new(0.0, 0.0, 0.0),
+                color: -1,
+                weights: RobloxBoneWeights {
+                    bones: [0; 4],
+                    weights: [0; 4],
+                },
+            })
+            .collect();
+        RobloxMesh::from_geometry(vertices, self.faces.clone())
+    }
+}
+
+// only a utility for now, nothing in the pipeline builds hulls yet
+impl RobloxMesh {
+    pub fn convex_hull(&self) -> Result<ConvexHull, FixerError> {
+        let positions = self.vertices.iter().map(|x| x.position).collect::<Vec<_>>();
+        convex_hull(&positions)
+    }
+}
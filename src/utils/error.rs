@@ -0,0 +1,84 @@
+use std::io;
+use thiserror::Error;
+
+use super::{mesh_reader::MeshError, unavailable::AssetUnavailable};
+
+// everything the library can fail with. callers that only want to report
+// can use the Display output, the variants are there to tell an unreachable
+// asset from a broken mesh from a broken place
+#[derive(Debug, Error)]
+pub enum FixerError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("{url} answered with HTTP {status}")]
+    HttpStatus { url: String, status: u16 },
+    #[error(transparent)]
+    Unavailable(#[from] AssetUnavailable),
+    #[error(transparent)]
+    Mesh(#[from] MeshError),
+    // the place or model itself could not be read, written or navigated
+    #[error("{0}")]
+    Dom(String),
+    #[error("{instance} has no usable {property}: {message}")]
+    Property {
+        instance: String,
+        property: String,
+        message: String,
+    },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Database(#[from] sled::Error),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Task(#[from] tokio::task::JoinError),
+    #[error("{0}")]
+    Other(String),
+}
+
+#[cfg(feature = "native")]
+impl FixerError {
+    pub fn http_status(url: &str, status: reqwest::StatusCode) -> Self {
+        FixerError::HttpStatus {
+            url: url.to_string(),
+            status: status.as_u16(),
+        }
+    }
+}
+
+impl From<String> for FixerError {
+    fn from(message: String) -> Self {
+        FixerError::Other(message)
+    }
+}
+
+impl From<&str> for FixerError {
+    fn from(message: &str) -> Self {
+        FixerError::Other(message.to_string())
+    }
+}
+
+// the rbx-dom formats each have their own error types, they all mean the
+// place couldn't be decoded or encoded
+macro_rules! dom_error {
+    ($($error:ty),*) => {
+        $(
+            impl From<$error> for FixerError {
+                fn from(err: $error) -> Self {
+                    FixerError::Dom(err.to_string())
+                }
+            }
+        )*
+    };
+}
+
+dom_error!(
+    rbx_binary::DecodeError,
+    rbx_binary::EncodeError,
+    rbx_xml::DecodeError,
+    rbx_xml::EncodeError
+);
@@ -0,0 +1,124 @@
+use rbx_dom_weak::{
+    types::{Enum, Ref, Variant},
+    WeakDom,
+};
+use std::str::FromStr;
+
+use super::{instance_path, path_filter::PathFilter, tags::TagFilter};
+
+// collision geometry studio baked for the old fidelity or shape, it gets
+// rebuilt from the mesh when these are missing
+pub const PHYSICS_PROPERTIES: [&str; 2] = ["PhysicsData", "PhysicalConfigData"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderFidelity {
+    Automatic = 0,
+    Precise = 1,
+    Performance = 2,
+}
+
+impl FromStr for RenderFidelity {
+    type Err = String;
+
+    fn from_str(fidelity: &str) -> Result<Self, Self::Err> {
+        match fidelity.to_ascii_lowercase().as_str() {
+            "automatic" => Ok(RenderFidelity::Automatic),
+            "precise" => Ok(RenderFidelity::Precise),
+            "performance" => Ok(RenderFidelity::Performance),
+            _ => Err(format!("Unknown render fidelity {:?}", fidelity)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionFidelity {
+    Default = 0,
+    Hull = 1,
+    Box = 2,
+    PreciseConvexDecomposition = 3,
+}
+
+impl FromStr for CollisionFidelity {
+    type Err = String;
+
+    fn from_str(fidelity: &str) -> Result<Self, Self::Err> {
+        match fidelity.to_ascii_lowercase().as_str() {
+            "default" => Ok(CollisionFidelity::Default),
+            "hull" => Ok(CollisionFidelity::Hull),
+            "box" => Ok(CollisionFidelity::Box),
+            "preciseconvexdecomposition" | "precise-convex-decomposition" => {
+                Ok(CollisionFidelity::PreciseConvexDecomposition)
+            }
+            _ => Err(format!("Unknown collision fidelity {:?}", fidelity)),
+        }
+    }
+}
+
+// one rule from the config. a part is covered when it passes both filters,
+// later rules win over earlier ones for the properties they both set
+#[derive(Debug, Clone, Default)]
+pub struct PropertyPolicy {
+    pub path_filter: PathFilter,
+    pub tag_filter: TagFilter,
+    pub render_fidelity: Option<RenderFidelity>,
+    pub collision_fidelity: Option<CollisionFidelity>,
+    pub double_sided: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PolicyResult {
+    pub parts: usize,
+    pub changed: usize,
+}
+
+fn set_property(dom: &mut WeakDom, referent: Ref, name: &str, value: Variant) -> bool {
+    let instance = dom.get_by_ref_mut(referent).expect("policy-instance");
+    if instance.properties.get(name) == Some(&value) {
+        return false;
+    }
+    instance.properties.insert(name.to_string(), value);
+    true
+}
+
+pub fn apply_policies(
+    dom: &mut WeakDom,
+    mesh_parts: &[Ref],
+    policies: &[PropertyPolicy],
+) -> PolicyResult {
+    let mut result = PolicyResult::default();
+
+    for referent in mesh_parts {
+        let path = instance_path(dom, *referent);
+        let mut changed = false;
+        for policy in policies {
+            if !policy.path_filter.matches(&path) || !policy.tag_filter.matches(dom, *referent) {
+                continue;
+            }
+
+            if let Some(fidelity) = policy.render_fidelity {
+                let value = Variant::Enum(Enum::from_u32(fidelity as u32));
+                changed |= set_property(dom, *referent, "RenderFidelity", value);
+            }
+            if let Some(fidelity) = policy.collision_fidelity {
+                let value = Variant::Enum(Enum::from_u32(fidelity as u32));
+                if set_property(dom, *referent, "CollisionFidelity", value) {
+                    let instance = dom.get_by_ref_mut(*referent).expect("policy-instance");
+                    for property in PHYSICS_PROPERTIES.iter() {
+                        instance.properties.remove(*property);
+                    }
+                    changed = true;
+                }
+            }
+            if let Some(double_sided) = policy.double_sided {
+                changed |= set_property(dom, *referent, "DoubleSided", Variant::Bool(double_sided));
+            }
+        }
+
+        result.parts += 1;
+        if changed {
+            result.changed += 1;
+        }
+    }
+
+    result
+}
@@ -0,0 +1,292 @@
+use glam::Mat4;
+use serde_json::{json, Value};
+use std::io::Write;
+
+use rbx_types::CFrame;
+
+use super::{
+    atomic_file::AtomicFile,
+    cframe::CFrameExt,
+    math::affine,
+    mesh_reader::{RobloxMesh, NO_PARENT},
+    FixerError,
+};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+#[derive(Default)]
+struct GltfBuffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GltfBuffer {
+    fn push_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.data.extend_from_slice(bytes);
+        self.views.len() - 1
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_floats(&mut self, values: &[[f32; 3]], kind: &str, bounds: bool) -> usize {
+        let components = if kind == "VEC2" { 2 } else { 3 };
+        let mut bytes = Vec::<u8>::with_capacity(values.len() * components * 4);
+        for value in values {
+            for component in value.iter().take(components) {
+                bytes.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+
+        let view = self.push_view(&bytes, ARRAY_BUFFER);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": kind,
+        });
+
+        // POSITION accessors must carry their bounds
+        if bounds && !values.is_empty() {
+            let mut min = values[0];
+            let mut max = values[0];
+            for value in values {
+                for i in 0..3 {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+
+        self.push_accessor(accessor)
+    }
+
+    fn push_bytes(&mut self, values: &[[u8; 4]], normalized: bool) -> usize {
+        let bytes = values.iter().flatten().cloned().collect::<Vec<u8>>();
+        let view = self.push_view(&bytes, ARRAY_BUFFER);
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_BYTE,
+            "normalized": normalized,
+            "count": values.len(),
+            "type": "VEC4",
+        }))
+    }
+
+    fn push_matrices(&mut self, matrices: &[[f32; 16]]) -> usize {
+        let mut bytes = Vec::<u8>::with_capacity(matrices.len() * 64);
+        for matrix in matrices {
+            for value in matrix.iter() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+        }));
+        self.data.extend_from_slice(&bytes);
+        let view = self.views.len() - 1;
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": matrices.len(),
+            "type": "MAT4",
+        }))
+    }
+
+    fn push_indices(&mut self, faces: &[[i32; 3]]) -> usize {
+        let mut bytes = Vec::<u8>::with_capacity(faces.len() * 12);
+        for face in faces {
+            for index in face {
+                bytes.extend_from_slice(&(*index as u32).to_le_bytes());
+            }
+        }
+
+        let view = self.push_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": faces.len() * 3,
+            "type": "SCALAR",
+        }))
+    }
+}
+
+// glTF matrices are column major, the same as glam's
+fn column_major(cframe: &CFrame) -> [f32; 16] {
+    Mat4::from(affine(cframe)).to_cols_array()
+}
+
+// envelopes index into their subset's bone list, glTF wants skin joints
+fn global_joints(mesh: &RobloxMesh) -> Vec<[u8; 4]> {
+    let mut joints = mesh
+        .vertices
+        .iter()
+        .map(|x| x.weights.bones)
+        .collect::<Vec<_>>();
+
+    for subset in mesh.skin_subsets.iter() {
+        let start = subset.verts_begin as usize;
+        let end = (start + subset.verts_length as usize).min(joints.len());
+        for vertex in joints[start.min(end)..end].iter_mut() {
+            for joint in vertex.iter_mut() {
+                let global = subset
+                    .bone_indices
+                    .get(*joint as usize)
+                    .cloned()
+                    .unwrap_or(0);
+                *joint = global.min(u8::MAX as u16) as u8;
+            }
+        }
+    }
+
+    joints
+}
+
+fn build_document(mesh: &RobloxMesh, name: &str) -> (Value, Vec<u8>) {
+    let mut buffer = GltfBuffer::default();
+    let vertices = &mesh.vertices;
+
+    let positions = vertices
+        .iter()
+        .map(|x| [x.position.x, x.position.y, x.position.z])
+        .collect::<Vec<_>>();
+    let normals = vertices
+        .iter()
+        .map(|x| [x.normal.x, x.normal.y, x.normal.z])
+        .collect::<Vec<_>>();
+    let uvs = vertices
+        .iter()
+        .map(|x| [x.uv.x, x.uv.y, 0.0])
+        .collect::<Vec<_>>();
+    let colors = vertices
+        .iter()
+        .map(|x| x.color.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    let mut attributes = json!({
+        "POSITION": buffer.push_floats(&positions, "VEC3", true),
+        "NORMAL": buffer.push_floats(&normals, "VEC3", false),
+        "TEXCOORD_0": buffer.push_floats(&uvs, "VEC2", false),
+        "COLOR_0": buffer.push_bytes(&colors, true),
+    });
+
+    let mut nodes = vec![json!({ "name": name, "mesh": 0 })];
+    let mut skins = Vec::<Value>::new();
+    if !mesh.bones.is_empty() {
+        let weights = vertices
+            .iter()
+            .map(|x| x.weights.weights)
+            .collect::<Vec<_>>();
+        attributes["JOINTS_0"] = json!(buffer.push_bytes(&global_joints(mesh), false));
+        attributes["WEIGHTS_0"] = json!(buffer.push_bytes(&weights, true));
+
+        // bone cframes are in world space, nodes want them relative to their
+        // parent and the skin wants the inverse of the world bind pose
+        let mut roots = Vec::<usize>::new();
+        let mut children = vec![Vec::<usize>::new(); mesh.bones.len()];
+        let mut inverse_binds = Vec::<[f32; 16]>::with_capacity(mesh.bones.len());
+        for (index, bone) in mesh.bones.iter().enumerate() {
+            let parent = bone.parent_index as usize;
+            let local = if bone.parent_index == NO_PARENT || parent >= mesh.bones.len() {
+                roots.push(index + 1);
+                bone.cframe
+            } else {
+                children[parent].push(index + 1);
+                mesh.bones[parent].cframe.inverse().mult(bone.cframe)
+            };
+
+            nodes.push(json!({ "name": bone.name, "matrix": column_major(&local) }));
+            inverse_binds.push(column_major(&bone.cframe.inverse()));
+        }
+        for (index, children) in children.into_iter().enumerate() {
+            if !children.is_empty() {
+                nodes[index + 1]["children"] = json!(children);
+            }
+        }
+
+        let joint_nodes = (1..=mesh.bones.len()).collect::<Vec<_>>();
+        nodes[0]["skin"] = json!(0);
+        nodes[0]["children"] = json!(roots);
+        skins.push(json!({
+            "joints": joint_nodes,
+            "inverseBindMatrices": buffer.push_matrices(&inverse_binds),
+        }));
+    }
+
+    // only the highest detail LOD is exported
+    let indices = buffer.push_indices(mesh.lod0_faces());
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "rbxlx-mesh-fixer" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": nodes,
+        "meshes": [{
+            "name": name,
+            "primitives": [{ "attributes": attributes, "indices": indices }],
+        }],
+        "accessors": buffer.accessors,
+        "bufferViews": buffer.views,
+        "buffers": [{ "byteLength": buffer.data.len() }],
+    });
+    if !skins.is_empty() {
+        document["skins"] = json!(skins);
+    }
+
+    (document, buffer.data)
+}
+
+fn write_chunk<W: Write>(
+    file: &mut W,
+    kind: u32,
+    data: &[u8],
+    padding: u8,
+) -> Result<(), FixerError> {
+    let padded = (data.len() + 3) / 4 * 4;
+    file.write_all(&(padded as u32).to_le_bytes())?;
+    file.write_all(&kind.to_le_bytes())?;
+    file.write_all(data)?;
+    file.write_all(&vec![padding; padded - data.len()])?;
+    Ok(())
+}
+
+pub fn export_glb(mesh: &RobloxMesh, name: &str, output_path: &str) -> Result<(), FixerError> {
+    let (document, bin) = build_document(mesh, name);
+    let json = serde_json::to_vec(&document)?;
+    let json_len = (json.len() + 3) / 4 * 4;
+    let bin_len = (bin.len() + 3) / 4 * 4;
+    let total = 12 + 8 + json_len + 8 + bin_len;
+
+    let mut file = AtomicFile::create(output_path)?;
+    file.write_all(&GLB_MAGIC.to_le_bytes())?;
+    file.write_all(&2u32.to_le_bytes())?;
+    file.write_all(&(total as u32).to_le_bytes())?;
+    write_chunk(&mut file, CHUNK_JSON, &json, b' ')?;
+    write_chunk(&mut file, CHUNK_BIN, &bin, 0)?;
+    file.commit()
+}
@@ -0,0 +1,198 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use super::{content_url::extract_assetid, report::DedupeReport, FixerError};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+td,th{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#f2f2f2}.cluster{margin-bottom:2em}\
+img{width:96px;height:96px;background:#eee}code{font-size:90%}";
+
+pub fn thumbnail_path(dir: &Path, asset_id: &str) -> Option<PathBuf> {
+    extract_assetid(asset_id.to_string())
+        .ok()
+        .map(|id| dir.join(format!("{}.png", id)))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// rbxthumb:// only resolves inside Studio, browsers need the web endpoint
+// that the same thumbnail is served from
+fn thumbnail_url(asset_id: &str) -> Option<String> {
+    extract_assetid(asset_id.to_string()).ok().map(|id| {
+        format!(
+            "https://www.roblox.com/asset-thumbnail/image?assetId={}&width=150&height=150&format=png",
+            id
+        )
+    })
+}
+
+// fetched thumbnails live in a folder beside the report, so they are linked
+// relative to it and the pair can be zipped up and shared
+fn thumbnail<W: Write>(
+    writer: &mut W,
+    report: &DedupeReport,
+    asset_id: &str,
+) -> Result<(), FixerError> {
+    let local = report.thumbnail_dir.as_ref().and_then(|dir| {
+        let path = thumbnail_path(dir, asset_id)?;
+        if !path.is_file() {
+            return None;
+        }
+        let folder = dir.file_name()?.to_string_lossy().to_string();
+        let file = path.file_name()?.to_string_lossy().to_string();
+        Some(format!("{}/{}", folder, file))
+    });
+
+    match local.or_else(|| thumbnail_url(asset_id)) {
+        Some(url) => write!(
+            writer,
+            "<img src=\"{}\" alt=\"{}\" loading=\"lazy\">",
+            escape_html(&url),
+            escape_html(asset_id)
+        )?,
+        None => write!(writer, "<code>{}</code>", escape_html(asset_id))?,
+    }
+    Ok(())
+}
+
+pub fn write_html<W: Write>(report: &DedupeReport, writer: &mut W) -> Result<(), FixerError> {
+    let instances = report
+        .clusters
+        .iter()
+        .map(|x| x.instances.len())
+        .sum::<usize>();
+    let replaced = report
+        .clusters
+        .iter()
+        .map(|x| x.replaced_assets.len())
+        .sum::<usize>();
+    let savings = report
+        .clusters
+        .iter()
+        .map(|x| x.estimated_savings)
+        .sum::<usize>();
+
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(
+        writer,
+        "<html><head><meta charset=\"utf-8\"><title>Mesh dedupe report</title><style>{}</style></head><body>",
+        STYLE
+    )?;
+    writeln!(writer, "<h1>Mesh dedupe report</h1>")?;
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<tr><th>Clusters</th><td>{}</td></tr>",
+        report.clusters.len()
+    )?;
+    writeln!(
+        writer,
+        "<tr><th>Replaced assets</th><td>{}</td></tr>",
+        replaced
+    )?;
+    writeln!(
+        writer,
+        "<tr><th>Rewritten instances</th><td>{}</td></tr>",
+        instances
+    )?;
+    writeln!(
+        writer,
+        "<tr><th>Estimated savings</th><td>{} bytes</td></tr>",
+        savings
+    )?;
+    let memory = &report.memory;
+    for (label, bytes) in [
+        ("Vertex memory saved", memory.vertex_bytes),
+        ("Index memory saved", memory.index_bytes),
+        ("Texture memory saved", memory.texture_bytes),
+        ("Union memory saved", memory.union_bytes),
+        ("Total memory saved", memory.total()),
+    ]
+    .iter()
+    {
+        writeln!(
+            writer,
+            "<tr><th>{}</th><td>{} bytes</td></tr>",
+            label, bytes
+        )?;
+    }
+    writeln!(writer, "</table>")?;
+
+    for cluster in report.clusters.iter() {
+        writeln!(writer, "<div class=\"cluster\">")?;
+        writeln!(
+            writer,
+            "<h2>Cluster {} &mdash; {} triangles, {} bytes saved</h2>",
+            cluster.hash, cluster.triangles, cluster.estimated_savings
+        )?;
+
+        writeln!(
+            writer,
+            "<table><tr><th>Canonical</th><th>Replaced</th></tr><tr><td>"
+        )?;
+        thumbnail(writer, report, &cluster.canonical_asset)?;
+        writeln!(
+            writer,
+            "<br><code>{}</code></td><td>",
+            escape_html(&cluster.canonical_asset)
+        )?;
+        for asset_id in cluster.replaced_assets.iter() {
+            thumbnail(writer, report, asset_id)?;
+            write!(writer, " ")?;
+        }
+        writeln!(writer, "</td></tr></table>")?;
+        writeln!(
+            writer,
+            "<p>Matched as {} with confidence {:.2}.</p>",
+            cluster.match_kind, cluster.confidence
+        )?;
+        if !cluster.applied {
+            writeln!(
+                writer,
+                "<p>Not applied, below --min-confidence. Review the instances below and merge them by hand or lower the threshold.</p>"
+            )?;
+        }
+        if cluster.third_party_canonical {
+            writeln!(
+                writer,
+                "<p>The canonical is owned by someone else, none of these meshes belong to the preferred owner.</p>"
+            )?;
+        }
+        if !cluster.color_mismatches.is_empty() {
+            writeln!(
+                writer,
+                "<p>Kept because their vertex colors differ from the canonical:"
+            )?;
+            for asset_id in cluster.color_mismatches.iter() {
+                write!(writer, " <code>{}</code>", escape_html(asset_id))?;
+            }
+            writeln!(writer, "</p>")?;
+        }
+
+        writeln!(
+            writer,
+            "<table><tr><th>Instance</th><th>Original asset</th></tr>"
+        )?;
+        for instance in cluster.instances.iter() {
+            writeln!(
+                writer,
+                "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>",
+                escape_html(&instance.path),
+                escape_html(&instance.original_asset)
+            )?;
+        }
+        writeln!(writer, "</table></div>")?;
+    }
+
+    writeln!(writer, "</body></html>")?;
+    Ok(())
+}
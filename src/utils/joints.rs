@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use rbx_types::CFrame;
+use tracing::warn;
+
+use super::{cframe::CFrameExt, reflection::property_or_default};
+
+// every JointInstance subclass stores its offsets in C0/C1
+const JOINT_CLASSES: [&str; 6] = ["Weld", "ManualWeld", "Snap", "Glue", "Motor", "Motor6D"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointSide {
+    Part0,
+    Part1,
+}
+
+impl JointSide {
+    fn offset_property(&self) -> &'static str {
+        match self {
+            JointSide::Part0 => "C0",
+            JointSide::Part1 => "C1",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JointGraph {
+    joints: HashMap<Ref, Vec<(Ref, JointSide)>>,
+    constraints: HashMap<Ref, Vec<Ref>>,
+}
+
+fn get_part(dom: &WeakDom, joint: Ref, property: &str) -> Option<Ref> {
+    match dom.get_by_ref(joint)?.properties.get(property) {
+        Some(Variant::Ref(part)) if part.is_some() => Some(*part),
+        _ => None,
+    }
+}
+
+impl JointGraph {
+    pub fn from_dom(dom: &WeakDom, refs: &[Ref]) -> Self {
+        let mut graph = JointGraph::default();
+
+        for referent in refs {
+            let instance = match dom.get_by_ref(*referent) {
+                Some(instance) => instance,
+                None => continue,
+            };
+
+            if JOINT_CLASSES.contains(&instance.class.as_str()) {
+                for side in [JointSide::Part0, JointSide::Part1].iter() {
+                    let property = match side {
+                        JointSide::Part0 => "Part0",
+                        JointSide::Part1 => "Part1",
+                    };
+
+                    if let Some(part) = get_part(dom, *referent, property) {
+                        graph
+                            .joints
+                            .entry(part)
+                            .or_insert_with(Vec::new)
+                            .push((*referent, *side));
+                    }
+                }
+            } else if instance.class == "WeldConstraint" {
+                for property in ["Part0", "Part1"].iter() {
+                    if let Some(part) = get_part(dom, *referent, property) {
+                        graph
+                            .constraints
+                            .entry(part)
+                            .or_insert_with(Vec::new)
+                            .push(*referent);
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
+    pub fn compensate(&self, dom: &mut WeakDom, part: Ref, correction: CFrame) -> usize {
+        // the part moved from P to P * R, so keeping P * C the same in world
+        // space means every offset attached to it becomes R^-1 * C
+        let inverse = correction.inverse();
+        let mut updated = 0;
+
+        if let Some(joints) = self.joints.get(&part) {
+            for (joint, side) in joints {
+                // an offset left at identity may not be serialized at all
+                let instance = dom.get_by_ref_mut(*joint).expect("joint");
+                let property = side.offset_property();
+                if let Some(Variant::CFrame(offset)) = property_or_default(instance, property) {
+                    instance
+                        .properties
+                        .insert(property.to_string(), Variant::CFrame(inverse.mult(offset)));
+                    updated += 1;
+                }
+            }
+        }
+
+        if let Some(constraints) = self.constraints.get(&part) {
+            for constraint in constraints {
+                let name = dom
+                    .get_by_ref(*constraint)
+                    .map(|x| x.name.clone())
+                    .unwrap_or_default();
+                warn!(
+                    "WeldConstraint {:?} is attached to a rotated part, its offset is recomputed when the place loads",
+                    name
+                );
+            }
+        }
+
+        updated
+    }
+}
+
+pub fn compensate_attachments(dom: &mut WeakDom, part: Ref, correction: CFrame) -> usize {
+    // attachments (and anything parented to them) are placed relative to the
+    // part, counter-rotating them keeps their world position where it was
+    let inverse = correction.inverse();
+    let children = match dom.get_by_ref(part) {
+        Some(instance) => instance.children().to_vec(),
+        None => return 0,
+    };
+    let mut updated = 0;
+
+    for child in children {
+        let instance = dom.get_by_ref_mut(child).expect("part-child");
+        if instance.class != "Attachment" && instance.class != "Bone" {
+            continue;
+        }
+
+        if let Some(Variant::CFrame(offset)) = property_or_default(instance, "CFrame") {
+            instance.properties.insert(
+                String::from("CFrame"),
+                Variant::CFrame(inverse.mult(offset)),
+            );
+            updated += 1;
+        }
+    }
+
+    updated
+}
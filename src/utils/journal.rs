@@ -0,0 +1,181 @@
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use rbx_types::{CFrame, Vector3};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    atomic_file::AtomicFile, cframe::CFrameExt, instance_path, reflection::property_or_default,
+    FixerError,
+};
+
+// every property the fixer is allowed to touch
+const TRACKED_PROPERTIES: [&str; 12] = [
+    "MeshId",
+    "MeshContent",
+    "Size",
+    "InitialSize",
+    "CFrame",
+    "C0",
+    "C1",
+    "AssetId",
+    "ColorMap",
+    "MetalnessMap",
+    "NormalMap",
+    "RoughnessMap",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum JournalValue {
+    Content(String),
+    Vector3([f32; 3]),
+    CFrame([f32; 12]),
+}
+
+impl JournalValue {
+    pub fn from_variant(variant: &Variant) -> Option<Self> {
+        match variant {
+            Variant::Content(content) => Some(JournalValue::Content(content.as_str().to_string())),
+            Variant::Vector3(v) => Some(JournalValue::Vector3([v.x, v.y, v.z])),
+            Variant::CFrame(cframe) => {
+                let c = cframe.components();
+                Some(JournalValue::CFrame([
+                    c.0, c.1, c.2, c.3, c.4, c.5, c.6, c.7, c.8, c.9, c.10, c.11,
+                ]))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_variant(&self) -> Variant {
+        match self {
+            JournalValue::Content(content) => {
+                Variant::Content(rbx_types::Content::from(content.clone()))
+            }
+            JournalValue::Vector3(v) => Variant::Vector3(Vector3::new(v[0], v[1], v[2])),
+            JournalValue::CFrame(c) => Variant::CFrame(CFrame::from_components(c)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub path: String,
+    pub referent: String,
+    pub property: String,
+    pub old_value: JournalValue,
+    pub new_value: JournalValue,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeJournal {
+    pub entries: Vec<JournalEntry>,
+}
+
+pub struct JournalSnapshot {
+    values: Vec<(Ref, &'static str, JournalValue)>,
+}
+
+impl JournalSnapshot {
+    pub fn capture(dom: &WeakDom, refs: &[Ref]) -> Self {
+        let mut values = Vec::new();
+
+        for referent in refs {
+            let instance = match dom.get_by_ref(*referent) {
+                Some(instance) => instance,
+                None => continue,
+            };
+
+            // defaulted properties count too, the rewrite may fill them in
+            for &property in TRACKED_PROPERTIES.iter() {
+                if let Some(value) = property_or_default(instance, property)
+                    .as_ref()
+                    .and_then(JournalValue::from_variant)
+                {
+                    values.push((*referent, property, value));
+                }
+            }
+        }
+
+        JournalSnapshot { values }
+    }
+
+    pub fn diff(&self, dom: &WeakDom) -> ChangeJournal {
+        let mut journal = ChangeJournal::default();
+
+        for (referent, property, old_value) in self.values.iter() {
+            let new_value = match dom
+                .get_by_ref(*referent)
+                .and_then(|x| property_or_default(x, property))
+                .as_ref()
+                .and_then(JournalValue::from_variant)
+            {
+                Some(new_value) => new_value,
+                None => continue,
+            };
+
+            if new_value != *old_value {
+                journal.entries.push(JournalEntry {
+                    path: instance_path(dom, *referent),
+                    referent: format!("{:?}", referent),
+                    property: property.to_string(),
+                    old_value: old_value.clone(),
+                    new_value,
+                });
+            }
+        }
+
+        journal
+    }
+}
+
+impl ChangeJournal {
+    pub fn write(&self, output_path: &str) -> Result<(), FixerError> {
+        let mut output_file = AtomicFile::create(output_path)?;
+        serde_json::to_writer_pretty(&mut output_file, self)?;
+        output_file.commit()
+    }
+
+    pub fn read(input_path: &str) -> Result<Self, FixerError> {
+        let input_fp = Path::new(input_path);
+        let input_file = BufReader::new(File::open(input_fp)?);
+        Ok(serde_json::from_reader(input_file)?)
+    }
+
+    // referents don't survive a save, so entries are matched back up by
+    // instance path and by the property still holding the value we wrote
+    pub fn revert(&self, dom: &mut WeakDom, refs: &[Ref]) -> usize {
+        let mut paths = HashMap::<String, Vec<Ref>>::new();
+        for referent in refs {
+            paths
+                .entry(instance_path(dom, *referent))
+                .or_insert_with(Vec::new)
+                .push(*referent);
+        }
+
+        let mut reverted = 0;
+        for entry in self.entries.iter() {
+            let candidates = match paths.get(&entry.path) {
+                Some(candidates) => candidates,
+                None => continue,
+            };
+
+            for referent in candidates {
+                let instance = dom.get_by_ref_mut(*referent).expect("journal-instance");
+                if let Some(prop) = instance.properties.get_mut(&entry.property) {
+                    if JournalValue::from_variant(prop).as_ref() == Some(&entry.new_value) {
+                        *prop = entry.old_value.to_variant();
+                        reverted += 1;
+                        break;
+                    }
+                }
+            }
+        }
+
+        reverted
+    }
+}
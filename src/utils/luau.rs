@@ -0,0 +1,123 @@
+use std::fmt::Write;
+
+use super::{
+    atomic_file::write_atomic,
+    journal::{ChangeJournal, JournalValue},
+    FixerError,
+};
+
+// MeshId and InitialSize can't be assigned from a script, ApplyMesh sets
+// both from a MeshPart created for the new id
+const APPLIED_WITH_MESH: [&str; 2] = ["MeshContent", "InitialSize"];
+const SCRIPTABLE_PROPERTIES: [&str; 4] = ["Size", "CFrame", "C0", "C1"];
+
+const HEADER: &str = r#"-- generated by rbxlx-mesh-fixer. paste into the studio command bar or run
+-- from a plugin, ApplyMesh needs plugin security
+local AssetService = game:GetService("AssetService")
+local ChangeHistoryService = game:GetService("ChangeHistoryService")
+
+local function find(path)
+	local names = string.split(path, "/")
+	local current = game:FindFirstChild(names[1])
+	for i = 2, #names do
+		if current == nil then
+			return nil
+		end
+		current = current:FindFirstChild(names[i])
+	end
+	return current
+end
+
+local meshes = {}
+local function applyMesh(instance, meshId)
+	local source = meshes[meshId]
+	if source == nil then
+		source = AssetService:CreateMeshPartAsync(meshId)
+		meshes[meshId] = source
+	end
+	instance:ApplyMesh(source)
+end
+
+"#;
+
+const FOOTER: &str = r#"
+ChangeHistoryService:SetWaypoint("Before rbxlx-mesh-fixer")
+local applied, missing = 0, 0
+for _, change in ipairs(changes) do
+	local instance = find(change[1])
+	if instance == nil then
+		missing += 1
+		warn("rbxlx-mesh-fixer: could not find " .. change[1])
+	elseif change[2] == "MeshId" then
+		applyMesh(instance, change[3])
+		applied += 1
+	else
+		instance[change[2]] = change[3]
+		applied += 1
+	end
+end
+ChangeHistoryService:SetWaypoint("After rbxlx-mesh-fixer")
+print(string.format("rbxlx-mesh-fixer: applied %d changes, %d instances missing", applied, missing))
+"#;
+
+// rust's string escapes are all valid in luau
+fn luau_string(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+fn luau_value(value: &JournalValue) -> String {
+    match value {
+        JournalValue::Content(content) => luau_string(content),
+        JournalValue::Vector3(v) => format!("Vector3.new({:?}, {:?}, {:?})", v[0], v[1], v[2]),
+        JournalValue::CFrame(c) => format!(
+            "CFrame.new({})",
+            c.iter()
+                .map(|x| format!("{:?}", x))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+// the journal's rewrites as a script that replays them in an open place.
+// instances are found by path the same way a revert finds them, journal
+// entries come in the order the rewrite made them so the mesh of a part is
+// always applied before its size
+pub fn migration_script(journal: &ChangeJournal) -> String {
+    let mut script = String::from(HEADER);
+    let mut skipped = vec![];
+
+    script.push_str("local changes = {\n");
+    for entry in journal.entries.iter() {
+        if APPLIED_WITH_MESH.contains(&entry.property.as_str()) {
+            continue;
+        }
+        if entry.property != "MeshId" && !SCRIPTABLE_PROPERTIES.contains(&entry.property.as_str()) {
+            skipped.push(entry);
+            continue;
+        }
+        writeln!(
+            script,
+            "\t{{ {}, {}, {} }},",
+            luau_string(&entry.path),
+            luau_string(&entry.property),
+            luau_value(&entry.new_value)
+        )
+        .expect("luau-write");
+    }
+    script.push_str("}\n");
+
+    if !skipped.is_empty() {
+        script.push_str("\n-- not settable from a script, apply these from the rewritten place:\n");
+        for entry in skipped {
+            writeln!(script, "--   {}.{}", entry.path, entry.property).expect("luau-write");
+        }
+    }
+
+    script.push_str(FOOTER);
+    script
+}
+
+pub fn write_migration_script(journal: &ChangeJournal, path: &str) -> Result<(), FixerError> {
+    write_atomic(path, migration_script(journal).as_bytes())
+}
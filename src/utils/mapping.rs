@@ -0,0 +1,41 @@
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+use rbx_dom_weak::{types::Ref, WeakDom};
+
+use super::{
+    atomic_file::AtomicFile,
+    content_url::normalize_content,
+    mesh_content::{get_mesh_id, set_mesh_id},
+    FixerError,
+};
+
+pub type AssetMapping = BTreeMap<String, String>;
+
+pub fn write_mapping(output_path: &str, mapping: &AssetMapping) -> Result<(), FixerError> {
+    let mut output_file = AtomicFile::create(output_path)?;
+    serde_json::to_writer_pretty(&mut output_file, mapping)?;
+    output_file.commit()
+}
+
+pub fn read_mapping(input_path: &str) -> Result<AssetMapping, FixerError> {
+    let input_fp = Path::new(input_path);
+    let input_file = BufReader::new(File::open(input_fp)?);
+    Ok(serde_json::from_reader(input_file)?)
+}
+
+pub fn apply_mapping(dom: &mut WeakDom, refs: &[Ref], mapping: &AssetMapping) -> usize {
+    let mut rewritten = 0;
+
+    for referent in refs {
+        let instance = dom.get_by_ref_mut(*referent).expect("mapped-instance");
+        let new_id = match mapping.get(&normalize_content(&get_mesh_id(instance))) {
+            Some(new_id) => normalize_content(new_id),
+            None => continue,
+        };
+
+        set_mesh_id(instance, &new_id);
+        rewritten += 1;
+    }
+
+    rewritten
+}
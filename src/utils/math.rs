@@ -0,0 +1,46 @@
+use glam::{Affine3A, DVec3, Mat3, Vec3};
+use rbx_types::{CFrame, Matrix3, Vector3};
+
+// geometry is done in glam, rbx_types values are only converted at the edges
+// where they come out of or go back into a place or mesh file
+
+pub fn vec3(v: Vector3) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+pub fn dvec3(v: Vector3) -> DVec3 {
+    DVec3::new(v.x as f64, v.y as f64, v.z as f64)
+}
+
+pub fn vector3(v: Vec3) -> Vector3 {
+    Vector3::new(v.x, v.y, v.z)
+}
+
+pub fn dvector3(v: DVec3) -> Vector3 {
+    Vector3::new(v.x as f32, v.y as f32, v.z as f32)
+}
+
+// a Matrix3's `x`, `y` and `z` are its rows, glam stores columns
+pub fn mat3(m: &Matrix3) -> Mat3 {
+    Mat3::from_cols(vec3(m.x), vec3(m.y), vec3(m.z)).transpose()
+}
+
+pub fn matrix3(m: Mat3) -> Matrix3 {
+    let rows = m.transpose();
+    Matrix3 {
+        x: vector3(rows.x_axis),
+        y: vector3(rows.y_axis),
+        z: vector3(rows.z_axis),
+    }
+}
+
+pub fn affine(cframe: &CFrame) -> Affine3A {
+    Affine3A::from_mat3_translation(mat3(&cframe.orientation), vec3(cframe.position))
+}
+
+pub fn cframe(affine: Affine3A) -> CFrame {
+    CFrame {
+        position: vector3(affine.translation.into()),
+        orientation: matrix3(affine.matrix3.into()),
+    }
+}
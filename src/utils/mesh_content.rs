@@ -0,0 +1,55 @@
+use rbx_dom_weak::{types::Variant, Instance};
+
+use super::reflection::property_or_default;
+
+// current Studio keeps the mesh reference in MeshContent and only mirrors it
+// into MeshId for older readers, files may carry either one or both
+pub const MESH_PROPERTIES: [&str; 2] = ["MeshContent", "MeshId"];
+
+fn content_string(value: &Variant) -> Option<String> {
+    match value {
+        Variant::Content(content) => Some(content.as_str().to_string()),
+        // some exporters write the reference out as a plain string
+        Variant::String(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+pub fn has_mesh_property(instance: &Instance) -> bool {
+    MESH_PROPERTIES
+        .iter()
+        .any(|x| instance.properties.contains_key(*x))
+}
+
+// the first non-empty reference wins, MeshContent is checked first since
+// that's the one Studio loads from
+pub fn get_mesh_id(instance: &Instance) -> String {
+    for name in MESH_PROPERTIES.iter() {
+        if let Some(mesh_id) = instance.properties.get(*name).and_then(content_string) {
+            if mesh_id.trim() != "" {
+                return mesh_id;
+            }
+        }
+    }
+
+    property_or_default(instance, "MeshId")
+        .as_ref()
+        .and_then(content_string)
+        .unwrap_or_default()
+}
+
+// every representation the instance already has is rewritten so the two
+// never disagree, MeshId is always set
+pub fn set_mesh_id(instance: &mut Instance, mesh_id: &str) {
+    if let Some(value) = instance.properties.get_mut("MeshContent") {
+        *value = match value {
+            Variant::String(_) => Variant::String(mesh_id.to_string()),
+            _ => Variant::Content(rbx_types::Content::from(mesh_id.to_string())),
+        };
+    }
+
+    instance.properties.insert(
+        String::from("MeshId"),
+        Variant::Content(rbx_types::Content::from(mesh_id.to_string())),
+    );
+}
@@ -1,11 +1,150 @@
+#[cfg(feature = "native")]
+use super::asset_downloader::download_asset;
 use super::{
-    asset_downloader::download_asset,
-    cframe::{Vector2Ext, Vector3Ext},
-    GenericError,
+    asset_provider::AssetProvider,
+    cframe::Vector2Ext,
+    math::{dvec3, vec3, vector3},
+    obb::OrientedBoundingBox,
+    FixerError,
 };
-use byteorder::{LittleEndian, ReadBytesExt};
-use rbx_types::{Matrix3, Vector2, Vector3};
-use std::io::{Cursor, Read};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use glam::Vec3;
+use rbx_types::{CFrame, Matrix3, Vector2, Vector3};
+use std::{
+    error::Error,
+    fmt,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+};
+
+// on-disk sizes of each repeated record in a v4 mesh
+const VERTEX_SIZE: usize = 40;
+const ENVELOPE_SIZE: usize = 8;
+const FACE_SIZE: usize = 12;
+const LOD_SIZE: usize = 4;
+const BONE_SIZE: usize = 60;
+const SUBSET_SIZE: usize = 72;
+
+// uvs closer than this hash the same, exporters round them differently
+const UV_SIGNATURE_STEP: f32 = 1.0 / 4096.0;
+// volume and surface area are rounded to this before they are hashed
+const MEASURE_SIGNATURE_STEP: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub enum MeshError {
+    UnsupportedVersion {
+        found: String,
+    },
+    Truncated {
+        section: &'static str,
+        offset: u64,
+        needed: usize,
+        remaining: usize,
+    },
+    InvalidCount {
+        section: &'static str,
+        count: i64,
+    },
+    // a face pointing past the vertices or a lod past the faces
+    InvalidIndex {
+        section: &'static str,
+        entry: usize,
+        index: i32,
+        limit: usize,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::UnsupportedVersion { found } => {
+                write!(f, "unsupported mesh version {:?}", found)
+            }
+            MeshError::Truncated {
+                section,
+                offset,
+                needed,
+                remaining,
+            } => write!(
+                f,
+                "mesh is truncated in {} at offset {}: needs {} bytes, {} left",
+                section, offset, needed, remaining
+            ),
+            MeshError::InvalidCount { section, count } => {
+                write!(
+                    f,
+                    "mesh header has an invalid {} count of {}",
+                    section, count
+                )
+            }
+            MeshError::InvalidIndex {
+                section: "lods",
+                entry,
+                index,
+                limit,
+            } => write!(
+                f,
+                "lod {} starts at face {}, before the lod ahead of it or past the {} faces",
+                entry, index, limit
+            ),
+            MeshError::InvalidIndex {
+                entry,
+                index,
+                limit,
+                ..
+            } => write!(
+                f,
+                "face {} references vertex {} but the mesh only has {}",
+                entry, index, limit
+            ),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+impl MeshError {
+    pub fn is_unsupported_version(err: &FixerError) -> bool {
+        matches!(err, FixerError::Mesh(MeshError::UnsupportedVersion { .. }))
+    }
+}
+
+// counts come straight from the header, so they are checked against what is
+// actually left in the buffer before anything gets allocated
+fn ensure_remaining(
+    cursor: &Cursor<Vec<u8>>,
+    section: &'static str,
+    count: i64,
+    size: usize,
+) -> Result<usize, MeshError> {
+    let length = cursor.get_ref().len() as u64;
+    ensure_within(cursor.position(), length, section, count, size)
+}
+
+// the same check for a stream, whose length is looked up once up front
+fn ensure_within(
+    offset: u64,
+    length: u64,
+    section: &'static str,
+    count: i64,
+    size: usize,
+) -> Result<usize, MeshError> {
+    if count < 0 {
+        return Err(MeshError::InvalidCount { section, count });
+    }
+
+    let remaining = length.saturating_sub(offset) as usize;
+    let needed = (count as usize).saturating_mul(size);
+    if needed > remaining {
+        return Err(MeshError::Truncated {
+            section,
+            offset,
+            needed,
+            remaining,
+        });
+    }
+
+    Ok(count as usize)
+}
 
 #[derive(Debug, Clone)]
 pub struct RobloxBoneWeights {
@@ -22,6 +161,33 @@ pub struct RobloxMeshVertex {
     pub weights: RobloxBoneWeights,
 }
 
+// bones are stored with their world space cframe, parents are indices into
+// the same list with 0xFFFF meaning no parent
+#[derive(Debug, Clone)]
+pub struct RobloxBone {
+    pub name_index: u32,
+    pub name: String,
+    pub parent_index: u16,
+    pub lod_parent_index: u16,
+    pub culling: f32,
+    pub cframe: CFrame,
+}
+
+pub const NO_PARENT: u16 = 0xFFFF;
+pub const SUBSET_BONE_LIMIT: usize = 26;
+
+// a skinned mesh is split into subsets that each reference at most 26 bones,
+// the vertex envelopes index into the subset's bone list
+#[derive(Debug, Clone)]
+pub struct RobloxSkinSubset {
+    pub faces_begin: u32,
+    pub faces_length: u32,
+    pub verts_begin: u32,
+    pub verts_length: u32,
+    pub num_bone_indices: u32,
+    pub bone_indices: [u16; SUBSET_BONE_LIMIT],
+}
+
 #[derive(Debug, Clone)]
 pub struct RobloxMeshHeader {
     pub num_meshes: u16,
@@ -48,6 +214,20 @@ pub struct RobloxMeshBoundingBoxSize {
     pub max: Vector3,
 }
 
+// everything the dedupe hash is made of, without the geometry behind it
+#[derive(Debug, Clone)]
+pub struct MeshSignature {
+    pub header: RobloxMeshHeader,
+    pub lods: Vec<i32>,
+    pub bounding_box: RobloxMeshBoundingBox,
+    pub bounding_box_size: RobloxMeshBoundingBoxSize,
+    pub triangles: i32,
+    pub volume: f32,
+    pub surface_area: f32,
+    pub hash: i32,
+    pub uv_hash: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct RobloxMesh {
     pub header: RobloxMeshHeader,
@@ -55,32 +235,153 @@ pub struct RobloxMesh {
     pub lods: Vec<i32>,
     pub faces: Vec<[i32; 3]>,
     pub vertices: Vec<RobloxMeshVertex>,
+    pub bones: Vec<RobloxBone>,
+    pub name_table: Vec<u8>,
+    pub skin_subsets: Vec<RobloxSkinSubset>,
 
     // Custom fields
     pub bounding_box: RobloxMeshBoundingBox,
     pub bounding_box_size: RobloxMeshBoundingBoxSize,
+    pub oriented_bounding_box: OrientedBoundingBox,
     pub rotation: Vector3,
     pub triangles: i32,
     pub hash: i32,
+    pub uv_hash: u32,
 }
 
-macro_rules! check_set_min {
-    ($pos:expr, $min:expr) => {
-        if $pos < $min {
-            $min = $pos
-        }
-    };
+impl RobloxMeshBoundingBox {}
+
+// the triangle count and bounds alone collide easily, volume and surface
+// area tell apart most meshes that happen to share them
+fn signature_hash(
+    triangles: i32,
+    size: &RobloxMeshBoundingBoxSize,
+    volume: f32,
+    surface_area: f32,
+) -> i32 {
+    let min = size.min.x + size.min.y + size.min.z;
+    let max = size.max.x + size.max.y + size.max.z;
+    let volume = (volume as f64 / MEASURE_SIGNATURE_STEP).round() as i64 as u32 as u64;
+    let area = (surface_area as f64 / MEASURE_SIGNATURE_STEP).round() as i64 as u32 as u64;
+    triangles.wrapping_add((min.abs() + max) as i32) ^ hash_share(volume << 32 | area) as i32
 }
 
-macro_rules! check_set_max {
-    ($pos:expr, $max:expr) => {
-        if $pos > $max {
-            $max = $pos
+// lods are face offsets that only ever grow and stay inside the face buffer,
+// anything else is a corrupt table that would throw the triangle count off
+fn validate_lods(lods: &[i32], num_faces: usize) -> Result<(), MeshError> {
+    let mut previous = 0;
+    for (entry, lod) in lods.iter().enumerate() {
+        if *lod < previous || *lod as usize > num_faces {
+            return Err(MeshError::InvalidIndex {
+                section: "lods",
+                entry,
+                index: *lod,
+                limit: num_faces,
+            });
         }
-    };
+        previous = *lod;
+    }
+    Ok(())
 }
 
-impl RobloxMeshBoundingBox {}
+// LODs are index ranges into the same face buffer, the first one is the
+// full detail mesh
+fn lod0_range(lods: &[i32], num_faces: usize) -> std::ops::Range<usize> {
+    if lods.len() > 1 {
+        let end = (lods[1].max(0) as usize).min(num_faces);
+        (lods[0].max(0) as usize).min(end)..end
+    } else {
+        0..num_faces
+    }
+}
+
+// volume is the signed tetrahedron sum against the origin, which only means
+// something for closed meshes, open ones still get a stable number out of
+// it. both are summed in f64 so large meshes don't drift
+#[derive(Default)]
+struct Measure {
+    volume: f64,
+    area: f64,
+}
+
+impl Measure {
+    fn add(&mut self, a: Vector3, b: Vector3, c: Vector3) {
+        let (a, b, c) = (dvec3(a), dvec3(b), dvec3(c));
+        self.volume += a.dot(b.cross(c)) / 6.0;
+        self.area += (b - a).cross(c - a).length() * 0.5;
+    }
+
+    fn finish(&self) -> (f32, f32) {
+        (self.volume.abs() as f32, self.area as f32)
+    }
+}
+
+fn measure(faces: &[[i32; 3]], position: impl Fn(i32) -> Vector3) -> (f32, f32) {
+    let mut measure = Measure::default();
+    for face in faces {
+        measure.add(position(face[0]), position(face[1]), position(face[2]));
+    }
+    measure.finish()
+}
+
+// one vertex's share of a per-vertex hash. the shares are summed, so the
+// hash doesn't depend on vertex order and can be built while streaming. the
+// splitmix64 finalizer keeps nearby values from cancelling each other out
+fn hash_share(value: u64) -> u32 {
+    let mut x = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (x ^ (x >> 31)) as u32
+}
+
+fn uv_hash_share(uv: &Vector3) -> u32 {
+    let u = (uv.x / UV_SIGNATURE_STEP).round() as i32 as u32 as u64;
+    let v = (uv.y / UV_SIGNATURE_STEP).round() as i32 as u32 as u64;
+    hash_share(u << 32 | v)
+}
+
+// the bounding box extremes are picked one after another, each skipping the
+// vertices claimed before it, so the four best of every kind are enough to
+// replay that without keeping the vertex list around
+struct ExtremeCandidates {
+    better: fn(&Vector3, &Vector3) -> bool,
+    best: Vec<(usize, Vector3)>,
+}
+
+impl ExtremeCandidates {
+    fn new(better: fn(&Vector3, &Vector3) -> bool) -> Self {
+        ExtremeCandidates {
+            better,
+            best: Vec::with_capacity(5),
+        }
+    }
+
+    // ties keep the earlier vertex in front, the same as the full parse
+    fn offer(&mut self, idx: usize, position: Vector3) {
+        let better = self.better;
+        let at = self
+            .best
+            .iter()
+            .position(|(_, x)| better(&position, x))
+            .unwrap_or(self.best.len());
+        if at < 4 {
+            self.best.insert(at, (idx, position));
+            self.best.truncate(4);
+        }
+    }
+
+    fn take(&self, taken: &mut Vec<usize>, num_verts: usize) -> Vector3 {
+        let (idx, position) = *self
+            .best
+            .iter()
+            .find(|(idx, _)| !taken.contains(idx))
+            .expect("mesh-vertices");
+        if num_verts - taken.len() > 1 {
+            taken.push(idx);
+        }
+        position
+    }
+}
 
 impl RobloxMesh {
     fn default_vector() -> Vector3 {
@@ -91,12 +392,29 @@ impl RobloxMesh {
         }
     }
 
-    fn read_header(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxMeshHeader, GenericError> {
+    fn read_header(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxMeshHeader, FixerError> {
+        ensure_remaining(cursor, "header", 1, 13 + 24)?;
+        RobloxMesh::parse_header(cursor)
+    }
+
+    fn parse_header<R: Read>(cursor: &mut R) -> Result<RobloxMeshHeader, FixerError> {
         let mut version: [u8; 13] = [0; 13];
-        cursor.read(&mut version)?;
+        cursor.read_exact(&mut version)?;
 
-        assert_eq!(std::str::from_utf8(&version)?, "version 4.00\n");
-        assert_eq!(cursor.read_i16::<LittleEndian>()?, 24);
+        if &version != b"version 4.00\n" {
+            let found = String::from_utf8_lossy(&version);
+            return Err(MeshError::UnsupportedVersion {
+                found: found.trim_end().to_string(),
+            }
+            .into());
+        }
+        let header_size = cursor.read_i16::<LittleEndian>()?;
+        if header_size != 24 {
+            return Err(MeshError::UnsupportedVersion {
+                found: format!("version 4.00 with a {} byte header", header_size),
+            }
+            .into());
+        }
 
         Ok(RobloxMeshHeader {
             num_meshes: cursor.read_u16::<LittleEndian>()?,
@@ -110,7 +428,7 @@ impl RobloxMesh {
         })
     }
 
-    fn read_vector3(cursor: &mut Cursor<Vec<u8>>) -> Result<Vector3, GenericError> {
+    fn read_vector3<R: Read>(cursor: &mut R) -> Result<Vector3, FixerError> {
         Ok(Vector3 {
             x: cursor.read_f32::<LittleEndian>()?,
             y: cursor.read_f32::<LittleEndian>()?,
@@ -118,12 +436,12 @@ impl RobloxMesh {
         })
     }
 
-    fn read_vert_weights(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxBoneWeights, GenericError> {
+    fn read_vert_weights(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxBoneWeights, FixerError> {
         let mut bones: [u8; 4] = [0; 4];
         let mut weights: [u8; 4] = [0; 4];
 
-        cursor.read(&mut bones)?;
-        cursor.read(&mut weights)?;
+        cursor.read_exact(&mut bones)?;
+        cursor.read_exact(&mut weights)?;
 
         Ok(RobloxBoneWeights {
             bones: bones,
@@ -134,9 +452,10 @@ impl RobloxMesh {
     fn read_faces(
         header: &RobloxMeshHeader,
         cursor: &mut Cursor<Vec<u8>>,
-    ) -> Result<Vec<[i32; 3]>, GenericError> {
-        let mut faces = Vec::<[i32; 3]>::with_capacity(header.num_faces as usize);
-        for _ in 0..header.num_faces {
+    ) -> Result<Vec<[i32; 3]>, FixerError> {
+        let num_faces = ensure_remaining(cursor, "faces", header.num_faces as i64, FACE_SIZE)?;
+        let mut faces = Vec::<[i32; 3]>::with_capacity(num_faces);
+        for _ in 0..num_faces {
             faces.push([
                 cursor.read_i32::<LittleEndian>()?,
                 cursor.read_i32::<LittleEndian>()?,
@@ -150,9 +469,15 @@ impl RobloxMesh {
     fn read_verts(
         header: &RobloxMeshHeader,
         cursor: &mut Cursor<Vec<u8>>,
-    ) -> Result<Vec<RobloxMeshVertex>, GenericError> {
-        let mut verts = Vec::<RobloxMeshVertex>::with_capacity(header.num_verts as usize);
-        for _ in 0..header.num_verts {
+    ) -> Result<Vec<RobloxMeshVertex>, FixerError> {
+        let vertex_size = if header.num_bones > 0 {
+            VERTEX_SIZE + ENVELOPE_SIZE
+        } else {
+            VERTEX_SIZE
+        };
+        let num_verts = ensure_remaining(cursor, "vertices", header.num_verts as i64, vertex_size)?;
+        let mut verts = Vec::<RobloxMeshVertex>::with_capacity(num_verts);
+        for _ in 0..num_verts {
             verts.push(RobloxMeshVertex {
                 position: RobloxMesh::read_vector3(cursor)?,
                 normal: RobloxMesh::read_vector3(cursor)?,
@@ -166,7 +491,7 @@ impl RobloxMesh {
         }
 
         if header.num_bones > 0 {
-            for x in 0..header.num_verts as usize {
+            for x in 0..num_verts {
                 let mut vert = &mut verts[x];
                 vert.weights = RobloxMesh::read_vert_weights(cursor)?;
             }
@@ -178,15 +503,115 @@ impl RobloxMesh {
     fn read_lods(
         header: &RobloxMeshHeader,
         cursor: &mut Cursor<Vec<u8>>,
-    ) -> Result<Vec<i32>, GenericError> {
-        let mut lods = Vec::<i32>::with_capacity(header.num_lods as usize);
-        for _ in 0..header.num_lods {
+    ) -> Result<Vec<i32>, FixerError> {
+        let num_lods = ensure_remaining(cursor, "lods", header.num_lods as i64, LOD_SIZE)?;
+        let mut lods = Vec::<i32>::with_capacity(num_lods);
+        for _ in 0..num_lods {
             lods.push(cursor.read_i32::<LittleEndian>()?);
         }
 
         Ok(lods)
     }
 
+    fn read_bones(
+        header: &RobloxMeshHeader,
+        cursor: &mut Cursor<Vec<u8>>,
+    ) -> Result<Vec<RobloxBone>, FixerError> {
+        let num_bones = ensure_remaining(cursor, "bones", header.num_bones as i64, BONE_SIZE)?;
+        let mut bones = Vec::<RobloxBone>::with_capacity(num_bones);
+        for _ in 0..num_bones {
+            let name_index = cursor.read_u32::<LittleEndian>()?;
+            let parent_index = cursor.read_u16::<LittleEndian>()?;
+            let lod_parent_index = cursor.read_u16::<LittleEndian>()?;
+            let culling = cursor.read_f32::<LittleEndian>()?;
+
+            let mut components = [0.0f32; 12];
+            for component in components[3..].iter_mut() {
+                *component = cursor.read_f32::<LittleEndian>()?;
+            }
+            for component in components[..3].iter_mut() {
+                *component = cursor.read_f32::<LittleEndian>()?;
+            }
+
+            bones.push(RobloxBone {
+                name_index,
+                name: String::new(),
+                parent_index,
+                lod_parent_index,
+                culling,
+                cframe: CFrame {
+                    position: Vector3::new(components[0], components[1], components[2]),
+                    orientation: Matrix3 {
+                        x: Vector3::new(components[3], components[4], components[5]),
+                        y: Vector3::new(components[6], components[7], components[8]),
+                        z: Vector3::new(components[9], components[10], components[11]),
+                    },
+                },
+            });
+        }
+
+        Ok(bones)
+    }
+
+    fn read_name_table(
+        header: &RobloxMeshHeader,
+        cursor: &mut Cursor<Vec<u8>>,
+    ) -> Result<Vec<u8>, FixerError> {
+        let size = ensure_remaining(cursor, "name table", header.name_table_size as i64, 1)?;
+        let mut name_table = vec![0u8; size];
+        cursor.read_exact(&mut name_table)?;
+        Ok(name_table)
+    }
+
+    fn read_skin_subsets(
+        header: &RobloxMeshHeader,
+        cursor: &mut Cursor<Vec<u8>>,
+    ) -> Result<Vec<RobloxSkinSubset>, FixerError> {
+        let num_subsets = ensure_remaining(
+            cursor,
+            "skin subsets",
+            header.num_skin_data as i64,
+            SUBSET_SIZE,
+        )?;
+        let mut subsets = Vec::<RobloxSkinSubset>::with_capacity(num_subsets);
+        for _ in 0..num_subsets {
+            let faces_begin = cursor.read_u32::<LittleEndian>()?;
+            let faces_length = cursor.read_u32::<LittleEndian>()?;
+            let verts_begin = cursor.read_u32::<LittleEndian>()?;
+            let verts_length = cursor.read_u32::<LittleEndian>()?;
+            let num_bone_indices = cursor.read_u32::<LittleEndian>()?;
+            let mut bone_indices = [0u16; SUBSET_BONE_LIMIT];
+            for index in bone_indices.iter_mut() {
+                *index = cursor.read_u16::<LittleEndian>()?;
+            }
+
+            subsets.push(RobloxSkinSubset {
+                faces_begin,
+                faces_length,
+                verts_begin,
+                verts_length,
+                num_bone_indices,
+                bone_indices,
+            });
+        }
+
+        Ok(subsets)
+    }
+
+    // names are null terminated strings packed into the table, bones point at
+    // the byte offset their name starts at
+    fn resolve_bone_names(&mut self) {
+        for bone in self.bones.iter_mut() {
+            let start = (bone.name_index as usize).min(self.name_table.len());
+            let end = self.name_table[start..]
+                .iter()
+                .position(|x| *x == 0)
+                .map(|x| start + x)
+                .unwrap_or(self.name_table.len());
+            bone.name = String::from_utf8_lossy(&self.name_table[start..end]).into_owned();
+        }
+    }
+
     /*
     fn get_most_right_point(&self) -> Vector3 {
         let max_x_vert = self.vertices[0].position.clone();
@@ -199,137 +624,234 @@ impl RobloxMesh {
     }
     */
 
-    fn get_vertex_and_remove(
-        items: &mut Vec<RobloxMeshVertex>,
-        f: &dyn Fn(RobloxMeshVertex, RobloxMeshVertex) -> bool,
-    ) -> RobloxMeshVertex {
-        let mut g_idx = 0;
-        let mut g_item = items[g_idx].clone();
-        for (idx, vertice) in items.iter().enumerate() {
-            if f(vertice.clone(), g_item.clone()) {
-                g_item = vertice.clone();
-                g_idx = idx;
+    // picks the first vertex that beats every other one not taken yet and
+    // marks it taken, tiny meshes run out of distinct vertices and reuse the
+    // last one
+    fn take_extreme_vertex(
+        vertices: &[RobloxMeshVertex],
+        taken: &mut Vec<usize>,
+        better: impl Fn(&Vector3, &Vector3) -> bool,
+    ) -> Vector3 {
+        let mut remaining = vertices
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !taken.contains(idx));
+        let (mut best_idx, mut best) = remaining.next().expect("mesh-vertices");
+        let mut count = 1;
+
+        for (idx, vertice) in remaining {
+            count += 1;
+            if better(&vertice.position, &best.position) {
+                best = vertice;
+                best_idx = idx;
             }
         }
 
-        items.remove(g_idx);
-        g_item.clone()
+        if count > 1 {
+            taken.push(best_idx);
+        }
+        best.position
     }
 
     fn calculate_bounding_box_size(&mut self) {
-        let mut min = self.vertices[0].position;
-        let mut max = self.vertices[0].position;
-
-        for vertice in self.vertices.clone() {
-            let pos = vertice.position;
-            check_set_min!(pos.x, min.x);
-            check_set_min!(pos.y, min.y);
-            check_set_min!(pos.z, min.z);
-            check_set_max!(pos.x, max.x);
-            check_set_max!(pos.y, max.y);
-            check_set_max!(pos.z, max.z);
-        }
+        let first = vec3(self.vertices[0].position);
+        let (min, max) = self.vertices.iter().fold((first, first), |(min, max), x| {
+            let pos = vec3(x.position);
+            (min.min(pos), max.max(pos))
+        });
 
-        self.bounding_box_size = RobloxMeshBoundingBoxSize { min, max };
+        self.bounding_box_size = RobloxMeshBoundingBoxSize {
+            min: vector3(min),
+            max: vector3(max),
+        };
     }
 
     fn caculate_bounding_box(&mut self) {
-        // default the min and max to the first vertice position
-        let mut vertices = self.vertices.clone();
-
-        let min_x =
-            RobloxMesh::get_vertex_and_remove(&mut vertices, &|vertice: RobloxMeshVertex,
-                                                               prev_vertice: RobloxMeshVertex|
-             -> bool {
-                vertice.position.x < prev_vertice.position.x
-            });
-
-        let max_x =
-            RobloxMesh::get_vertex_and_remove(&mut vertices, &|vertice: RobloxMeshVertex,
-                                                               prev_vertice: RobloxMeshVertex|
-             -> bool {
-                vertice.position.x > prev_vertice.position.x
-            });
-
-        let min_z =
-            RobloxMesh::get_vertex_and_remove(&mut vertices, &|vertice: RobloxMeshVertex,
-                                                               prev_vertice: RobloxMeshVertex|
-             -> bool {
-                vertice.position.z < prev_vertice.position.z
-            });
+        // each extreme skips the vertices already claimed by the ones before
+        // it, only their indices are kept around
+        let mut taken = Vec::with_capacity(4);
+        let vertices = &self.vertices;
 
-        let max_z =
-            RobloxMesh::get_vertex_and_remove(&mut vertices, &|vertice: RobloxMeshVertex,
-                                                               prev_vertice: RobloxMeshVertex|
-             -> bool {
-                vertice.position.z > prev_vertice.position.z
-            });
+        let min_x = RobloxMesh::take_extreme_vertex(vertices, &mut taken, |a, b| a.x < b.x);
+        let max_x = RobloxMesh::take_extreme_vertex(vertices, &mut taken, |a, b| a.x > b.x);
+        let min_z = RobloxMesh::take_extreme_vertex(vertices, &mut taken, |a, b| a.z < b.z);
+        let max_z = RobloxMesh::take_extreme_vertex(vertices, &mut taken, |a, b| a.z > b.z);
 
         self.bounding_box = RobloxMeshBoundingBox {
-            min_x: min_x.position,
-            max_x: max_x.position,
-            min_z: min_z.position,
-            max_z: max_z.position,
+            min_x,
+            max_x,
+            min_z,
+            max_z,
         };
     }
 
     fn calculate_hash(&mut self) {
-        let min = self.bounding_box_size.min.x
-            + self.bounding_box_size.min.y
-            + self.bounding_box_size.min.z;
-        let max = self.bounding_box_size.max.x
-            + self.bounding_box_size.max.y
-            + self.bounding_box_size.max.z;
-        self.hash = self.triangles + (min.abs() + max) as i32;
-    }
-
-    pub fn calculate_rotation(self, mesh2: &RobloxMesh) -> Vector3 {
-        // let max_a = self.bounding_box.max;
-        // let min_a = self.bounding_box.min;
-        // let min_b = mesh2.bounding_box.min;
-        // let max_b = mesh2.bounding_box.max;
-
-        // max_a.x;
-        // min_a.x;
-        // max_a.z;
-        // min_a.z;
-
-        // let alpha = max_a.sub(min_a);
-        // let alpha2 = Vector2::new(alpha.x, alpha.z).normalize();
-        // let beta = max_b.sub(min_b);
-        // let beta2 = Vector2::new(beta.x, beta.z).normalize();
-
-        // let theta = alpha.dot(beta);
-        // println!("dot: {:?}", theta);
-        // println!("alpha: {:?}, beta: {:?}", alpha, beta);
-
-        // let x_diff = max_2.x - max.x;
-        // let z_diff = max_2.z - max.z;
-        // let rot_y = (y_diff.atan2(x_diff) * 180.0 / std::f32::consts::PI) / 2.0;
-        // let rot_y = z_diff.atan2(x_diff);
+        let (volume, surface_area) =
+            measure(self.lod0_faces(), |x| self.vertices[x as usize].position);
+        self.hash = signature_hash(
+            self.triangles,
+            &self.bounding_box_size,
+            volume,
+            surface_area,
+        );
+        self.uv_hash = self
+            .vertices
+            .iter()
+            .fold(0u32, |hash, x| hash.wrapping_add(uv_hash_share(&x.uv)));
+    }
 
-        Vector3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
+    // where the mesh's bounds sit relative to its own origin
+    pub fn bounds_center(&self) -> Vector3 {
+        let min = vec3(self.bounding_box_size.min);
+        let max = vec3(self.bounding_box_size.max);
+        vector3((min + max) / 2.0)
+    }
+
+    fn calculate_oriented_bounding_box(&mut self) {
+        let positions = self.vertices.iter().map(|x| x.position);
+        self.oriented_bounding_box = OrientedBoundingBox::from_points(positions);
+    }
+
+    // how far this mesh is turned about y compared to mesh2, taken from the
+    // heading of each one's oriented bounding box
+    pub fn calculate_rotation(&self, mesh2: &RobloxMesh) -> Vector3 {
+        let mut rotation = RobloxMesh::default_vector();
+        let (obb, obb2) = (&self.oriented_bounding_box, &mesh2.oriented_bounding_box);
+        if let Some(yaw) = obb.yaw_to(obb2) {
+            rotation.y = yaw;
         }
+        rotation
     }
 
-    pub async fn from_asset_id(asset_id: String) -> Result<RobloxMesh, GenericError> {
+    // meshes without vertex colors store plain white, so two uncolored
+    // meshes always agree here
+    pub fn vertex_color_hash(&self) -> u32 {
+        self.vertices.iter().fold(0u32, |hash, x| {
+            hash.wrapping_add(hash_share(x.color as u32 as u64))
+        })
+    }
+
+    // rough in-memory footprint of the geometry, a v4 vertex is 40 bytes
+    // (position, normal, uv and color) and a face is three i32 indices
+    pub fn geometry_bytes(&self) -> usize {
+        self.vertex_bytes() + self.index_bytes()
+    }
+
+    pub fn vertex_bytes(&self) -> usize {
+        self.vertices.len() * VERTEX_SIZE
+    }
+
+    pub fn index_bytes(&self) -> usize {
+        self.faces.len() * FACE_SIZE
+    }
+
+    #[cfg(feature = "native")]
+    pub async fn from_asset_id(asset_id: String) -> Result<RobloxMesh, FixerError> {
         let asset_data = &mut download_asset(asset_id).await?;
         RobloxMesh::from_cursor(asset_data)
     }
 
-    pub fn from_cursor(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxMesh, GenericError> {
+    pub async fn from_provider(
+        provider: &dyn AssetProvider,
+        asset_id: String,
+    ) -> Result<RobloxMesh, FixerError> {
+        let asset_data = &mut provider.fetch(asset_id).await?;
+        RobloxMesh::from_cursor(asset_data)
+    }
+
+    fn write_vector3<W: Write>(writer: &mut W, vector: &Vector3) -> Result<(), FixerError> {
+        writer.write_f32::<LittleEndian>(vector.x)?;
+        writer.write_f32::<LittleEndian>(vector.y)?;
+        writer.write_f32::<LittleEndian>(vector.z)?;
+        Ok(())
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), FixerError> {
+        // counts come from the data rather than the parsed header so edited
+        // meshes stay consistent
+        writer.write_all(b"version 4.00\n")?;
+        writer.write_i16::<LittleEndian>(24)?;
+        writer.write_u16::<LittleEndian>(self.header.num_meshes)?;
+        writer.write_i32::<LittleEndian>(self.vertices.len() as i32)?;
+        writer.write_i32::<LittleEndian>(self.faces.len() as i32)?;
+        writer.write_u16::<LittleEndian>(self.lods.len() as u16)?;
+        writer.write_u16::<LittleEndian>(self.bones.len() as u16)?;
+        writer.write_i32::<LittleEndian>(self.name_table.len() as i32)?;
+        writer.write_u16::<LittleEndian>(self.skin_subsets.len() as u16)?;
+        writer.write_u16::<LittleEndian>(self.header.stub)?;
+
+        for vert in self.vertices.iter() {
+            RobloxMesh::write_vector3(writer, &vert.position)?;
+            RobloxMesh::write_vector3(writer, &vert.normal)?;
+            RobloxMesh::write_vector3(writer, &vert.uv)?;
+            writer.write_i32::<LittleEndian>(vert.color)?;
+        }
+
+        if !self.bones.is_empty() {
+            for vert in self.vertices.iter() {
+                writer.write_all(&vert.weights.bones)?;
+                writer.write_all(&vert.weights.weights)?;
+            }
+        }
+
+        for face in self.faces.iter() {
+            for index in face.iter() {
+                writer.write_i32::<LittleEndian>(*index)?;
+            }
+        }
+
+        for lod in self.lods.iter() {
+            writer.write_i32::<LittleEndian>(*lod)?;
+        }
+
+        for bone in self.bones.iter() {
+            let m = bone.cframe.orientation;
+            writer.write_u32::<LittleEndian>(bone.name_index)?;
+            writer.write_u16::<LittleEndian>(bone.parent_index)?;
+            writer.write_u16::<LittleEndian>(bone.lod_parent_index)?;
+            writer.write_f32::<LittleEndian>(bone.culling)?;
+            RobloxMesh::write_vector3(writer, &m.x)?;
+            RobloxMesh::write_vector3(writer, &m.y)?;
+            RobloxMesh::write_vector3(writer, &m.z)?;
+            RobloxMesh::write_vector3(writer, &bone.cframe.position)?;
+        }
+
+        writer.write_all(&self.name_table)?;
+
+        for subset in self.skin_subsets.iter() {
+            writer.write_u32::<LittleEndian>(subset.faces_begin)?;
+            writer.write_u32::<LittleEndian>(subset.faces_length)?;
+            writer.write_u32::<LittleEndian>(subset.verts_begin)?;
+            writer.write_u32::<LittleEndian>(subset.verts_length)?;
+            writer.write_u32::<LittleEndian>(subset.num_bone_indices)?;
+            for index in subset.bone_indices.iter() {
+                writer.write_u16::<LittleEndian>(*index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FixerError> {
+        let mut data = Vec::<u8>::new();
+        self.to_writer(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn from_cursor(cursor: &mut Cursor<Vec<u8>>) -> Result<RobloxMesh, FixerError> {
         let header = RobloxMesh::read_header(cursor)?;
         let mut mesh = RobloxMesh {
             header: header.clone(),
             vertices: RobloxMesh::read_verts(&header, cursor)?,
             faces: RobloxMesh::read_faces(&header, cursor)?,
             lods: RobloxMesh::read_lods(&header, cursor)?,
+            bones: RobloxMesh::read_bones(&header, cursor)?,
+            name_table: RobloxMesh::read_name_table(&header, cursor)?,
+            skin_subsets: RobloxMesh::read_skin_subsets(&header, cursor)?,
 
             // custom fields
             hash: 0,
+            uv_hash: 0,
             triangles: 0,
             rotation: RobloxMesh::default_vector(),
             bounding_box: RobloxMeshBoundingBox {
@@ -342,17 +864,396 @@ impl RobloxMesh {
                 min: RobloxMesh::default_vector(),
                 max: RobloxMesh::default_vector(),
             },
+            oriented_bounding_box: OrientedBoundingBox::default(),
         };
 
-        if mesh.lods.len() > 1 {
-            mesh.triangles = mesh.lods[1] - mesh.lods[0];
+        if mesh.vertices.is_empty() {
+            return Err(MeshError::InvalidCount {
+                section: "vertices",
+                count: 0,
+            }
+            .into());
+        }
+        for (face, indices) in mesh.faces.iter().enumerate() {
+            for index in indices.iter() {
+                if *index < 0 || *index as usize >= mesh.vertices.len() {
+                    return Err(MeshError::InvalidIndex {
+                        section: "faces",
+                        entry: face,
+                        index: *index,
+                        limit: mesh.vertices.len(),
+                    }
+                    .into());
+                }
+            }
         }
 
-        mesh.calculate_bounding_box_size();
-        mesh.caculate_bounding_box();
-        mesh.calculate_hash();
+        validate_lods(&mesh.lods, mesh.faces.len())?;
 
+        mesh.resolve_bone_names();
+        mesh.recalculate();
         Ok(mesh)
     }
+
+    // reads just enough of a mesh to know its hash. only positions are kept,
+    // faces are measured as they stream past and normals, uvs and colors are
+    // folded into the hashes. the lod table sits after the faces, so it is
+    // read first by seeking ahead. bones, the name table and skin data don't
+    // feed the signature and are left unread
+    pub fn read_signature<R: Read + Seek>(reader: &mut R) -> Result<MeshSignature, FixerError> {
+        let start = reader.stream_position()?;
+        let length = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        ensure_within(start, length, "header", 1, 13 + 24)?;
+        let header = RobloxMesh::parse_header(reader)?;
+        if header.num_verts <= 0 {
+            return Err(MeshError::InvalidCount {
+                section: "vertices",
+                count: header.num_verts as i64,
+            }
+            .into());
+        }
+        let vertex_size = if header.num_bones > 0 {
+            VERTEX_SIZE + ENVELOPE_SIZE
+        } else {
+            VERTEX_SIZE
+        };
+        let num_verts = ensure_within(
+            reader.stream_position()?,
+            length,
+            "vertices",
+            header.num_verts as i64,
+            vertex_size,
+        )?;
+
+        let mut extremes = [
+            ExtremeCandidates::new(|a, b| a.x < b.x),
+            ExtremeCandidates::new(|a, b| a.x > b.x),
+            ExtremeCandidates::new(|a, b| a.z < b.z),
+            ExtremeCandidates::new(|a, b| a.z > b.z),
+        ];
+        let mut min = Vec3::ZERO;
+        let mut max = Vec3::ZERO;
+        let mut uv_hash = 0u32;
+        let mut normal = [0u8; 12];
+        let mut color = [0u8; 4];
+        let mut positions = Vec::<Vector3>::with_capacity(num_verts.min(1 << 20));
+        for idx in 0..num_verts {
+            let pos = RobloxMesh::read_vector3(reader)?;
+            positions.push(pos);
+            reader.read_exact(&mut normal)?;
+            let uv = RobloxMesh::read_vector3(reader)?;
+            reader.read_exact(&mut color)?;
+            uv_hash = uv_hash.wrapping_add(uv_hash_share(&uv));
+
+            if idx == 0 {
+                min = vec3(pos);
+                max = vec3(pos);
+            }
+            min = min.min(vec3(pos));
+            max = max.max(vec3(pos));
+            for candidates in extremes.iter_mut() {
+                candidates.offer(idx, pos);
+            }
+        }
+
+        if header.num_bones > 0 {
+            let mut envelope = [0u8; ENVELOPE_SIZE];
+            for _ in 0..num_verts {
+                reader.read_exact(&mut envelope)?;
+            }
+        }
+
+        let faces_start = reader.stream_position()?;
+        let num_faces = ensure_within(
+            faces_start,
+            length,
+            "faces",
+            header.num_faces as i64,
+            FACE_SIZE,
+        )?;
+        let lods_start = reader.seek(SeekFrom::Current((num_faces * FACE_SIZE) as i64))?;
+        let num_lods = ensure_within(lods_start, length, "lods", header.num_lods as i64, LOD_SIZE)?;
+        let mut lods = Vec::<i32>::with_capacity(num_lods);
+        for _ in 0..num_lods {
+            lods.push(reader.read_i32::<LittleEndian>()?);
+        }
+        validate_lods(&lods, num_faces)?;
+        let triangles = if lods.len() > 1 {
+            lod0_range(&lods, num_faces).len() as i32
+        } else {
+            0
+        };
+
+        reader.seek(SeekFrom::Start(faces_start))?;
+        let lod0 = lod0_range(&lods, num_faces);
+        let mut measure = Measure::default();
+        for face in 0..num_faces {
+            let mut indices = [0i32; 3];
+            for index in indices.iter_mut() {
+                *index = reader.read_i32::<LittleEndian>()?;
+                if *index < 0 || *index as usize >= num_verts {
+                    return Err(MeshError::InvalidIndex {
+                        section: "faces",
+                        entry: face,
+                        index: *index,
+                        limit: num_verts,
+                    }
+                    .into());
+                }
+            }
+            if lod0.contains(&face) {
+                let [a, b, c] = indices;
+                measure.add(
+                    positions[a as usize],
+                    positions[b as usize],
+                    positions[c as usize],
+                );
+            }
+        }
+
+        let mut taken = Vec::with_capacity(4);
+        let bounding_box = RobloxMeshBoundingBox {
+            min_x: extremes[0].take(&mut taken, num_verts),
+            max_x: extremes[1].take(&mut taken, num_verts),
+            min_z: extremes[2].take(&mut taken, num_verts),
+            max_z: extremes[3].take(&mut taken, num_verts),
+        };
+        let bounding_box_size = RobloxMeshBoundingBoxSize {
+            min: vector3(min),
+            max: vector3(max),
+        };
+        let (volume, surface_area) = measure.finish();
+
+        Ok(MeshSignature {
+            hash: signature_hash(triangles, &bounding_box_size, volume, surface_area),
+            volume,
+            surface_area,
+            header,
+            lods,
+            bounding_box,
+            bounding_box_size,
+            triangles,
+            uv_hash,
+        })
+    }
+
+    pub fn signature(&self) -> MeshSignature {
+        MeshSignature {
+            header: self.header.clone(),
+            lods: self.lods.clone(),
+            bounding_box: self.bounding_box.clone(),
+            bounding_box_size: self.bounding_box_size.clone(),
+            triangles: self.triangles,
+            volume: self.volume(),
+            surface_area: self.surface_area(),
+            hash: self.hash,
+            uv_hash: self.uv_hash,
+        }
+    }
+
+    pub async fn signature_from_provider(
+        provider: &dyn AssetProvider,
+        asset_id: String,
+    ) -> Result<MeshSignature, FixerError> {
+        let asset_data = &mut provider.fetch(asset_id).await?;
+        RobloxMesh::read_signature(asset_data)
+    }
+
+    // builds a single-LOD mesh from raw geometry, used by the importers
+    pub fn from_geometry(
+        vertices: Vec<RobloxMeshVertex>,
+        faces: Vec<[i32; 3]>,
+    ) -> Result<RobloxMesh, FixerError> {
+        // a single triangle is the smallest mesh, the bounding box extremes
+        // share the last vertex when there are fewer than four
+        if vertices.len() < 3 || faces.is_empty() {
+            return Err("A mesh needs at least three vertices and one face".into());
+        }
+
+        let mut mesh = RobloxMesh {
+            header: RobloxMeshHeader {
+                num_meshes: 1,
+                num_verts: vertices.len() as i32,
+                num_faces: faces.len() as i32,
+                num_lods: 2,
+                num_bones: 0,
+                num_skin_data: 0,
+                name_table_size: 0,
+                stub: 0,
+            },
+            lods: vec![0, faces.len() as i32],
+            bones: vec![],
+            name_table: vec![],
+            skin_subsets: vec![],
+            faces,
+            vertices,
+
+            // custom fields
+            hash: 0,
+            uv_hash: 0,
+            triangles: 0,
+            rotation: RobloxMesh::default_vector(),
+            bounding_box: RobloxMeshBoundingBox {
+                min_x: RobloxMesh::default_vector(),
+                max_x: RobloxMesh::default_vector(),
+                min_z: RobloxMesh::default_vector(),
+                max_z: RobloxMesh::default_vector(),
+            },
+            bounding_box_size: RobloxMeshBoundingBoxSize {
+                min: RobloxMesh::default_vector(),
+                max: RobloxMesh::default_vector(),
+            },
+            oriented_bounding_box: OrientedBoundingBox::default(),
+        };
+
+        mesh.recalculate();
+        Ok(mesh)
+    }
+
+    pub fn lod0_faces(&self) -> &[[i32; 3]] {
+        &self.faces[lod0_range(&self.lods, self.faces.len())]
+    }
+
+    // keeps only the full detail faces, returns how many faces were dropped
+    pub fn strip_lods(&mut self) -> usize {
+        if self.lods.len() <= 2 {
+            return 0;
+        }
+
+        let before = self.faces.len();
+        self.faces = self.lod0_faces().to_vec();
+        self.lods = vec![0, self.faces.len() as i32];
+        self.recalculate();
+
+        before - self.faces.len()
+    }
+
+    // refreshes the derived fields after the geometry changed
+    pub fn recalculate(&mut self) {
+        self.header.num_verts = self.vertices.len() as i32;
+        self.header.num_faces = self.faces.len() as i32;
+        self.header.num_lods = self.lods.len() as u16;
+        self.header.num_bones = self.bones.len() as u16;
+        self.header.name_table_size = self.name_table.len() as i32;
+        self.header.num_skin_data = self.skin_subsets.len() as u16;
+
+        if self.lods.len() > 1 {
+            self.triangles = lod0_range(&self.lods, self.faces.len()).len() as i32;
+        }
+
+        self.calculate_bounding_box_size();
+        self.caculate_bounding_box();
+        self.calculate_oriented_bounding_box();
+        self.calculate_hash();
+    }
 }
 //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, z: f32) -> RobloxMeshVertex {
+        RobloxMeshVertex {
+            position: Vector3::new(x, y, z),
+            normal: Vector3::new(0.0, 1.0, 0.0),
+            uv: Vector3::new(x * 0.5, z * 0.5, 0.0),
+            color: -1,
+            weights: RobloxBoneWeights {
+                bones: [0; 4],
+                weights: [0; 4],
+            },
+        }
+    }
+
+    // a unit cube, 8 vertices and 12 outward facing triangles
+    fn cube() -> RobloxMesh {
+        let mut vertices = Vec::new();
+        for &x in [0.0, 1.0].iter() {
+            for &y in [0.0, 1.0].iter() {
+                for &z in [0.0, 1.0].iter() {
+                    vertices.push(vertex(x, y, z));
+                }
+            }
+        }
+        let faces = vec![
+            [0, 1, 3],
+            [0, 3, 2],
+            [4, 6, 7],
+            [4, 7, 5],
+            [0, 4, 5],
+            [0, 5, 1],
+            [2, 3, 7],
+            [2, 7, 6],
+            [0, 2, 6],
+            [0, 6, 4],
+            [1, 5, 7],
+            [1, 7, 3],
+        ];
+        RobloxMesh::from_geometry(vertices, faces).expect("cube")
+    }
+
+    // the streamed signature decides clusters for meshes that are never
+    // parsed, so it has to agree with the full parse exactly
+    fn assert_same_signature(mesh: &RobloxMesh) {
+        let data = mesh.to_bytes().expect("mesh-bytes");
+        let parsed = RobloxMesh::from_cursor(&mut Cursor::new(data.clone())).expect("from-cursor");
+        let signature = RobloxMesh::read_signature(&mut Cursor::new(data)).expect("read-signature");
+        let expected = parsed.signature();
+        assert_eq!(signature.hash, expected.hash);
+        assert_eq!(signature.hash, parsed.hash);
+        assert_eq!(signature.uv_hash, expected.uv_hash);
+        assert_eq!(signature.triangles, expected.triangles);
+        assert_eq!(signature.volume, expected.volume);
+        assert_eq!(signature.surface_area, expected.surface_area);
+        assert_eq!(signature.lods, expected.lods);
+    }
+
+    #[test]
+    fn signature_matches_full_parse() {
+        assert_same_signature(&cube());
+    }
+
+    #[test]
+    fn signature_matches_full_parse_without_lods() {
+        let mut mesh = cube();
+        mesh.lods.clear();
+        mesh.recalculate();
+        assert_same_signature(&mesh);
+    }
+
+    #[test]
+    fn signature_matches_full_parse_with_lods() {
+        // a second lod reusing half of the faces, only the first counts
+        let mut mesh = cube();
+        let lod1 = mesh.faces[..6].to_vec();
+        mesh.faces.extend(lod1);
+        mesh.lods = vec![0, 12, 18];
+        mesh.recalculate();
+        assert_same_signature(&mesh);
+        assert_eq!(mesh.triangles, 12);
+    }
+
+    #[test]
+    fn truncated_input_fails_the_same_in_both_readers() {
+        let data = cube().to_bytes().expect("cube-bytes");
+        // header, vertices, faces and lods end at 37, 357, 501 and 509
+        for cut in [20, 137, 407, 505].iter() {
+            let truncated = data[..*cut].to_vec();
+            let parsed = RobloxMesh::from_cursor(&mut Cursor::new(truncated.clone()));
+            let signature = RobloxMesh::read_signature(&mut Cursor::new(truncated));
+            match (parsed, signature) {
+                (Err(parsed), Err(signature)) => {
+                    assert!(matches!(
+                        signature,
+                        FixerError::Mesh(MeshError::Truncated { .. })
+                    ));
+                    assert_eq!(parsed.to_string(), signature.to_string());
+                }
+                _ => panic!("a mesh cut at {} bytes was read", cut),
+            }
+        }
+    }
+}
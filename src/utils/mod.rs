@@ -1,10 +1,67 @@
-use std::error::Error;
+use md5::{Digest, Md5};
+use rbx_dom_weak::{types::Ref, WeakDom};
 
+#[cfg(feature = "native")]
 pub mod asset_downloader;
+pub mod asset_lists;
+pub mod asset_provider;
+pub mod atomic_file;
+pub mod attributes;
+pub mod blob_store;
+#[cfg(feature = "native")]
+pub mod cache_lock;
+#[cfg(feature = "native")]
+pub mod canonical;
 pub mod cframe;
+pub mod cleanup;
+pub mod confidence;
+pub mod content_url;
+pub mod convex_hull;
+pub mod error;
+pub mod fidelity;
+pub mod gltf;
+pub mod html_report;
+pub mod joints;
+pub mod journal;
+pub mod luau;
+pub mod mapping;
+pub mod math;
+pub mod mesh_content;
 pub mod mesh_reader;
+pub mod obb;
+pub mod obj_reader;
+pub mod obj_writer;
+#[cfg(feature = "native")]
+pub mod open_cloud;
+pub mod path_filter;
+#[cfg(feature = "native")]
+pub mod progress;
+#[cfg(feature = "native")]
+pub mod rate_limit;
+pub mod reflection;
+#[cfg(feature = "native")]
+pub mod remote_cache;
+pub mod report;
+pub mod sanitize;
+#[cfg(feature = "native")]
+pub mod shutdown;
+pub mod signature_cache;
+#[cfg(feature = "native")]
+pub mod signature_db;
+pub mod similarity;
+pub mod simplify;
+pub mod surface_appearance;
+pub mod tags;
+pub mod texture_header;
+#[cfg(feature = "native")]
+pub mod thumbnails;
+pub mod timings;
+pub mod unavailable;
+pub mod union_operation;
+pub mod verify;
+
+pub use error::FixerError;
 
-pub type GenericError = Box<dyn Error + 'static>;
 type TupleComponent = (
     f32,
     f32,
@@ -23,3 +80,29 @@ type TupleComponent = (
     f32,
     f32,
 );
+
+// std's hasher may change between releases, anything written to disk or
+// compared across machines is keyed by this instead
+pub fn digest_bytes(data: &[u8]) -> u128 {
+    let mut digest = [0u8; 16];
+    digest.copy_from_slice(&Md5::digest(data));
+    u128::from_be_bytes(digest)
+}
+
+pub fn instance_path(dom: &WeakDom, referent: Ref) -> String {
+    let mut names = Vec::<&str>::new();
+    let mut current = dom.get_by_ref(referent);
+
+    while let Some(instance) = current {
+        // the DataModel itself is left out so paths start at the service
+        if instance.parent().is_none() {
+            break;
+        }
+
+        names.push(&instance.name);
+        current = dom.get_by_ref(instance.parent());
+    }
+
+    names.reverse();
+    names.join("/")
+}
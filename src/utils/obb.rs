@@ -0,0 +1,185 @@
+use glam::{DMat3, DVec3};
+use rbx_types::Vector3;
+use std::f64::consts::PI;
+
+use super::math::{dvec3, dvector3};
+
+// relative gap between the two horizontal spreads below which the mesh is
+// too round to have a heading
+const MIN_AXIS_SEPARATION: f64 = 0.05;
+// a mesh that is balanced along its main axis could be facing either way
+const MIN_SKEWNESS: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedBoundingBox {
+    pub center: Vector3,
+    // unit axes, the one the vertices spread furthest along first
+    pub axes: [Vector3; 3],
+    pub half_size: Vector3,
+    // the yaw of the main horizontal axis, measured the way CFrame.Angles
+    // turns about y. the axis is pointed towards the side holding more of
+    // the mesh so a copy turned around by 180 degrees is told apart. meshes
+    // that are too round or too balanced to have a heading get none
+    pub heading: Option<f32>,
+}
+
+// points are passed as an iterator that gets walked a few times, so mesh
+// vertices never have to be copied into a list of their own
+fn mean(points: impl Iterator<Item = Vector3>) -> (DVec3, usize) {
+    let (sum, count) = points.fold((DVec3::ZERO, 0), |(sum, count), x| {
+        (sum + dvec3(x), count + 1)
+    });
+    (sum / count.max(1) as f64, count)
+}
+
+fn covariance(points: impl Iterator<Item = Vector3>, mean: DVec3, count: usize) -> DMat3 {
+    let sum = points.fold(DMat3::ZERO, |sum, x| {
+        let d = dvec3(x) - mean;
+        sum + DMat3::from_cols(d * d.x, d * d.y, d * d.z)
+    });
+    sum.mul_scalar(1.0 / count.max(1) as f64)
+}
+
+// cyclic jacobi rotations, a 3x3 covariance converges in a handful of
+// sweeps. glam has no eigen solver, this is the one piece done by hand
+fn symmetric_eigen(matrix: DMat3) -> ([f64; 3], [DVec3; 3]) {
+    let mut a = matrix.to_cols_array_2d();
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..32 {
+        let off = a[0][1] * a[0][1] + a[0][2] * a[0][2] + a[1][2] * a[1][2];
+        if off < 1e-24 {
+            break;
+        }
+
+        for &(p, q) in [(0, 1), (0, 2), (1, 2)].iter() {
+            if a[p][q].abs() < 1e-30 {
+                continue;
+            }
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            for row in a.iter_mut() {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            for (k, (pk, qk)) in row_p.iter().zip(row_q.iter()).enumerate() {
+                a[p][k] = c * pk - s * qk;
+                a[q][k] = s * pk + c * qk;
+            }
+            for row in v.iter_mut() {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+        }
+    }
+
+    let vector = |i: usize| DVec3::new(v[0][i], v[1][i], v[2][i]);
+    (
+        [a[0][0], a[1][1], a[2][2]],
+        [vector(0), vector(1), vector(2)],
+    )
+}
+
+impl Default for OrientedBoundingBox {
+    fn default() -> Self {
+        OrientedBoundingBox {
+            center: Vector3::new(0.0, 0.0, 0.0),
+            axes: [
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+                Vector3::new(0.0, 0.0, 1.0),
+            ],
+            half_size: Vector3::new(0.0, 0.0, 0.0),
+            heading: None,
+        }
+    }
+}
+
+impl OrientedBoundingBox {
+    // principal axes of the vertex cloud, the box is then fitted tight
+    // around the points along them
+    pub fn from_points<I>(points: I) -> Self
+    where
+        I: Iterator<Item = Vector3> + Clone,
+    {
+        let (mean, count) = mean(points.clone());
+        let (values, vectors) = symmetric_eigen(covariance(points.clone(), mean, count));
+
+        let mut order = [0, 1, 2];
+        order.sort_by(|a, b| values[*b].total_cmp(&values[*a]));
+        let axes = order.map(|i| vectors[i].normalize());
+        let variances = order.map(|i| values[i]);
+
+        let mut min = DVec3::splat(f64::MAX);
+        let mut max = DVec3::splat(f64::MIN);
+        let mut skew = DVec3::ZERO;
+        for point in points {
+            let d = dvec3(point) - mean;
+            let along = DVec3::new(d.dot(axes[0]), d.dot(axes[1]), d.dot(axes[2]));
+            min = min.min(along);
+            max = max.max(along);
+            skew += along * along * along;
+        }
+
+        let offset = (min + max) / 2.0;
+        let center = mean + axes[0] * offset.x + axes[1] * offset.y + axes[2] * offset.z;
+        OrientedBoundingBox {
+            center: dvector3(center),
+            axes: axes.map(dvector3),
+            half_size: dvector3((max - min) / 2.0),
+            heading: heading(&axes, variances, skew / count.max(1) as f64),
+        }
+    }
+
+    // how far this box is turned about y compared to `other`. boxes without
+    // a clear heading are left unrotated
+    pub fn yaw_to(&self, other: &OrientedBoundingBox) -> Option<f32> {
+        let yaw = wrap_angle(self.heading? as f64 - other.heading? as f64) as f32;
+        match yaw.abs() > 1e-3 {
+            true => Some(yaw),
+            false => None,
+        }
+    }
+}
+
+// the mesh turns about whichever axis points up the most, the heading comes
+// from the wider of the other two
+fn heading(axes: &[DVec3; 3], variances: [f64; 3], skew: DVec3) -> Option<f32> {
+    let up = (0..3)
+        .max_by(|a, b| axes[*a].y.abs().total_cmp(&axes[*b].y.abs()))
+        .expect("obb-axes");
+    let mut horizontal = (0..3).filter(|x| *x != up);
+    let main = horizontal.next().expect("obb-axes");
+    let minor = horizontal.next().expect("obb-axes");
+
+    let total = variances[main] + variances[minor];
+    if total <= 0.0 || (variances[main] - variances[minor]) / total < MIN_AXIS_SEPARATION {
+        return None;
+    }
+    let skewness = skew[main] / variances[main].powf(1.5);
+    if skewness.abs() < MIN_SKEWNESS {
+        return None;
+    }
+
+    let direction = match skewness < 0.0 {
+        true => -axes[main],
+        false => axes[main],
+    };
+    let angle = direction.z.atan2(direction.x);
+    Some(wrap_angle(-angle) as f32)
+}
+
+// into (-pi, pi]
+pub fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
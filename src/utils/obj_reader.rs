@@ -0,0 +1,153 @@
+use rbx_types::Vector3;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use super::{
+    math::{vec3, vector3},
+    mesh_reader::{RobloxBoneWeights, RobloxMesh, RobloxMeshVertex},
+    FixerError,
+};
+
+// opaque white, vertex colors tint the texture so this leaves it untouched
+const DEFAULT_COLOR: i32 = -1;
+
+#[derive(Default)]
+struct ObjData {
+    positions: Vec<Vector3>,
+    normals: Vec<Vector3>,
+    uvs: Vec<Vector3>,
+}
+
+fn parse_floats(parts: &[&str], line: usize) -> Result<Vector3, FixerError> {
+    let mut values = [0.0f32; 3];
+    for (i, part) in parts.iter().take(3).enumerate() {
+        values[i] = part
+            .parse()
+            .map_err(|_| format!("line {}: invalid number {:?}", line, part))?;
+    }
+
+    Ok(Vector3::new(values[0], values[1], values[2]))
+}
+
+// obj indices are 1-based and negative values count back from the end
+fn resolve_index(index: &str, len: usize, line: usize) -> Result<Option<usize>, FixerError> {
+    if index.is_empty() {
+        return Ok(None);
+    }
+
+    let index: i64 = index
+        .parse()
+        .map_err(|_| format!("line {}: invalid index {:?}", line, index))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!("line {}: index {} is out of range", line, index).into());
+    }
+    Ok(Some(resolved as usize))
+}
+
+pub fn read_obj<R: BufRead>(reader: R) -> Result<RobloxMesh, FixerError> {
+    let mut data = ObjData::default();
+    let mut vertices = Vec::<RobloxMeshVertex>::new();
+    let mut faces = Vec::<[i32; 3]>::new();
+    let mut needs_normal = Vec::<bool>::new();
+    let mut corners = HashMap::<(usize, Option<usize>, Option<usize>), i32>::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let parts = line.split_whitespace().collect::<Vec<&str>>();
+
+        match parts.first() {
+            Some(&"v") => data.positions.push(parse_floats(&parts[1..], number)?),
+            Some(&"vn") => data.normals.push(parse_floats(&parts[1..], number)?),
+            Some(&"vt") => {
+                // obj puts the uv origin at the bottom left, roblox at the top
+                let uv = parse_floats(&parts[1..], number)?;
+                data.uvs.push(Vector3::new(uv.x, 1.0 - uv.y, 0.0));
+            }
+            Some(&"f") => {
+                let mut polygon = Vec::<i32>::new();
+                for corner in parts[1..].iter() {
+                    let mut indices = corner.split('/');
+                    let position =
+                        resolve_index(indices.next().unwrap_or(""), data.positions.len(), number)?
+                            .ok_or_else(|| {
+                                format!("line {}: face is missing a position", number)
+                            })?;
+                    let uv = resolve_index(indices.next().unwrap_or(""), data.uvs.len(), number)?;
+                    let normal =
+                        resolve_index(indices.next().unwrap_or(""), data.normals.len(), number)?;
+
+                    // roblox vertices carry their own normal and uv, so every
+                    // distinct combination becomes its own vertex
+                    let index = *corners.entry((position, uv, normal)).or_insert_with(|| {
+                        vertices.push(RobloxMeshVertex {
+                            position: data.positions[position],
+                            normal: normal
+                                .map(|x| data.normals[x])
+                                .unwrap_or_else(|| Vector3::new(0.0, 0.0, 0.0)),
+                            uv: uv
+                                .map(|x| data.uvs[x])
+                                .unwrap_or_else(|| Vector3::new(0.0, 0.0, 0.0)),
+                            color: DEFAULT_COLOR,
+                            weights: RobloxBoneWeights {
+                                bones: [0; 4],
+                                weights: [0; 4],
+                            },
+                        });
+                        needs_normal.push(normal.is_none());
+                        vertices.len() as i32 - 1
+                    });
+                    polygon.push(index);
+                }
+
+                if polygon.len() < 3 {
+                    return Err(format!("line {}: face has fewer than 3 corners", number).into());
+                }
+
+                // polygons are fanned out into triangles
+                for i in 1..polygon.len() - 1 {
+                    faces.push([polygon[0], polygon[i], polygon[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // vertices without a normal get the average of the faces touching them
+    if needs_normal.iter().any(|x| *x) {
+        for face in faces.iter() {
+            let a = vec3(vertices[face[0] as usize].position);
+            let b = vec3(vertices[face[1] as usize].position);
+            let c = vec3(vertices[face[2] as usize].position);
+            let normal = (b - a).cross(c - a);
+
+            for index in face.iter() {
+                let index = *index as usize;
+                if needs_normal[index] {
+                    vertices[index].normal = vector3(vec3(vertices[index].normal) + normal);
+                }
+            }
+        }
+
+        for (index, vertex) in vertices.iter_mut().enumerate() {
+            if needs_normal[index] {
+                vertex.normal = vector3(vec3(vertex.normal).normalize_or_zero());
+            }
+        }
+    }
+
+    RobloxMesh::from_geometry(vertices, faces)
+}
+
+pub fn read_obj_file(path: &str) -> Result<RobloxMesh, FixerError> {
+    read_obj(BufReader::new(File::open(path)?))
+}
@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use super::{atomic_file::AtomicFile, mesh_reader::RobloxMesh, FixerError};
+
+// only the full detail lod is written, obj has no notion of the others, and
+// every vertex keeps its own position, uv and normal so indices line up
+pub fn write_obj<W: Write>(
+    mesh: &RobloxMesh,
+    name: &str,
+    writer: &mut W,
+) -> Result<(), FixerError> {
+    writeln!(writer, "o {}", name)?;
+    for vertex in mesh.vertices.iter() {
+        let position = vertex.position;
+        writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+    }
+    for vertex in mesh.vertices.iter() {
+        // roblox puts the uv origin at the top left, obj at the bottom
+        writeln!(writer, "vt {} {}", vertex.uv.x, 1.0 - vertex.uv.y)?;
+    }
+    for vertex in mesh.vertices.iter() {
+        let normal = vertex.normal;
+        writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+    }
+
+    for face in mesh.lod0_faces() {
+        let [a, b, c] = [face[0] + 1, face[1] + 1, face[2] + 1];
+        writeln!(writer, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c)?;
+    }
+    Ok(())
+}
+
+pub fn export_obj(mesh: &RobloxMesh, name: &str, output_path: &str) -> Result<(), FixerError> {
+    let mut file = AtomicFile::create(output_path)?;
+    write_obj(mesh, name, &mut file)?;
+    file.commit()
+}
@@ -0,0 +1,179 @@
+use reqwest::multipart;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{asset_downloader::AssetDownloader, FixerError};
+
+const ASSETS_URL: &str = "https://apis.roblox.com/assets/v1";
+const UNIVERSES_URL: &str = "https://apis.roblox.com/universes/v1";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Creator {
+    User(u64),
+    Group(u64),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OperationResponse {
+    asset_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Operation {
+    path: String,
+    #[serde(default)]
+    done: bool,
+    response: Option<OperationResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishResponse {
+    version_number: u64,
+}
+
+// requests go through the downloader, so uploads share its client, rate
+// limiter and deadline
+pub struct OpenCloudClient {
+    downloader: AssetDownloader,
+    api_key: String,
+    creator: Option<Creator>,
+}
+
+impl OpenCloudClient {
+    pub fn new(api_key: String, downloader: AssetDownloader) -> Self {
+        OpenCloudClient {
+            downloader,
+            api_key,
+            creator: None,
+        }
+    }
+
+    pub fn with_creator(mut self, creator: Creator) -> Self {
+        self.creator = Some(creator);
+        self
+    }
+
+    fn creation_context(&self) -> Result<serde_json::Value, FixerError> {
+        match self.creator {
+            Some(Creator::User(id)) => Ok(json!({ "creator": { "userId": id.to_string() } })),
+            Some(Creator::Group(id)) => Ok(json!({ "creator": { "groupId": id.to_string() } })),
+            None => Err("Uploading assets requires a user or group creator".into()),
+        }
+    }
+
+    async fn get_operation(&self, path: &str) -> Result<Operation, FixerError> {
+        let url = format!("{}/{}", ASSETS_URL, path);
+        let response = self
+            .downloader
+            .send(|client| client.get(&url).header("x-api-key", &self.api_key))
+            .await?;
+        if !response.status().is_success() {
+            return Err(FixerError::http_status(
+                response.url().as_str(),
+                response.status(),
+            ));
+        }
+
+        self.downloader.read_json(response).await
+    }
+
+    // uploads run as long running operations, the asset id only shows up once
+    // moderation has picked the upload up
+    pub async fn upload_mesh(&self, name: &str, data: Vec<u8>) -> Result<String, FixerError> {
+        let request = json!({
+            "assetType": "Mesh",
+            "displayName": name,
+            "description": "Uploaded by rbxlx-mesh-fixer",
+            "creationContext": self.creation_context()?,
+        });
+        // a form is consumed by sending it, retries build a fresh one
+        let form = || {
+            multipart::Form::new()
+                .text("request", request.to_string())
+                .part(
+                    "fileContent",
+                    multipart::Part::bytes(data.clone())
+                        .file_name(format!("{}.mesh", name))
+                        .mime_str("application/octet-stream")
+                        .expect("mesh-mime"),
+                )
+        };
+
+        let url = format!("{}/assets", ASSETS_URL);
+        let response = self
+            .downloader
+            .send_upload(|client| {
+                client
+                    .post(&url)
+                    .header("x-api-key", &self.api_key)
+                    .multipart(form())
+            })
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Uploading {:?} failed: {} {}", name, status, body).into());
+        }
+
+        let mut operation = self.downloader.read_json::<Operation>(response).await?;
+        for _ in 0..30 {
+            if operation.done {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            operation = self.get_operation(&operation.path).await?;
+        }
+
+        match operation.response {
+            Some(response) if operation.done => Ok(format!("rbxassetid://{}", response.asset_id)),
+            _ => Err(format!("Upload of {:?} did not finish in time", name).into()),
+        }
+    }
+
+    pub async fn publish_place(
+        &self,
+        universe_id: u64,
+        place_id: u64,
+        place_path: &str,
+    ) -> Result<u64, FixerError> {
+        let data = tokio::fs::read(place_path).await?;
+        let content_type = if place_path.ends_with(".rbxlx") {
+            "application/xml"
+        } else {
+            "application/octet-stream"
+        };
+
+        let url = format!(
+            "{}/{}/places/{}/versions?versionType=Published",
+            UNIVERSES_URL, universe_id, place_id
+        );
+        let response = self
+            .downloader
+            .send_upload(|client| {
+                client
+                    .post(&url)
+                    .header("x-api-key", &self.api_key)
+                    .header("Content-Type", content_type)
+                    .body(data.clone())
+            })
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(
+                format!("Publishing place {} failed: {} {}", place_id, status, body).into(),
+            );
+        }
+
+        let published = self
+            .downloader
+            .read_json::<PublishResponse>(response)
+            .await?;
+        Ok(published.version_number)
+    }
+}
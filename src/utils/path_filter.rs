@@ -0,0 +1,65 @@
+// include/exclude globs over "/" separated instance paths. `*` and `?` stay
+// inside a single path segment, `**` spans any number of segments
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            match_segment(&pattern[1..], name)
+                || (!name.is_empty() && match_segment(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => match_segment(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => match_segment(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            match_segments(&pattern[1..], path)
+                || (!path.is_empty() && match_segments(pattern, &path[1..]))
+        }
+        (Some(p), Some(n)) => {
+            match_segment(p.as_bytes(), n.as_bytes()) && match_segments(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_matches('/').split('/').collect::<Vec<_>>();
+    let path = path.split('/').collect::<Vec<_>>();
+    match_segments(&pattern, &path)
+}
+
+impl PathFilter {
+    pub fn include(&mut self, pattern: &str) {
+        // `!pattern` reads more naturally in config files and shell history
+        match pattern.strip_prefix('!') {
+            Some(pattern) => self.exclude.push(pattern.to_string()),
+            None => self.include.push(pattern.to_string()),
+        }
+    }
+
+    pub fn exclude(&mut self, pattern: &str) {
+        self.exclude
+            .push(pattern.trim_start_matches('!').to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    // no include patterns means everything is included
+    pub fn matches(&self, path: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|x| glob_match(x, path));
+        included && !self.exclude.iter().any(|x| glob_match(x, path))
+    }
+}
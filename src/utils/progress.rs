@@ -0,0 +1,38 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+const PHASE_TEMPLATE: &str =
+    "{prefix:>12.bold} [{bar:40.cyan/blue}] {pos}/{len} ({eta} left) {wide_msg}";
+
+// one bar per pipeline phase, all drawn together so a long download doesn't
+// push the parse and rewrite bars off screen
+pub struct Progress {
+    multi: MultiProgress,
+    quiet: bool,
+}
+
+impl Progress {
+    pub fn new(quiet: bool) -> Self {
+        let multi = if quiet {
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+        } else {
+            MultiProgress::new()
+        };
+
+        Progress { multi, quiet }
+    }
+
+    pub fn phase(&self, name: &'static str, len: usize) -> ProgressBar {
+        if self.quiet {
+            return ProgressBar::hidden();
+        }
+
+        let bar = self.multi.add(ProgressBar::new(len as u64));
+        bar.set_style(
+            ProgressStyle::with_template(PHASE_TEMPLATE)
+                .expect("progress-template")
+                .progress_chars("=> "),
+        );
+        bar.set_prefix(name);
+        bar
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+// assetdelivery starts answering 429 somewhere past this on a single ip
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 10.0;
+
+// never throttled below this, however many 429s come back
+const MIN_REQUESTS_PER_SECOND: f64 = 0.5;
+
+// how long to back off after a 429 without a Retry-After header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+    paused_until: Option<Instant>,
+    successes: u32,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        Bucket {
+            rate,
+            tokens: rate,
+            refilled: Instant::now(),
+            paused_until: None,
+            successes: 0,
+        }
+    }
+
+    // takes a token if one is ready, otherwise says how long until one is
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(paused_until) = self.paused_until {
+            if paused_until > now {
+                return Some(paused_until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+// a token bucket per host. a 429 halves that host's rate and pauses it for
+// the Retry-After, a second's worth of successes in a row earns one request
+// per second back, up to the configured cap
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_rate: f64,
+    hosts: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        RateLimiter {
+            max_rate: requests_per_second,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn acquire(&self, host: &str) {
+        loop {
+            let wait = {
+                let mut hosts = self.hosts.lock().expect("rate-limiter");
+                let max_rate = self.max_rate;
+                hosts
+                    .entry(host.to_string())
+                    .or_insert_with(|| Bucket::new(max_rate))
+                    .take(Instant::now())
+            };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    pub fn throttled(&self, host: &str, retry_after: Option<Duration>) {
+        let mut hosts = self.hosts.lock().expect("rate-limiter");
+        let max_rate = self.max_rate;
+        let bucket = hosts
+            .entry(host.to_string())
+            .or_insert_with(|| Bucket::new(max_rate));
+        bucket.rate = (bucket.rate / 2.0).max(MIN_REQUESTS_PER_SECOND);
+        bucket.tokens = 0.0;
+        bucket.successes = 0;
+        bucket.paused_until = Some(Instant::now() + retry_after.unwrap_or(DEFAULT_RETRY_AFTER));
+        warn!(
+            "Rate limited by {}, slowing down to {:.1} requests per second",
+            host, bucket.rate
+        );
+    }
+
+    pub fn succeeded(&self, host: &str) {
+        let mut hosts = self.hosts.lock().expect("rate-limiter");
+        if let Some(bucket) = hosts.get_mut(host) {
+            if bucket.rate >= self.max_rate {
+                return;
+            }
+            bucket.successes += 1;
+            if bucket.successes as f64 >= bucket.rate {
+                bucket.rate = (bucket.rate + 1.0).min(self.max_rate);
+                bucket.successes = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use rbx_dom_weak::{types::Variant, Instance};
+
+// walks the superclass chain in the reflection database, classes it doesn't
+// know about only match themselves
+pub fn is_a(class: &str, base: &str) -> bool {
+    let database = rbx_reflection_database::get();
+    let mut current = database.classes.get(class);
+    while let Some(descriptor) = current {
+        if descriptor.name == base {
+            return true;
+        }
+        current = descriptor
+            .superclass
+            .as_ref()
+            .and_then(|x| database.classes.get(x.as_ref()));
+    }
+
+    class == base
+}
+
+// properties left at their default aren't always written out, reads fall
+// back to the class default instead of treating them as missing
+pub fn property_or_default(instance: &Instance, name: &str) -> Option<Variant> {
+    if let Some(value) = instance.properties.get(name) {
+        return Some(value.clone());
+    }
+
+    let database = rbx_reflection_database::get();
+    let mut current = database.classes.get(instance.class.as_str());
+    while let Some(descriptor) = current {
+        if let Some(value) = descriptor.default_properties.get(name) {
+            return Some(value.clone());
+        }
+        current = descriptor
+            .superclass
+            .as_ref()
+            .and_then(|x| database.classes.get(x.as_ref()));
+    }
+
+    None
+}
@@ -0,0 +1,154 @@
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use tokio::time::timeout;
+
+use super::{
+    asset_downloader::{CONNECT_TIMEOUT, READ_TIMEOUT},
+    blob_store::BlobId,
+    FixerError,
+};
+
+// a team-shared mirror of the local blob store, laid out the same way under
+// a base url: `index/<cache key>` holds the blob id, `blobs/<blob id>` the
+// bytes. any server that answers GET and PUT works, `s3://bucket/prefix` is
+// shorthand for the bucket's https endpoint. requests are not SigV4 signed,
+// so an s3 bucket has to allow the runners through its bucket policy
+#[derive(Debug, Clone)]
+pub struct RemoteCache {
+    client: Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl RemoteCache {
+    pub fn new(url: &str) -> Result<Self, FixerError> {
+        let base_url = match url.strip_prefix("s3://") {
+            Some(path) => {
+                let (bucket, prefix) = match path.split_once('/') {
+                    Some((bucket, prefix)) => (bucket, prefix),
+                    None => (path, ""),
+                };
+                if bucket.is_empty() {
+                    return Err(format!("Remote cache {:?} is missing a bucket", url).into());
+                }
+                format!("https://{}.s3.amazonaws.com/{}", bucket, prefix)
+            }
+            None if url.starts_with("http://") || url.starts_with("https://") => url.to_string(),
+            None => {
+                return Err(format!("Remote cache {:?} must be an http(s) or s3 url", url).into())
+            }
+        };
+
+        Ok(RemoteCache {
+            // a stalled mirror is given up on as quickly as roblox would be
+            client: Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("http-client"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+        })
+    }
+
+    // sent as a bearer token on every request
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, &format!("{}/{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder, path: &str) -> Result<Response, FixerError> {
+        timeout(READ_TIMEOUT, request.send())
+            .await
+            .map_err(|_| {
+                format!(
+                    "Remote cache did not answer {:?} within {:?}",
+                    path, READ_TIMEOUT
+                )
+            })?
+            .map_err(FixerError::from)
+    }
+
+    async fn get_bytes(&self, path: &str) -> Result<Option<Vec<u8>>, FixerError> {
+        let mut response = self.send(self.request(Method::GET, path), path).await?;
+        // s3 answers 403 instead of 404 for missing keys without list access
+        match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => return Ok(None),
+            status if !status.is_success() => {
+                return Err(FixerError::http_status(response.url().as_str(), status))
+            }
+            _ => {}
+        }
+
+        let mut data = Vec::new();
+        loop {
+            let chunk = timeout(READ_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Remote cache read of {:?} stalled for {:?}",
+                        path, READ_TIMEOUT
+                    )
+                })??;
+            match chunk {
+                Some(chunk) => data.extend_from_slice(&chunk),
+                None => return Ok(Some(data)),
+            }
+        }
+    }
+
+    async fn put_bytes(&self, path: &str, data: Vec<u8>) -> Result<(), FixerError> {
+        let response = self
+            .send(self.request(Method::PUT, path).body(data), path)
+            .await?;
+        if !response.status().is_success() {
+            return Err(FixerError::http_status(
+                response.url().as_str(),
+                response.status(),
+            ));
+        }
+        Ok(())
+    }
+
+    // blobs are checked against their id, a truncated or tampered upload is
+    // an error rather than a silently wrong mesh
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, FixerError> {
+        let id = match self.get_bytes(&format!("index/{}", key)).await? {
+            Some(index) => String::from_utf8_lossy(&index).to_string(),
+            None => return Ok(None),
+        };
+        let id = match BlobId::parse(&id) {
+            Some(id) => id,
+            None => return Err(format!("Remote cache index {:?} is malformed", key).into()),
+        };
+
+        let data = match self.get_bytes(&format!("blobs/{}", id)).await? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        if BlobId::of(&data) != id {
+            return Err(format!("Remote cache blob {} does not match its id", id).into());
+        }
+        Ok(Some(data))
+    }
+
+    pub async fn put(&self, key: &str, data: &[u8]) -> Result<(), FixerError> {
+        let id = BlobId::of(data);
+        self.put_bytes(&format!("blobs/{}", id), data.to_vec())
+            .await?;
+        self.put_bytes(&format!("index/{}", key), id.to_string().into_bytes())
+            .await
+    }
+}
@@ -0,0 +1,125 @@
+use std::{io::Write, path::PathBuf};
+
+use serde::Serialize;
+
+use super::{atomic_file::AtomicFile, confidence::MatchKind, html_report::write_html, FixerError};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AffectedInstance {
+    pub path: String,
+    pub original_asset: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterReport {
+    pub hash: i32,
+    pub canonical_asset: String,
+    pub replaced_assets: Vec<String>,
+    // same shape as the canonical but tinted differently, left untouched
+    pub color_mismatches: Vec<String>,
+    pub instances: Vec<AffectedInstance>,
+    pub triangles: i32,
+    pub estimated_savings: usize,
+    // --prefer-owner was set but none of the candidates belong to that owner
+    pub third_party_canonical: bool,
+    pub match_kind: MatchKind,
+    pub confidence: f32,
+    // false when the cluster fell under --min-confidence, its instances are
+    // listed but still use their own meshes
+    pub applied: bool,
+}
+
+// memory the client no longer has to stream in and keep resident once the
+// duplicates are gone, geometry is the vertex and index buffers of every
+// replaced mesh and textures the bytes of every replaced map
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MemorySavings {
+    pub vertex_bytes: usize,
+    pub index_bytes: usize,
+    pub texture_bytes: usize,
+    pub union_bytes: usize,
+}
+
+impl MemorySavings {
+    pub fn total(&self) -> usize {
+        self.vertex_bytes + self.index_bytes + self.texture_bytes + self.union_bytes
+    }
+
+    pub fn add(&mut self, other: &MemorySavings) {
+        self.vertex_bytes += other.vertex_bytes;
+        self.index_bytes += other.index_bytes;
+        self.texture_bytes += other.texture_bytes;
+        self.union_bytes += other.union_bytes;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupeReport {
+    pub clusters: Vec<ClusterReport>,
+    pub memory: MemorySavings,
+    #[serde(skip)]
+    pub thumbnail_dir: Option<PathBuf>,
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl DedupeReport {
+    // batch runs fold every place into one report, instance paths get the
+    // place file name in front so rows can be told apart
+    pub fn merge(&mut self, place: &str, other: DedupeReport) {
+        self.memory.add(&other.memory);
+        for mut cluster in other.clusters {
+            for instance in cluster.instances.iter_mut() {
+                instance.path = format!("{}:{}", place, instance.path);
+            }
+            self.clusters.push(cluster);
+        }
+    }
+
+    pub fn write(&self, output_path: &str) -> Result<(), FixerError> {
+        let mut output_file = AtomicFile::create(output_path)?;
+        if output_path.ends_with(".csv") {
+            self.write_csv(&mut output_file)?;
+        } else if output_path.ends_with(".html") {
+            write_html(self, &mut output_file)?;
+        } else {
+            serde_json::to_writer_pretty(&mut output_file, self)?;
+        }
+
+        output_file.commit()
+    }
+
+    fn write_csv<W: Write>(&self, writer: &mut W) -> Result<(), FixerError> {
+        // one row per affected instance, the cluster columns are repeated
+        writeln!(
+            writer,
+            "hash,canonical_asset,original_asset,instance_path,triangles,estimated_savings,match_kind,confidence,applied"
+        )?;
+
+        for cluster in self.clusters.iter() {
+            for instance in cluster.instances.iter() {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{},{}",
+                    cluster.hash,
+                    escape_csv(&cluster.canonical_asset),
+                    escape_csv(&instance.original_asset),
+                    escape_csv(&instance.path),
+                    cluster.triangles,
+                    cluster.estimated_savings,
+                    cluster.match_kind,
+                    cluster.confidence,
+                    cluster.applied
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use glam::{Mat3, Vec3};
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use rbx_types::{CFrame, Matrix3, Vector3};
+
+use super::{
+    cframe::CFrameExt,
+    instance_path,
+    math::{mat3, vec3, vector3},
+};
+
+// the smallest size studio lets a part have
+const MIN_PART_SIZE: f32 = 0.001;
+// rotations further than this from orthonormal get rebuilt
+const ORTHONORMAL_TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug, Clone)]
+pub struct SanitizeIssue {
+    pub path: String,
+    pub property: String,
+    pub problem: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SanitizeReport {
+    pub checked: usize,
+    pub issues: Vec<SanitizeIssue>,
+}
+
+fn is_finite_vector(v: Vector3) -> bool {
+    vec3(v).is_finite()
+}
+
+fn finite_or_zero(v: Vec3) -> Vec3 {
+    Vec3::from_array(v.to_array().map(|x| if x.is_finite() { x } else { 0.0 }))
+}
+
+// broken sizes come back as the smallest valid one, a negative axis is
+// assumed to have only lost its sign
+fn sanitize_size(size: Vector3) -> Option<(Vector3, &'static str)> {
+    let size = vec3(size);
+    let problem = if !size.is_finite() {
+        "is not finite"
+    } else if size.min_element() <= 0.0 {
+        "has a zero or negative axis"
+    } else {
+        return None;
+    };
+    let fixed = finite_or_zero(size).abs().max(Vec3::splat(MIN_PART_SIZE));
+    Some((vector3(fixed), problem))
+}
+
+fn sanitize_cframe(cframe: &CFrame) -> Option<(CFrame, &'static str)> {
+    let mut fixed = *cframe;
+    let mut problem = None;
+
+    let position = vec3(cframe.position);
+    if !position.is_finite() {
+        fixed.position = vector3(finite_or_zero(position));
+        problem = Some("has a position that is not finite");
+    }
+
+    let rotation = mat3(&cframe.orientation);
+    if !rotation.is_finite() || rotation.determinant().abs() < ORTHONORMAL_TOLERANCE {
+        fixed.orientation = Matrix3::identity();
+        problem = problem.or(Some("has a rotation that is not finite or collapsed"));
+    } else if !(rotation * rotation.transpose()).abs_diff_eq(Mat3::IDENTITY, ORTHONORMAL_TOLERANCE)
+    {
+        fixed = fixed.orthonormalize();
+        problem = problem.or(Some("has a skewed or scaled rotation"));
+    }
+
+    problem.map(|x| (fixed, x))
+}
+
+// the corrected value for a property, or None when it's fine. values there
+// is no sensible correction for are dropped so they fall back to the default
+fn sanitize_value(name: &str, value: &Variant) -> Option<(Option<Variant>, &'static str)> {
+    match value {
+        Variant::Vector3(size) if name == "Size" || name == "InitialSize" => {
+            sanitize_size(*size).map(|(x, problem)| (Some(Variant::Vector3(x)), problem))
+        }
+        Variant::CFrame(cframe) => {
+            sanitize_cframe(cframe).map(|(x, problem)| (Some(Variant::CFrame(x)), problem))
+        }
+        Variant::Vector3(v) if !is_finite_vector(*v) => Some((None, "is not finite")),
+        Variant::Float32(x) if !x.is_finite() => Some((None, "is not finite")),
+        Variant::Float64(x) if !x.is_finite() => Some((None, "is not finite")),
+        _ => None,
+    }
+}
+
+// looks for the values that make studio refuse to open a place: sizes that
+// aren't positive, NaN or infinite numbers and rotations that aren't one.
+// with `fix` set they are corrected in the dom as they're found
+pub fn sanitize_parts(dom: &mut WeakDom, parts: &[Ref], fix: bool) -> SanitizeReport {
+    let mut report = SanitizeReport::default();
+
+    for referent in parts {
+        let instance = match dom.get_by_ref(*referent) {
+            Some(instance) => instance,
+            None => continue,
+        };
+        report.checked += 1;
+
+        let mut fixes = vec![];
+        let mut names = instance.properties.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            if let Some((fixed, problem)) = sanitize_value(name, &instance.properties[name]) {
+                report.issues.push(SanitizeIssue {
+                    path: instance_path(dom, *referent),
+                    property: name.clone(),
+                    problem: problem.to_string(),
+                });
+                fixes.push((name.clone(), fixed));
+            }
+        }
+
+        if !fix {
+            continue;
+        }
+        let instance = dom.get_by_ref_mut(*referent).expect("sanitize-instance");
+        for (name, fixed) in fixes {
+            match fixed {
+                Some(value) => {
+                    instance.properties.insert(name, value);
+                }
+                None => {
+                    instance.properties.remove(&name);
+                }
+            }
+        }
+    }
+
+    report
+}
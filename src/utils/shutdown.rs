@@ -0,0 +1,32 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, warn};
+
+use crate::summary::{RunSummary, EXIT_PARTIAL};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// the first Ctrl-C asks the pipeline to wind down at the next safe point,
+// a second one gives up on that and exits straight away, still leaving a
+// summary behind when one was asked for
+pub fn install_handler(summary_path: Option<String>) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        warn!("Interrupted, finishing up. Press Ctrl-C again to exit immediately");
+        INTERRUPTED.store(true, Ordering::SeqCst);
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            if let Some(summary_path) = &summary_path {
+                if let Err(err) = RunSummary::aborted().write(summary_path) {
+                    error!("Could not write run summary {:?}: {}", summary_path, err);
+                }
+            }
+            std::process::exit(EXIT_PARTIAL);
+        }
+    });
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracing::warn;
+
+use super::{atomic_file::AtomicFile, digest_bytes, mesh_reader::MeshSignature, FixerError};
+
+// lives next to the downloaded assets it describes, moved along with them
+// when the cache dir is
+pub const DEFAULT_SIGNATURE_CACHE: &str = "cache/signatures.json";
+pub const SIGNATURE_CACHE_FILE: &str = "signatures.json";
+
+// bumped whenever the hash or what is stored here changes, older entries are
+// then recomputed instead of trusted
+const SIGNATURE_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureRecord {
+    pub version: u32,
+    pub triangles: i32,
+    pub num_verts: i32,
+    pub num_faces: i32,
+    pub bounds: [[f32; 3]; 2],
+    pub volume: f32,
+    pub surface_area: f32,
+    pub hash: i32,
+    pub uv_hash: u32,
+}
+
+impl SignatureRecord {
+    pub fn from_signature(signature: &MeshSignature) -> Self {
+        let size = &signature.bounding_box_size;
+        SignatureRecord {
+            version: SIGNATURE_VERSION,
+            triangles: signature.triangles,
+            num_verts: signature.header.num_verts,
+            num_faces: signature.header.num_faces,
+            bounds: [
+                [size.min.x, size.min.y, size.min.z],
+                [size.max.x, size.max.y, size.max.z],
+            ],
+            volume: signature.volume,
+            surface_area: signature.surface_area,
+            hash: signature.hash,
+            uv_hash: signature.uv_hash,
+        }
+    }
+}
+
+// parsed signatures keyed by a checksum of the raw asset, so a re-uploaded
+// or edited file never reuses a stale entry. welding changes the hash, so
+// welded signatures are stored under their own key
+pub struct SignatureCache {
+    path: PathBuf,
+    entries: Mutex<BTreeMap<String, SignatureRecord>>,
+    dirty: AtomicBool,
+}
+
+impl SignatureCache {
+    pub fn open<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let mut entries: BTreeMap<String, SignatureRecord> = match File::open(&path) {
+            Ok(file) => match serde_json::from_reader(BufReader::new(file)) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!("Ignoring unreadable signature cache {:?}: {}", path, err);
+                    BTreeMap::new()
+                }
+            },
+            Err(_) => BTreeMap::new(),
+        };
+        // entries from an older version can never be hit again, their keys
+        // may not even be computed the same way
+        entries.retain(|_, x| x.version == SIGNATURE_VERSION);
+
+        SignatureCache {
+            path,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn key(data: &[u8], welded: bool) -> String {
+        let suffix = if welded { "-welded" } else { "" };
+        format!("{:032x}-{}{}", digest_bytes(data), data.len(), suffix)
+    }
+
+    pub fn get(&self, key: &str) -> Option<SignatureRecord> {
+        let entries = self.entries.lock().expect("signature-cache");
+        entries
+            .get(key)
+            .filter(|x| x.version == SIGNATURE_VERSION)
+            .cloned()
+    }
+
+    pub fn insert(&self, key: String, record: SignatureRecord) {
+        let mut entries = self.entries.lock().expect("signature-cache");
+        entries.insert(key, record);
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // only rewritten when something new was added
+    pub fn save(&self) -> Result<(), FixerError> {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entries = self.entries.lock().expect("signature-cache");
+        let mut file = AtomicFile::create(&self.path)?;
+        serde_json::to_writer(&mut file, &*entries)?;
+        file.commit()
+    }
+}
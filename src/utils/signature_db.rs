@@ -0,0 +1,153 @@
+use rbx_types::Vector3;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    similarity::{FuzzyMatch, SignatureFeatures},
+    FixerError,
+};
+
+// bumped whenever cluster keys or the records change, a database written by
+// another version is cleared on open instead of handing out stale picks
+const DATABASE_VERSION: u32 = 2;
+// stored in the default tree next to the records, which all have four byte
+// keys so the two never collide
+const VERSION_KEY: &[u8] = b"version";
+
+// fuzzy signatures live in their own tree, keyed by triangle count, volume
+// cell and cluster key so a lookup is three prefix scans
+const FUZZY_TREE: &str = "fuzzy-index";
+// the cells depend on the tolerance, a run with other settings rebuilds
+const FUZZY_PARAMS: &[u8] = b"params";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FuzzyEntry {
+    key: i32,
+    scope: String,
+    features: SignatureFeatures,
+}
+
+fn fuzzy_prefix(triangles: i32, cell: i64) -> Vec<u8> {
+    let mut prefix = triangles.to_be_bytes().to_vec();
+    prefix.extend_from_slice(&cell.to_be_bytes());
+    prefix
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanonicalRecord {
+    pub asset_id: String,
+    pub init_size: [f32; 3],
+    pub size: [f32; 3],
+}
+
+impl CanonicalRecord {
+    pub fn new(asset_id: String, init_size: Vector3, size: Vector3) -> Self {
+        CanonicalRecord {
+            asset_id,
+            init_size: [init_size.x, init_size.y, init_size.z],
+            size: [size.x, size.y, size.z],
+        }
+    }
+
+    pub fn init_size(&self) -> Vector3 {
+        Vector3::new(self.init_size[0], self.init_size[1], self.init_size[2])
+    }
+
+    pub fn size(&self) -> Vector3 {
+        Vector3::new(self.size[0], self.size[1], self.size[2])
+    }
+}
+
+// maps mesh signatures to the asset every place should converge on, shared
+// between runs so a whole universe ends up with the same canonical picks
+pub struct SignatureDatabase {
+    db: sled::Db,
+}
+
+impl SignatureDatabase {
+    pub fn open(path: &str) -> Result<Self, FixerError> {
+        let db = sled::open(path)?;
+        let version = DATABASE_VERSION.to_be_bytes();
+        if db.get(VERSION_KEY)?.as_deref() != Some(&version[..]) {
+            // the fuzzy index points at cluster keys too
+            db.clear()?;
+            db.drop_tree(FUZZY_TREE)?;
+            db.insert(VERSION_KEY, &version[..])?;
+        }
+        Ok(SignatureDatabase { db })
+    }
+
+    pub fn get(&self, hash: i32) -> Result<Option<CanonicalRecord>, FixerError> {
+        match self.db.get(hash.to_be_bytes())? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert(&self, hash: i32, record: &CanonicalRecord) -> Result<(), FixerError> {
+        self.db
+            .insert(hash.to_be_bytes(), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+
+    fn fuzzy_tree(&self, fuzzy: &FuzzyMatch) -> Result<sled::Tree, FixerError> {
+        let tree = self.db.open_tree(FUZZY_TREE)?;
+        let params = serde_json::to_vec(fuzzy)?;
+        if tree.get(FUZZY_PARAMS)?.as_deref() != Some(&params[..]) {
+            tree.clear()?;
+            tree.insert(FUZZY_PARAMS, params)?;
+        }
+        Ok(tree)
+    }
+
+    // the cluster key of an earlier run's mesh that this one only drifted
+    // away from, the closest one when several are in range
+    pub fn find_fuzzy(
+        &self,
+        fuzzy: &FuzzyMatch,
+        uv_signature: bool,
+        scope: &str,
+        features: &SignatureFeatures,
+    ) -> Result<Option<i32>, FixerError> {
+        let tree = self.fuzzy_tree(fuzzy)?;
+        let cell = fuzzy.volume_cell(features.volume());
+        let mut closest = None::<(f32, i32)>;
+        for neighbour in cell - 1..=cell + 1 {
+            for item in tree.scan_prefix(fuzzy_prefix(features.triangles, neighbour)) {
+                let (_, value) = item?;
+                let entry = serde_json::from_slice::<FuzzyEntry>(&value)?;
+                if entry.scope != scope || !fuzzy.matches(&entry.features, features, uv_signature) {
+                    continue;
+                }
+                let distance = fuzzy.distance(&entry.features, features);
+                if closest.map_or(true, |(best, _)| distance < best) {
+                    closest = Some((distance, entry.key));
+                }
+            }
+        }
+        Ok(closest.map(|(_, key)| key))
+    }
+
+    pub fn index_fuzzy(
+        &self,
+        fuzzy: &FuzzyMatch,
+        key: i32,
+        scope: &str,
+        features: &SignatureFeatures,
+    ) -> Result<(), FixerError> {
+        let tree = self.fuzzy_tree(fuzzy)?;
+        let mut index_key = fuzzy_prefix(features.triangles, fuzzy.volume_cell(features.volume()));
+        index_key.extend_from_slice(&key.to_be_bytes());
+        let entry = FuzzyEntry {
+            key,
+            scope: scope.to_string(),
+            features: *features,
+        };
+        tree.insert(index_key, serde_json::to_vec(&entry)?)?;
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), FixerError> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
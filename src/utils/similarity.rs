@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::signature_cache::SignatureRecord;
+
+// how far apart two signatures are. the bounds corners, volume and surface
+// area are compared as one vector of eight values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    // the largest single difference
+    Chebyshev,
+    Euclidean,
+    // the largest difference relative to the bigger of the two values, so
+    // one tolerance works for small props and whole buildings alike
+    Relative,
+}
+
+impl Default for DistanceMetric {
+    fn default() -> Self {
+        DistanceMetric::Chebyshev
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = String;
+
+    fn from_str(metric: &str) -> Result<Self, Self::Err> {
+        match metric {
+            "chebyshev" | "max" => Ok(DistanceMetric::Chebyshev),
+            "euclidean" => Ok(DistanceMetric::Euclidean),
+            "relative" => Ok(DistanceMetric::Relative),
+            _ => Err(format!("Unknown distance metric {:?}", metric)),
+        }
+    }
+}
+
+// the parts of a signature that drift when a mesh is re-exported, next to
+// the ones that have to match exactly
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SignatureFeatures {
+    pub triangles: i32,
+    pub uv_hash: u32,
+    pub values: [f32; 8],
+}
+
+impl SignatureFeatures {
+    pub fn volume(&self) -> f32 {
+        self.values[6]
+    }
+
+    pub fn from_record(record: &SignatureRecord) -> Self {
+        let [min, max] = record.bounds;
+        SignatureFeatures {
+            triangles: record.triangles,
+            uv_hash: record.uv_hash,
+            values: [
+                min[0],
+                min[1],
+                min[2],
+                max[0],
+                max[1],
+                max[2],
+                record.volume,
+                record.surface_area,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    pub tolerance: f32,
+    pub metric: DistanceMetric,
+}
+
+impl FuzzyMatch {
+    // the furthest apart two volumes can be and still match, the larger of
+    // the two passed in. volume is one of the compared values, so no metric
+    // lets it drift further than its own tolerance
+    pub fn volume_window(&self, volume: f32) -> f32 {
+        match self.metric {
+            DistanceMetric::Chebyshev | DistanceMetric::Euclidean => self.tolerance,
+            DistanceMetric::Relative => self.tolerance * volume.abs(),
+        }
+    }
+
+    // a grid over mesh volume with cells at least one window wide, two
+    // volumes that can still match are never more than one cell apart.
+    // relative windows grow with the volume, so their grid is logarithmic
+    pub fn volume_cell(&self, volume: f32) -> i64 {
+        let volume = volume.abs() as f64;
+        let tolerance = self.tolerance as f64;
+        match self.metric {
+            DistanceMetric::Chebyshev | DistanceMetric::Euclidean => {
+                (volume / tolerance.max(f64::EPSILON)).floor() as i64
+            }
+            DistanceMetric::Relative if tolerance >= 1.0 => 0,
+            DistanceMetric::Relative => {
+                let width = -(1.0 - tolerance).ln();
+                (volume.max(f64::MIN_POSITIVE).ln() / width.max(f64::EPSILON)).floor() as i64
+            }
+        }
+    }
+
+    pub fn distance(&self, a: &SignatureFeatures, b: &SignatureFeatures) -> f32 {
+        let pairs = a.values.iter().zip(b.values.iter());
+        match self.metric {
+            DistanceMetric::Chebyshev => pairs.map(|(a, b)| (a - b).abs()).fold(0.0, f32::max),
+            DistanceMetric::Euclidean => pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt(),
+            DistanceMetric::Relative => pairs
+                .map(|(a, b)| {
+                    let scale = a.abs().max(b.abs());
+                    match scale > 0.0 {
+                        true => (a - b).abs() / scale,
+                        false => 0.0,
+                    }
+                })
+                .fold(0.0, f32::max),
+        }
+    }
+
+    // drift never changes the triangle count, a different uv unwrap only
+    // counts when uvs are part of the signature
+    pub fn matches(
+        &self,
+        a: &SignatureFeatures,
+        b: &SignatureFeatures,
+        uv_signature: bool,
+    ) -> bool {
+        if a.triangles != b.triangles || (uv_signature && a.uv_hash != b.uv_hash) {
+            return false;
+        }
+        self.distance(a, b) <= self.tolerance
+    }
+}
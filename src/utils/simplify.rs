@@ -0,0 +1,257 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, BinaryHeap},
+};
+
+use super::{
+    mesh_reader::{RobloxMesh, RobloxMeshVertex},
+    FixerError,
+};
+
+// symmetric 4x4 matrix stored as its upper triangle
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Quadric([
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ])
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        let mut sum = [0.0; 10];
+        for i in 0..10 {
+            sum[i] = self.0[i] + other.0[i];
+        }
+        Quadric(sum)
+    }
+
+    fn error(&self, x: f64, y: f64, z: f64) -> f64 {
+        let q = &self.0;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    keep: usize,
+    remove: usize,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// reversed so the binary heap pops the cheapest collapse first
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+fn position(vertex: &RobloxMeshVertex) -> (f64, f64, f64) {
+    (
+        vertex.position.x as f64,
+        vertex.position.y as f64,
+        vertex.position.z as f64,
+    )
+}
+
+// collapses an edge onto whichever endpoint has the lower quadric error, so
+// kept vertices keep their original normal and uv
+fn plan_collapse(
+    vertices: &[RobloxMeshVertex],
+    quadrics: &[Quadric],
+    versions: &[u32],
+    a: usize,
+    b: usize,
+) -> Collapse {
+    let quadric = quadrics[a].add(&quadrics[b]);
+    let (ax, ay, az) = position(&vertices[a]);
+    let (bx, by, bz) = position(&vertices[b]);
+    let cost_a = quadric.error(ax, ay, az);
+    let cost_b = quadric.error(bx, by, bz);
+
+    let (keep, remove, cost) = if cost_a <= cost_b {
+        (a, b, cost_a)
+    } else {
+        (b, a, cost_b)
+    };
+    Collapse {
+        cost,
+        keep,
+        remove,
+        versions: (versions[keep], versions[remove]),
+    }
+}
+
+// quadric error metric edge collapse (Garland & Heckbert) down to roughly
+// `target_faces` triangles. only the first LOD is simplified
+pub fn simplify(mesh: &RobloxMesh, target_faces: usize) -> Result<RobloxMesh, FixerError> {
+    let vertices = &mesh.vertices;
+    let mut faces = mesh.lod0_faces().to_vec();
+    let mut alive = vec![true; faces.len()];
+    let mut alive_count = faces.len();
+    let mut removed = vec![false; vertices.len()];
+    let mut versions = vec![0u32; vertices.len()];
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    let mut vertex_faces = vec![Vec::<usize>::new(); vertices.len()];
+
+    for (index, face) in faces.iter().enumerate() {
+        for corner in face.iter() {
+            let corner = *corner as usize;
+            if corner >= vertices.len() {
+                return Err(format!("Face {} references missing vertex {}", index, corner).into());
+            }
+            vertex_faces[corner].push(index);
+        }
+
+        let a = vertices[face[0] as usize].position;
+        let b = vertices[face[1] as usize].position;
+        let c = vertices[face[2] as usize].position;
+        let (ux, uy, uz) = ((b.x - a.x) as f64, (b.y - a.y) as f64, (b.z - a.z) as f64);
+        let (vx, vy, vz) = ((c.x - a.x) as f64, (c.y - a.y) as f64, (c.z - a.z) as f64);
+        let (nx, ny, nz) = (uy * vz - uz * vy, uz * vx - ux * vz, ux * vy - uy * vx);
+        let length = (nx * nx + ny * ny + nz * nz).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+
+        let (nx, ny, nz) = (nx / length, ny / length, nz / length);
+        let d = -(nx * a.x as f64 + ny * a.y as f64 + nz * a.z as f64);
+        let plane = Quadric::from_plane(nx, ny, nz, d);
+        for corner in face.iter() {
+            let corner = *corner as usize;
+            quadrics[corner] = quadrics[corner].add(&plane);
+        }
+    }
+
+    let mut edges = BTreeSet::<(usize, usize)>::new();
+    for face in faces.iter() {
+        for i in 0..3 {
+            let a = face[i] as usize;
+            let b = face[(i + 1) % 3] as usize;
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+
+    let mut heap = BinaryHeap::<Collapse>::new();
+    for (a, b) in edges {
+        heap.push(plan_collapse(vertices, &quadrics, &versions, a, b));
+    }
+
+    while alive_count > target_faces {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+
+        // stale entries are left in the heap and skipped here
+        if removed[collapse.keep]
+            || removed[collapse.remove]
+            || versions[collapse.keep] != collapse.versions.0
+            || versions[collapse.remove] != collapse.versions.1
+        {
+            continue;
+        }
+
+        let keep = collapse.keep;
+        let remove = collapse.remove;
+        removed[remove] = true;
+        versions[keep] += 1;
+        quadrics[keep] = quadrics[keep].add(&quadrics[remove]);
+
+        let moved = std::mem::take(&mut vertex_faces[remove]);
+        for face_index in moved {
+            if !alive[face_index] {
+                continue;
+            }
+
+            let face = &mut faces[face_index];
+            for corner in face.iter_mut() {
+                if *corner as usize == remove {
+                    *corner = keep as i32;
+                }
+            }
+
+            if face[0] == face[1] || face[1] == face[2] || face[0] == face[2] {
+                alive[face_index] = false;
+                alive_count -= 1;
+            } else {
+                vertex_faces[keep].push(face_index);
+            }
+        }
+
+        let mut neighbours = BTreeSet::<usize>::new();
+        for face_index in vertex_faces[keep].iter() {
+            if alive[*face_index] {
+                for corner in faces[*face_index].iter() {
+                    if *corner as usize != keep {
+                        neighbours.insert(*corner as usize);
+                    }
+                }
+            }
+        }
+        for neighbour in neighbours {
+            heap.push(plan_collapse(
+                vertices, &quadrics, &versions, keep, neighbour,
+            ));
+        }
+    }
+
+    // compact the surviving vertices and faces into fresh buffers
+    let mut remap = vec![-1i32; vertices.len()];
+    let mut new_vertices = Vec::<RobloxMeshVertex>::new();
+    let mut new_faces = Vec::<[i32; 3]>::with_capacity(alive_count);
+    for (index, face) in faces.iter().enumerate() {
+        if !alive[index] {
+            continue;
+        }
+
+        let mut new_face = [0i32; 3];
+        for (i, corner) in face.iter().enumerate() {
+            let corner = *corner as usize;
+            if remap[corner] < 0 {
+                remap[corner] = new_vertices.len() as i32;
+                new_vertices.push(vertices[corner].clone());
+            }
+            new_face[i] = remap[corner];
+        }
+        new_faces.push(new_face);
+    }
+
+    RobloxMesh::from_geometry(new_vertices, new_faces)
+}
@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use tracing::warn;
+
+use super::{
+    asset_provider::AssetProvider, content_url::normalize_content, unavailable::as_unavailable,
+    FixerError,
+};
+
+pub const SURFACE_APPEARANCE_MAPS: [&str; 4] =
+    ["ColorMap", "MetalnessMap", "NormalMap", "RoughnessMap"];
+
+#[derive(Debug, Clone, Default)]
+pub struct MapSavings {
+    pub replaced: usize,
+    pub unique_removed: usize,
+    pub bytes_saved: usize,
+}
+
+fn get_map(dom: &WeakDom, referent: Ref, map: &str) -> Option<String> {
+    match dom.get_by_ref(referent)?.properties.get(map) {
+        Some(Variant::Content(content)) => {
+            let content = content.clone().into_string();
+            if content.trim() == "" {
+                None
+            } else {
+                Some(content)
+            }
+        }
+        _ => None,
+    }
+}
+
+pub fn find_surface_appearances(dom: &WeakDom, mesh_parts: &[Ref]) -> Vec<Ref> {
+    let mut appearances = Vec::<Ref>::new();
+
+    for referent in mesh_parts {
+        if let Some(mesh_part) = dom.get_by_ref(*referent) {
+            for child in mesh_part.children() {
+                if let Some(instance) = dom.get_by_ref(*child) {
+                    if instance.class == "SurfaceAppearance" {
+                        appearances.push(*child);
+                    }
+                }
+            }
+        }
+    }
+
+    appearances
+}
+
+pub async fn dedupe_surface_appearances(
+    provider: &dyn AssetProvider,
+    dom: &mut WeakDom,
+    appearances: &[Ref],
+) -> Result<BTreeMap<&'static str, MapSavings>, FixerError> {
+    // maps are deduped by their exact bytes, so the same image uploaded
+    // under multiple ids collapses onto the first id we come across
+    let mut canonical_ids = HashMap::<u128, String>::new();
+    let mut asset_hashes = HashMap::<String, (u128, usize)>::new();
+    let mut removed_ids = HashMap::<&'static str, HashSet<String>>::new();
+    let mut savings = BTreeMap::<&'static str, MapSavings>::new();
+
+    for referent in appearances {
+        for &map in SURFACE_APPEARANCE_MAPS.iter() {
+            let asset_id = match get_map(dom, *referent, map) {
+                Some(asset_id) => asset_id,
+                None => continue,
+            };
+
+            if !asset_hashes.contains_key(&asset_id) {
+                let hash = match provider.content_hash(asset_id.clone()).await {
+                    Ok(hash) => hash,
+                    Err(err) if as_unavailable(&err).is_some() => {
+                        warn!("Skipping {} {:?}: {}", map, asset_id, err);
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+                asset_hashes.insert(asset_id.clone(), hash);
+            }
+
+            let (hash, size) = asset_hashes[&asset_id];
+            let canonical_id = canonical_ids
+                .entry(hash)
+                .or_insert_with(|| asset_id.clone())
+                .clone();
+
+            if canonical_id == asset_id {
+                continue;
+            }
+
+            let map_savings = savings.entry(map).or_insert_with(MapSavings::default);
+            map_savings.replaced += 1;
+            if removed_ids
+                .entry(map)
+                .or_insert_with(HashSet::new)
+                .insert(asset_id.clone())
+            {
+                map_savings.unique_removed += 1;
+                map_savings.bytes_saved += size;
+            }
+
+            let appearance = dom.get_by_ref_mut(*referent).expect("surface-appearance");
+            if let Some(prop) = appearance.properties.get_mut(map) {
+                *prop =
+                    Variant::Content(rbx_types::Content::from(normalize_content(&canonical_id)));
+            }
+        }
+    }
+
+    Ok(savings)
+}
@@ -0,0 +1,51 @@
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    Instance, WeakDom,
+};
+
+// CollectionService tags, a boolean attribute of the same name counts too
+// since that's easier to toggle from the properties panel
+pub fn has_tag(instance: &Instance, tag: &str) -> bool {
+    if let Some(Variant::Tags(tags)) = instance.properties.get("Tags") {
+        if tags.iter().any(|x| x == tag) {
+            return true;
+        }
+    }
+
+    match instance.properties.get("Attributes") {
+        Some(Variant::Attributes(attributes)) => {
+            matches!(attributes.get(tag), Some(Variant::Bool(true)))
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TagFilter {
+    pub required: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.skipped.is_empty()
+    }
+
+    // tags are inherited, tagging a model opts in or out everything inside it
+    pub fn matches(&self, dom: &WeakDom, referent: Ref) -> bool {
+        let mut tagged = Vec::<&str>::new();
+        let mut current = dom.get_by_ref(referent);
+        while let Some(instance) = current {
+            for tag in self.required.iter().chain(self.skipped.iter()) {
+                if has_tag(instance, tag) {
+                    tagged.push(tag);
+                }
+            }
+            current = dom.get_by_ref(instance.parent());
+        }
+
+        let required =
+            self.required.is_empty() || self.required.iter().any(|x| tagged.contains(&x.as_str()));
+        required && !self.skipped.iter().any(|x| tagged.contains(&x.as_str()))
+    }
+}
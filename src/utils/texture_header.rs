@@ -0,0 +1,113 @@
+use std::fmt;
+
+use super::FixerError;
+
+const PNG_MAGIC: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Png,
+    Jpeg,
+}
+
+impl fmt::Display for TextureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureFormat::Png => write!(f, "png"),
+            TextureFormat::Jpeg => write!(f, "jpeg"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextureHeader {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// the IHDR chunk always comes first, right after the signature
+fn read_png(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((read_u32(data, 16)?, read_u32(data, 20)?))
+}
+
+// walks the segments up to the first start of frame, which holds the size
+fn read_jpeg(data: &[u8]) -> Option<(u32, u32)> {
+    let mut offset = 2;
+    loop {
+        if *data.get(offset)? != 0xff {
+            return None;
+        }
+        let marker = *data.get(offset + 1)?;
+        match marker {
+            // fill bytes before a marker
+            0xff => {
+                offset += 1;
+                continue;
+            }
+            // markers without a length
+            0x01 | 0xd0..=0xd7 => {
+                offset += 2;
+                continue;
+            }
+            0xd9 | 0xda => return None,
+            // every SOFn except the huffman, arithmetic and jpeg-ls tables
+            0xc0..=0xcf if marker != 0xc4 && marker != 0xc8 && marker != 0xcc => {
+                let height = read_u16(data, offset + 5)?;
+                let width = read_u16(data, offset + 7)?;
+                return Some((width, height));
+            }
+            _ => offset += 2 + read_u16(data, offset + 2)? as usize,
+        }
+    }
+}
+
+impl TextureHeader {
+    // only the first few bytes are looked at, the pixels are never decoded
+    pub fn read(data: &[u8]) -> Result<Self, FixerError> {
+        let (format, size) = if data.starts_with(&PNG_MAGIC) {
+            (TextureFormat::Png, read_png(data))
+        } else if data.starts_with(&[0xff, 0xd8]) {
+            (TextureFormat::Jpeg, read_jpeg(data))
+        } else {
+            return Err("Texture is not a png or jpeg image".into());
+        };
+
+        match size {
+            Some((width, height)) if width > 0 && height > 0 => Ok(TextureHeader {
+                format,
+                width,
+                height,
+            }),
+            _ => Err(format!("Texture has a malformed {} header", format).into()),
+        }
+    }
+
+    // decoded to rgba8 on the gpu with a full mip chain, whatever the file
+    // was compressed with
+    pub fn memory_bytes(&self) -> usize {
+        let (mut width, mut height) = (self.width as usize, self.height as usize);
+        let mut bytes = 0;
+        loop {
+            bytes += width * height * 4;
+            if width == 1 && height == 1 {
+                return bytes;
+            }
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+use std::{collections::BTreeSet, path::Path};
+use tracing::{debug, warn};
+
+use super::{
+    asset_downloader::AssetDownloader, content_url::extract_assetid, html_report::thumbnail_path,
+    report::DedupeReport, FixerError,
+};
+
+const THUMBNAILS_URL: &str = "https://thumbnails.roblox.com/v1/assets";
+// the thumbnails api refuses more than 100 ids per request
+const BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Thumbnail {
+    target_id: u64,
+    state: String,
+    image_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ThumbnailResponse {
+    data: Vec<Thumbnail>,
+}
+
+// thumbnails that are still rendering or were moderated are skipped, the
+// report falls back to the web url for those
+pub async fn fetch_thumbnails(
+    downloader: &AssetDownloader,
+    report: &DedupeReport,
+    dir: &Path,
+) -> Result<usize, FixerError> {
+    let mut ids = BTreeSet::<String>::new();
+    for cluster in report.clusters.iter() {
+        let assets =
+            std::iter::once(&cluster.canonical_asset).chain(cluster.replaced_assets.iter());
+        for asset_id in assets {
+            if let Ok(id) = extract_assetid(asset_id.clone()) {
+                if !dir.join(format!("{}.png", id)).is_file() {
+                    ids.insert(id);
+                }
+            }
+        }
+    }
+
+    tokio::fs::create_dir_all(dir).await?;
+    let ids = ids.into_iter().collect::<Vec<_>>();
+    let mut fetched = 0;
+    for batch in ids.chunks(BATCH_SIZE) {
+        let query = [
+            ("assetIds", batch.join(",")),
+            ("size", String::from("420x420")),
+            ("format", String::from("Png")),
+        ];
+        let response = downloader
+            .send(|client| client.get(THUMBNAILS_URL).query(&query))
+            .await?;
+        if !response.status().is_success() {
+            return Err(FixerError::http_status(
+                response.url().as_str(),
+                response.status(),
+            ));
+        }
+
+        let thumbnails = downloader.read_json::<ThumbnailResponse>(response).await?;
+        for thumbnail in thumbnails.data {
+            let image_url = match (thumbnail.state.as_str(), thumbnail.image_url) {
+                ("Completed", Some(image_url)) => image_url,
+                (state, _) => {
+                    warn!("No thumbnail for {:?} ({})", thumbnail.target_id, state);
+                    continue;
+                }
+            };
+
+            let response = downloader.send(|client| client.get(&image_url)).await?;
+            if !response.status().is_success() {
+                warn!(
+                    "No thumbnail for {:?} ({})",
+                    thumbnail.target_id,
+                    response.status()
+                );
+                continue;
+            }
+            let data = downloader.read_body(response).await?;
+            let path = dir.join(format!("{}.png", thumbnail.target_id));
+            tokio::fs::write(&path, &data).await?;
+            debug!("Saved thumbnail {:?}", path);
+            fetched += 1;
+        }
+    }
+
+    Ok(fetched)
+}
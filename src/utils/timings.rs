@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+// wall clock time per pipeline phase, kept in the order the phases first ran
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    pub fn add(&mut self, phase: &'static str, elapsed: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((phase, elapsed)),
+        }
+    }
+
+    pub fn record(&mut self, phase: &'static str, start: Instant) {
+        self.add(phase, start.elapsed());
+    }
+
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let value = f();
+        self.record(phase, start);
+        value
+    }
+
+    pub fn merge(&mut self, other: &Timings) {
+        for (phase, elapsed) in other.phases.iter() {
+            self.add(phase, *elapsed);
+        }
+    }
+
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
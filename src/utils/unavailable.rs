@@ -0,0 +1,60 @@
+use std::{error::Error, fmt};
+
+use super::FixerError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnavailableReason {
+    Moderated,
+    Deleted,
+    Unauthorized,
+    Status(u16),
+    NotCached,
+    DeadlineExceeded,
+}
+
+impl UnavailableReason {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => UnavailableReason::Unauthorized,
+            403 => UnavailableReason::Moderated,
+            404 => UnavailableReason::Deleted,
+            _ => UnavailableReason::Status(status),
+        }
+    }
+}
+
+impl fmt::Display for UnavailableReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnavailableReason::Moderated => write!(f, "moderated or private (403)"),
+            UnavailableReason::Deleted => write!(f, "deleted or missing (404)"),
+            UnavailableReason::Unauthorized => write!(f, "requires authentication (401)"),
+            UnavailableReason::Status(status) => write!(f, "unexpected status {}", status),
+            UnavailableReason::NotCached => write!(f, "not in the cache and --offline is set"),
+            UnavailableReason::DeadlineExceeded => write!(f, "not fetched before the --deadline"),
+        }
+    }
+}
+
+// returned when assetdelivery answers but refuses to hand over the asset,
+// callers can match on this to skip the asset instead of aborting
+#[derive(Debug, Clone)]
+pub struct AssetUnavailable {
+    pub asset_id: String,
+    pub reason: UnavailableReason,
+}
+
+impl fmt::Display for AssetUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "asset {} is {}", self.asset_id, self.reason)
+    }
+}
+
+impl Error for AssetUnavailable {}
+
+pub fn as_unavailable(err: &FixerError) -> Option<&AssetUnavailable> {
+    match err {
+        FixerError::Unavailable(unavailable) => Some(unavailable),
+        _ => None,
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    Instance, WeakDom,
+};
+use tracing::warn;
+
+use super::{
+    asset_provider::AssetProvider, content_url::normalize_content, digest_bytes,
+    unavailable::as_unavailable, FixerError,
+};
+
+#[derive(Debug, Clone)]
+pub struct UnionCluster {
+    pub canonical_id: String,
+    pub duplicate_ids: BTreeSet<String>,
+    pub instances: usize,
+    pub bytes_saved: usize,
+}
+
+fn get_asset_id(instance: &Instance) -> Option<String> {
+    match instance.properties.get("AssetId") {
+        Some(Variant::Content(content)) => {
+            let content = content.clone().into_string();
+            if content.trim() == "" {
+                None
+            } else {
+                Some(content)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn get_mesh_data(instance: &Instance) -> Option<&[u8]> {
+    match instance.properties.get("MeshData") {
+        Some(Variant::BinaryString(data)) if !data.as_ref().is_empty() => Some(data.as_ref()),
+        _ => None,
+    }
+}
+
+pub fn filter_unions(dom: &WeakDom, refs: &[Ref]) -> Vec<Ref> {
+    refs.iter()
+        .filter(|referent| {
+            dom.get_by_ref(**referent)
+                .map(|instance| instance.class == "UnionOperation")
+                .unwrap_or(false)
+        })
+        .cloned()
+        .collect()
+}
+
+pub async fn dedupe_unions(
+    provider: &dyn AssetProvider,
+    dom: &mut WeakDom,
+    unions: &[Ref],
+) -> Result<Vec<UnionCluster>, FixerError> {
+    let mut clusters = HashMap::<u128, UnionCluster>::new();
+    let mut asset_hashes = HashMap::<String, (u128, usize)>::new();
+
+    for referent in unions {
+        let instance = dom.get_by_ref(*referent).expect("union");
+        let asset_id = get_asset_id(instance);
+
+        // older unions carry their geometry inline in MeshData instead of
+        // pointing at an uploaded asset, those can only be reported
+        let (hash, size) = match &asset_id {
+            Some(asset_id) => match asset_hashes.get(asset_id) {
+                Some(hash) => *hash,
+                None => {
+                    let hash = match provider.content_hash(asset_id.clone()).await {
+                        Ok(hash) => hash,
+                        Err(err) if as_unavailable(&err).is_some() => {
+                            warn!("Skipping union {:?}: {}", asset_id, err);
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    };
+                    asset_hashes.insert(asset_id.clone(), hash);
+                    hash
+                }
+            },
+            None => match get_mesh_data(instance) {
+                Some(data) => (digest_bytes(data), data.len()),
+                None => continue,
+            },
+        };
+
+        let cluster = clusters.entry(hash).or_insert_with(|| UnionCluster {
+            canonical_id: String::new(),
+            duplicate_ids: BTreeSet::new(),
+            instances: 0,
+            bytes_saved: 0,
+        });
+        cluster.instances += 1;
+
+        let asset_id = match asset_id {
+            Some(asset_id) => asset_id,
+            None => continue,
+        };
+
+        if cluster.canonical_id.is_empty() {
+            cluster.canonical_id = asset_id;
+        } else if cluster.canonical_id != asset_id {
+            if cluster.duplicate_ids.insert(asset_id) {
+                cluster.bytes_saved += size;
+            }
+
+            let instance = dom.get_by_ref_mut(*referent).expect("union");
+            if let Some(prop) = instance.properties.get_mut("AssetId") {
+                *prop = Variant::Content(rbx_types::Content::from(normalize_content(
+                    &cluster.canonical_id,
+                )));
+            }
+        }
+    }
+
+    let mut duplicates = clusters
+        .into_iter()
+        .map(|(_, cluster)| cluster)
+        .filter(|cluster| cluster.instances > 1)
+        .collect::<Vec<UnionCluster>>();
+    duplicates.sort_by(|a, b| {
+        b.instances
+            .cmp(&a.instances)
+            .then_with(|| a.canonical_id.cmp(&b.canonical_id))
+    });
+
+    Ok(duplicates)
+}
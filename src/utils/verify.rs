@@ -0,0 +1,122 @@
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    WeakDom,
+};
+use std::collections::HashMap;
+
+use super::{
+    instance_path,
+    journal::{ChangeJournal, JournalValue},
+};
+
+// binary cframes may be re-encoded through a rotation id, so floats only
+// have to survive to within this much
+const FLOAT_TOLERANCE: f32 = 1e-4;
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub instances: usize,
+    pub checked_changes: usize,
+    pub problems: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+fn all_refs(dom: &WeakDom) -> Vec<Ref> {
+    let mut refs = Vec::new();
+    let mut stack = dom.root().children().to_vec();
+    while let Some(referent) = stack.pop() {
+        let instance = dom.get_by_ref(referent).expect("verify-instance");
+        stack.extend(instance.children().iter().copied());
+        refs.push(referent);
+    }
+    refs
+}
+
+fn values_match(a: &JournalValue, b: &JournalValue) -> bool {
+    let close = |a: &[f32], b: &[f32]| {
+        a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| (a - b).abs() <= FLOAT_TOLERANCE)
+    };
+
+    match (a, b) {
+        (JournalValue::Content(a), JournalValue::Content(b)) => a == b,
+        (JournalValue::Vector3(a), JournalValue::Vector3(b)) => close(a, b),
+        (JournalValue::CFrame(a), JournalValue::CFrame(b)) => close(a, b),
+        _ => false,
+    }
+}
+
+// compares the dom that was saved against what came back from disk: the
+// same number of instances, no dangling referents and every journaled
+// change still holding the value we wrote
+pub fn verify_round_trip(
+    written: &WeakDom,
+    reopened: &WeakDom,
+    journal: &ChangeJournal,
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let written_refs = all_refs(written);
+    let reopened_refs = all_refs(reopened);
+    report.instances = reopened_refs.len();
+
+    if written_refs.len() != reopened_refs.len() {
+        report.problems.push(format!(
+            "saved {} instances but {} came back",
+            written_refs.len(),
+            reopened_refs.len()
+        ));
+    }
+
+    let mut paths = HashMap::<String, Vec<Ref>>::new();
+    for referent in reopened_refs.iter() {
+        let instance = reopened.get_by_ref(*referent).expect("verify-instance");
+        for (name, value) in instance.properties.iter() {
+            if let Variant::Ref(target) = value {
+                if target.is_some() && reopened.get_by_ref(*target).is_none() {
+                    report.problems.push(format!(
+                        "{}.{} points at a missing instance",
+                        instance_path(reopened, *referent),
+                        name
+                    ));
+                }
+            }
+        }
+
+        paths
+            .entry(instance_path(reopened, *referent))
+            .or_insert_with(Vec::new)
+            .push(*referent);
+    }
+
+    for entry in journal.entries.iter() {
+        report.checked_changes += 1;
+        let found = paths
+            .get(&entry.path)
+            .map(|candidates| {
+                candidates.iter().any(|x| {
+                    reopened
+                        .get_by_ref(*x)
+                        .and_then(|x| x.properties.get(&entry.property))
+                        .and_then(JournalValue::from_variant)
+                        .map(|x| values_match(&x, &entry.new_value))
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false);
+
+        if !found {
+            report.problems.push(format!(
+                "{}.{} did not round-trip as {:?}",
+                entry.path, entry.property, entry.new_value
+            ));
+        }
+    }
+
+    report
+}
@@ -0,0 +1,101 @@
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use rbxlx_mesh_fixer::{utils::shutdown, MeshDeduplicator, Place};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::{error, info, info_span, Instrument};
+
+// outputs are written next to their inputs, so they have to be recognisable
+// or every save would trigger another run
+const OUTPUT_MARKER: &str = ".fixed";
+
+// studio writes places in several chunks, wait for it to go quiet before
+// opening anything
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+fn is_watched_file(path: &Path) -> bool {
+    let extension = path.extension().and_then(|x| x.to_str());
+    let stem = path.file_stem().and_then(|x| x.to_str()).unwrap_or("");
+    matches!(extension, Some("rbxl") | Some("rbxm")) && !stem.ends_with(OUTPUT_MARKER)
+}
+
+fn output_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or("place");
+    let extension = input.extension().and_then(|x| x.to_str()).unwrap_or("rbxl");
+    input.with_file_name(format!("{}{}.{}", stem, OUTPUT_MARKER, extension))
+}
+
+async fn process(deduplicator: &MeshDeduplicator, input: &Path) {
+    let output = output_path(input);
+    let result = async {
+        let mut place = Place::open(&input.to_string_lossy())?;
+        let result = deduplicator.run(&mut place).await?;
+        place.save(&output.to_string_lossy())?;
+        Ok::<_, rbxlx_mesh_fixer::FixerError>(result)
+    }
+    .instrument(info_span!("place", path = %input.display()))
+    .await;
+
+    match result {
+        Ok(result) => info!(
+            "{:?}: remapped {:?} meshes, saved to {:?}",
+            input,
+            result.mapping.len(),
+            output
+        ),
+        Err(err) => error!("{:?}: {}", input, err),
+    }
+}
+
+pub async fn run_watch(deduplicator: &MeshDeduplicator, dir: &str) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(err) => error!("Watch error: {}", err),
+        })
+        .expect("could not create file watcher");
+    watcher
+        .watch(Path::new(dir), RecursiveMode::NonRecursive)
+        .expect("could not watch directory");
+    info!("Watching {:?} for place files..", dir);
+
+    loop {
+        let event = tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        let mut pending = BTreeSet::<PathBuf>::new();
+        let mut next = Some(event);
+        // keep draining until nothing has changed for a while
+        while let Some(event) = next {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                pending.extend(event.paths.into_iter().filter(|x| is_watched_file(x)));
+            }
+            next = tokio::time::timeout(SETTLE_DELAY, rx.recv())
+                .await
+                .ok()
+                .flatten();
+        }
+
+        for input in pending {
+            if shutdown::interrupted() {
+                return;
+            }
+            if input.is_file() {
+                process(deduplicator, &input).await;
+            }
+        }
+    }
+}